@@ -1,28 +1,57 @@
 use crate::{
-    checks::page::{Method, Page},
+    checks::{
+        domain::Domain,
+        page::{HttpVersion, IpVersion, Method, Page, PageOptions, PageRequest},
+    },
     configuration::{
-        CHECK_CONNECTION_TIMEOUT, CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE, CHECK_MAX_CONNECTIONS,
-        CHECK_MAX_REDIRECTIONS, CHECK_TIMEOUT, DEFAULT_SLACK_NAME,
+        CHECK_CHARSET_SNIFF_LENGTH, CHECK_CONNECTION_TIMEOUT,
+        CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE, CHECK_MAX_CONNECTIONS,
+        CHECK_MAX_REDIRECTIONS, CHECK_TIMEOUT, CURLE_SSL_PINNEDPUBKEYNOTMATCH,
+        DEFAULT_SLACK_NAME, HISTORY_DIR,
     },
     products::{
-        expected::{DomainExpectation, Expected, PageExpectation, PageExpectations},
+        expected::{
+            DomainExpectation, Expected, JsonExpectation, PageExpectation,
+            PageExpectations,
+        },
         story::*,
         unexpected::{Unexpected, UnexpectedMinor},
     },
     *,
 };
+use chrono::Utc;
 use curl::{
-    easy::{Easy2, List},
+    easy::{Auth, Easy2, HttpVersion as CurlHttpVersion, IpResolve, List},
     multi::Multi,
     Error as CurlError,
 };
+use encoding_rs::{Encoding, UTF_8};
+use lazy_static::lazy_static;
 use rayon::prelude::*;
-use ssl_expiration2::SslExpiration;
+use regex::Regex;
+use serde_json::Value;
 use std::{
-    env,
+    cell::OnceCell,
+    env, fs,
     io::{Error, ErrorKind},
+    net::{IpAddr, ToSocketAddrs},
     time::Duration,
 };
+use url::{Host, Url};
+
+
+lazy_static! {
+    /// Charset parameter of Content-Type header
+    static ref CHARSET_REGEX: Regex =
+        Regex::new(r#"(?i)charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap();
+
+    /// Charset declared by <meta> tag
+    static ref META_CHARSET_REGEX: Regex =
+        Regex::new(r#"(?i)<meta[^>]*charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap();
+
+    /// HTTP response code class, like "2xx"
+    static ref CODE_CLASS_REGEX: Regex = Regex::new(r"^[1-5]xx$").unwrap();
+}
 
 
 /// Trait implementing all helper functions for Curl-driven checks
@@ -44,15 +73,10 @@ pub trait GenericChecker {
                         all_domains
                             .par_iter()
                             .flat_map(|domain| {
-                                domain
-                                    .expects
-                                    .par_iter()
-                                    .map(|expectation| {
-                                        Self::check_ssl_expire(
-                                            &domain.name,
-                                            *expectation,
-                                            notifier.clone(),
-                                        )
+                                Self::fan_out_domain(domain)
+                                    .into_par_iter()
+                                    .flat_map(|address| {
+                                        Self::check_domain(domain, address, notifier.clone())
                                     })
                                     .collect::<Stories>()
                             })
@@ -76,13 +100,39 @@ pub trait GenericChecker {
                     all_pages.sort();
                     all_pages.dedup();
                     let mut multi = Multi::new();
-                    multi.pipelining(false, true).unwrap_or_default(); // disable http1.1, enable http2-multiplex
+                    // enable http2-multiplex. HTTP version is set per page (http_version)
+                    multi.pipelining(false, true).unwrap_or_default();
 
                     // #[allow(clippy::needless_collect)] // Clippy BUG: not needless!
-                    // collect tuple of page-checks and Curl handler:
-                    let process_handlers: Vec<_> = all_pages
+                    // collect tuple of page-checks and Curl handler. Pages with unreadable
+                    // request body or unresolvable secrets are not performed at all:
+                    let fanned_out_pages: Vec<_> =
+                        all_pages.iter().flat_map(Self::fan_out_page).collect();
+                    let mut request_failures = Stories::new();
+                    let process_handlers: Vec<_> = fanned_out_pages
                         .iter()
-                        .map(|check| (check, Self::load_handler_for(check, &multi)))
+                        .filter_map(|(check, address)| {
+                            let url = Self::page_label(check, *address);
+                            match Self::prepare_request_for(check, &url) {
+                                Ok((request_body, auth, key_passphrase)) => {
+                                    Some((
+                                        (check, *address),
+                                        Self::load_handler_for(
+                                            check,
+                                            request_body,
+                                            auth,
+                                            key_passphrase,
+                                            &multi,
+                                        ),
+                                    ))
+                                }
+                                Err(failure) => {
+                                    request_failures
+                                        .push(Story::error(failure, notifier.clone()));
+                                    None
+                                }
+                            }
+                        })
                         .collect();
 
                     // perform all checks at once:
@@ -92,12 +142,34 @@ pub trait GenericChecker {
                             .unwrap_or_default();
                     }
 
+                    // collect transfer errors of each handler:
+                    let mut transfer_errors: Vec<Option<CurlError>> =
+                        process_handlers.iter().map(|_| None).collect();
+                    multi.messages(|message| {
+                        for (index, (_, handler)) in process_handlers.iter().enumerate() {
+                            if let Ok(handle) = handler {
+                                if let Some(Err(err)) = message.result_for2(handle) {
+                                    transfer_errors[index] = Some(err);
+                                }
+                            }
+                        }
+                    });
+
                     // Collect History of results:
                     process_handlers
                         .into_iter()
-                        .flat_map(|(page, handler)| {
-                            Self::process_page_handler(page, handler, &multi, notifier.clone())
+                        .zip(transfer_errors)
+                        .flat_map(|(((page, address), handler), transfer_error)| {
+                            Self::process_page_handler(
+                                page,
+                                address,
+                                handler,
+                                transfer_error,
+                                &multi,
+                                notifier.clone(),
+                            )
                         })
+                        .chain(request_failures)
                         .collect::<Stories>()
                 })
             })
@@ -105,54 +177,614 @@ pub trait GenericChecker {
     }
 
 
-    /// Check SSL certificate expiration using OpenSSL function
+    /// Expand page with fan_out_dns set, into one page per resolved address (of IP version
+    /// given in page options). Returns pairs of: page to check (with resolve override) and
+    /// the address. Addresses pinned by "resolve" option are used instead of DNS lookup
+    #[instrument]
+    fn fan_out_page(page: &Page) -> Vec<(Page, Option<IpAddr>)> {
+        let unchanged = vec![(page.clone(), None)];
+        if !page.fan_out_dns.unwrap_or(false) {
+            return unchanged;
+        }
+        let (host, port) = match Url::parse(&page.url) {
+            Ok(url) => {
+                match (url.host(), url.port_or_known_default()) {
+                    (Some(Host::Domain(host)), Some(port)) => (host.to_string(), port),
+                    (host, _) => {
+                        warn!("No host name to fan out in URL: {}. Host: {host:?}", page.url);
+                        return unchanged;
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("Couldn't parse URL: {}. Cause: {err}", page.url);
+                return unchanged;
+            }
+        };
+
+        let options = page.options.clone().unwrap_or_default();
+        let resolve_prefix = format!("{host}:{port}:");
+        let (pinned, other_overrides): (Vec<String>, Vec<String>) = options
+            .resolve
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|entry| entry.starts_with(&resolve_prefix));
+        let addresses: Vec<IpAddr> = if pinned.is_empty() {
+            (host.as_str(), port)
+                .to_socket_addrs()
+                .map(|addresses| addresses.map(|address| address.ip()).collect())
+                .unwrap_or_else(|err| {
+                    warn!("Couldn't resolve: {host} to fan out. Cause: {err}");
+                    vec![]
+                })
+        } else {
+            pinned
+                .iter()
+                .flat_map(|entry| entry[resolve_prefix.len()..].split(','))
+                .filter_map(|address| {
+                    let address = address.trim_matches(|c| c == '[' || c == ']');
+                    address
+                        .parse()
+                        .map_err(|err| {
+                            warn!("Skipping pinned address: {address}. Cause: {err}")
+                        })
+                        .ok()
+                })
+                .collect()
+        };
+        let mut addresses: Vec<IpAddr> = addresses
+            .into_iter()
+            .filter(|address| {
+                match options.ip_version {
+                    Some(IpVersion::V4) => address.is_ipv4(),
+                    Some(IpVersion::V6) => address.is_ipv6(),
+                    Some(IpVersion::Any) | None => true,
+                }
+            })
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+        if addresses.is_empty() {
+            return unchanged;
+        }
+
+        debug!("Fanning out: {} to addresses: {addresses:?}", page.url);
+        addresses
+            .into_iter()
+            .map(|address| {
+                let resolve_override = match address {
+                    IpAddr::V4(_) => format!("{resolve_prefix}{address}"),
+                    IpAddr::V6(_) => format!("{resolve_prefix}[{address}]"),
+                };
+                let resolve = [other_overrides.clone(), vec![resolve_override]].concat();
+                let checked_page = Page {
+                    options: Some(PageOptions {
+                        resolve: Some(resolve),
+                        ..options.clone()
+                    }),
+                    ..page.clone()
+                };
+                (checked_page, Some(address))
+            })
+            .collect()
+    }
+
+
+    /// URL of the page to report in stories. Fanned out pages are labelled with the address
+    fn page_label(page: &Page, address: Option<IpAddr>) -> String {
+        match address {
+            Some(address) => format!("{} ({address})", page.url),
+            None => page.url.to_string(),
+        }
+    }
+
+
+    /// Addresses to check for domain with fan_out_dns set. None means: connect by domain name
+    #[instrument]
+    fn fan_out_domain(domain: &Domain) -> Vec<Option<IpAddr>> {
+        if !domain.fan_out_dns.unwrap_or(false) {
+            return vec![None];
+        }
+        let mut addresses: Vec<IpAddr> = (domain.name.as_str(), domain.port())
+            .to_socket_addrs()
+            .map(|addresses| addresses.map(|address| address.ip()).collect())
+            .unwrap_or_else(|err| {
+                warn!("Couldn't resolve: {} to fan out. Cause: {err}", domain.name);
+                vec![]
+            });
+        addresses.sort();
+        addresses.dedup();
+        if addresses.is_empty() {
+            vec![None]
+        } else {
+            addresses.into_iter().map(Some).collect()
+        }
+    }
+
+
+    /// Check TLS certificate of the domain. Single TLS connection is made for all
+    /// expectations. With address given, the certificate is checked on that particular address.
+    /// Registration expectations don't need the TLS connection
+    #[instrument]
+    fn check_domain(
+        domain: &Domain,
+        address: Option<IpAddr>,
+        notifier: Option<String>,
+    ) -> Stories {
+        let domain_name = match address {
+            Some(address) => format!("{} ({address})", domain.label()),
+            None => domain.label(),
+        };
+        // TLS connection is made once, only when a TLS expectation is checked:
+        let peer = OnceCell::new();
+        let connect = || {
+            tls_peer(
+                &domain.name,
+                address,
+                domain.port(),
+                domain.starttls,
+                domain.ca_bundle.as_deref(),
+            )
+        };
+        domain
+            .expects
+            .iter()
+            .map(|expectation| {
+                match expectation {
+                    DomainExpectation::ValidRegistrationPeriod(expected_days) => {
+                        Self::handle_domain_registration_expectation(
+                            &domain_name,
+                            domain,
+                            *expected_days,
+                            notifier.clone(),
+                        )
+                    }
+
+                    _ => {
+                        match peer.get_or_init(connect) {
+                            Ok(peer) => {
+                                Self::handle_domain_expectation(
+                                    &domain_name,
+                                    domain,
+                                    address,
+                                    peer,
+                                    expectation,
+                                    notifier.clone(),
+                                )
+                            }
+
+                            Err(err) => {
+                                Story::minor(UnexpectedMinor::InternalProtocolProblem(
+                                    domain_name.to_string(),
+                                    err.to_string(),
+                                ))
+                            }
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
+
+    /// Build a Story from a ValidRegistrationPeriod DomainExpectation (RDAP, WHOIS)
     #[instrument]
-    fn check_ssl_expire(
+    fn handle_domain_registration_expectation(
+        domain_name: &str,
+        domain: &Domain,
+        expected_days: i32,
+        notifier: Option<String>,
+    ) -> Story {
+        match registration_expiry(
+            &domain.name,
+            domain.rdap_server.as_deref(),
+            domain.whois_server.as_deref(),
+        ) {
+            Ok(expiry) => {
+                let days = (expiry - Utc::now()).num_days() as i32;
+                if days < expected_days || days < 0 {
+                    Story::error(
+                        Unexpected::DomainRegistrationExpiring(domain_name.to_string(), days),
+                        notifier,
+                    )
+                } else {
+                    Story::success(
+                        Expected::DomainRegistrationFresh(
+                            domain_name.to_string(),
+                            days,
+                            expected_days,
+                        ),
+                        notifier,
+                    )
+                }
+            }
+            Err(err) => {
+                Story::error(
+                    Unexpected::DomainRegistrationLookupFailed(
+                        domain_name.to_string(),
+                        err.to_string(),
+                    ),
+                    notifier,
+                )
+            }
+        }
+    }
+
+
+    /// Build a Story from a DomainExpectation, using certificate of the TLS peer.
+    /// TLS version and cipher expectations probe the server with additional connections
+    #[instrument(skip(peer))]
+    fn handle_domain_expectation(
         domain_name: &str,
-        domain_expectation: DomainExpectation,
+        domain: &Domain,
+        address: Option<IpAddr>,
+        peer: &TlsPeer,
+        domain_expectation: &DomainExpectation,
         notifier: Option<String>,
     ) -> Story {
-        SslExpiration::from_domain_name_with_timeout(domain_name, CHECK_TIMEOUT)
-            .map(|ssl_validator| {
-                match domain_expectation {
-                    DomainExpectation::ValidExpiryPeriod(expected_days)
-                        if ssl_validator.days() < expected_days
-                            || ssl_validator.is_expired() =>
+        let internal_problem = |err: Error| {
+            Story::minor(UnexpectedMinor::InternalProtocolProblem(
+                domain_name.to_string(),
+                err.to_string(),
+            ))
+        };
+        match domain_expectation {
+            DomainExpectation::ValidExpiryPeriod(expected_days) => {
+                match peer.days_to_expiry() {
+                    Ok(days) if days < *expected_days || days < 0 => {
+                        Story::error(
+                            Unexpected::TLSDomainExpired(domain_name.to_string(), days),
+                            notifier,
+                        )
+                    }
+                    Ok(days) => {
+                        Story::success(
+                            Expected::TLSCertificateFresh(
+                                domain_name.to_string(),
+                                days,
+                                *expected_days,
+                            ),
+                            notifier,
+                        )
+                    }
+                    Err(err) => internal_problem(err),
+                }
+            }
+
+            DomainExpectation::ValidIssuer(expected_issuer) => {
+                let issuer = peer.issuer();
+                if issuer
+                    .to_lowercase()
+                    .contains(&expected_issuer.to_lowercase())
+                {
+                    Story::success(
+                        Expected::TLSCertificateIssuer(domain_name.to_string(), issuer),
+                        notifier,
+                    )
+                } else {
+                    Story::error(
+                        Unexpected::TLSIssuerMismatch(
+                            domain_name.to_string(),
+                            issuer,
+                            expected_issuer.to_string(),
+                        ),
+                        notifier,
+                    )
+                }
+            }
+
+            DomainExpectation::ValidSubjectAltName if peer.covers_name(&domain.name) => {
+                Story::success(
+                    Expected::TLSCertificateNameCovered(domain_name.to_string()),
+                    notifier,
+                )
+            }
+
+            DomainExpectation::ValidSubjectAltName => {
+                Story::error(
+                    Unexpected::TLSNameNotCovered(
+                        domain_name.to_string(),
+                        peer.subject_alt_names().join(", "),
+                    ),
+                    notifier,
+                )
+            }
+
+            DomainExpectation::ValidChain if peer.chain_verified() => {
+                Story::success(
+                    Expected::TLSCertificateChainComplete(
+                        domain_name.to_string(),
+                        peer.chain.len(),
+                    ),
+                    notifier,
+                )
+            }
+
+            DomainExpectation::ValidChain => {
+                Story::error(
+                    Unexpected::TLSChainIncomplete(
+                        domain_name.to_string(),
+                        peer.verify_result.error_string().to_string(),
+                    ),
+                    notifier,
+                )
+            }
+
+            DomainExpectation::ValidKeyAlgorithm(expected_algorithm) => {
+                match peer.public_key() {
+                    Ok((algorithm, bits))
+                        if algorithm.eq_ignore_ascii_case(expected_algorithm)
+                            || (algorithm == "EC"
+                                && expected_algorithm.eq_ignore_ascii_case("ECDSA")) =>
                     {
+                        Story::success(
+                            Expected::TLSCertificateKey(
+                                domain_name.to_string(),
+                                algorithm,
+                                bits,
+                            ),
+                            notifier,
+                        )
+                    }
+                    Ok((algorithm, _)) => {
                         Story::error(
-                            Unexpected::TLSDomainExpired(
+                            Unexpected::TLSKeyAlgorithmMismatch(
                                 domain_name.to_string(),
-                                ssl_validator.days(),
+                                algorithm,
+                                expected_algorithm.to_string(),
                             ),
                             notifier,
                         )
                     }
+                    Err(err) => internal_problem(err),
+                }
+            }
 
-                    DomainExpectation::ValidExpiryPeriod(expected_days) => {
+            DomainExpectation::ValidMinimumKeySize(minimum_bits) => {
+                match peer.public_key() {
+                    Ok((algorithm, bits)) if bits >= *minimum_bits => {
                         Story::success(
-                            Expected::TLSCertificateFresh(
+                            Expected::TLSCertificateKey(
+                                domain_name.to_string(),
+                                algorithm,
+                                bits,
+                            ),
+                            notifier,
+                        )
+                    }
+                    Ok((_, bits)) => {
+                        Story::error(
+                            Unexpected::TLSKeyTooSmall(
                                 domain_name.to_string(),
-                                ssl_validator.days(),
-                                expected_days,
+                                bits,
+                                *minimum_bits,
                             ),
                             notifier,
                         )
                     }
+                    Err(err) => internal_problem(err),
                 }
-            })
-            .unwrap_or_else(|err| {
-                Story::minor(UnexpectedMinor::InternalProtocolProblem(
-                    domain_name.to_string(),
-                    err.to_string(),
-                ))
-            })
+            }
+
+            DomainExpectation::ValidSignatureAlgorithm(expected_algorithm) => {
+                let algorithm = peer.signature_algorithm();
+                if algorithm
+                    .to_lowercase()
+                    .contains(&expected_algorithm.to_lowercase())
+                {
+                    Story::success(
+                        Expected::TLSCertificateSignature(domain_name.to_string(), algorithm),
+                        notifier,
+                    )
+                } else {
+                    Story::error(
+                        Unexpected::TLSSignatureAlgorithmMismatch(
+                            domain_name.to_string(),
+                            algorithm,
+                            expected_algorithm.to_string(),
+                        ),
+                        notifier,
+                    )
+                }
+            }
+
+            DomainExpectation::ForbidTlsVersion(version) => {
+                match tls_version_accepted(
+                    &domain.name,
+                    address,
+                    domain.port(),
+                    domain.starttls,
+                    *version,
+                ) {
+                    Ok(false) => {
+                        Story::success(
+                            Expected::TLSVersionRefused(
+                                domain_name.to_string(),
+                                version.to_string(),
+                            ),
+                            notifier,
+                        )
+                    }
+                    Ok(true) => {
+                        Story::error(
+                            Unexpected::TLSVersionForbidden(
+                                domain_name.to_string(),
+                                version.to_string(),
+                            ),
+                            notifier,
+                        )
+                    }
+                    Err(err) => internal_problem(err),
+                }
+            }
+
+            DomainExpectation::RequireTlsVersion(version) => {
+                match tls_version_accepted(
+                    &domain.name,
+                    address,
+                    domain.port(),
+                    domain.starttls,
+                    *version,
+                ) {
+                    Ok(true) => {
+                        Story::success(
+                            Expected::TLSVersionAccepted(
+                                domain_name.to_string(),
+                                version.to_string(),
+                            ),
+                            notifier,
+                        )
+                    }
+                    Ok(false) => {
+                        Story::error(
+                            Unexpected::TLSVersionMissing(
+                                domain_name.to_string(),
+                                version.to_string(),
+                            ),
+                            notifier,
+                        )
+                    }
+                    Err(err) => internal_problem(err),
+                }
+            }
+
+            DomainExpectation::ForbidWeakCiphers => {
+                match weak_ciphers_accepted(
+                    &domain.name,
+                    address,
+                    domain.port(),
+                    domain.starttls,
+                ) {
+                    Ok(ciphers) if ciphers.is_empty() => {
+                        Story::success(
+                            Expected::TLSWeakCiphersRefused(domain_name.to_string()),
+                            notifier,
+                        )
+                    }
+                    Ok(ciphers) => {
+                        Story::error(
+                            Unexpected::TLSWeakCiphersAccepted(
+                                domain_name.to_string(),
+                                ciphers.join(", "),
+                            ),
+                            notifier,
+                        )
+                    }
+                    Err(err) => internal_problem(err),
+                }
+            }
+
+            DomainExpectation::NotRevoked => {
+                let revocation = peer
+                    .issuer_certificate(domain.ca_bundle.as_deref())
+                    .and_then(|issuer| {
+                        let responder = domain
+                            .ocsp_responder
+                            .clone()
+                            .or_else(|| peer.ocsp_responder())
+                            .ok_or_else(|| {
+                                Error::new(ErrorKind::NotFound, "No OCSP responder defined")
+                            })?;
+                        let response = ocsp_query(&responder, &peer.certificate, &issuer)?;
+                        peer.revocation_status(&response, &issuer)
+                            .map(|status| (responder, status))
+                    });
+                match revocation {
+                    Ok((responder, RevocationStatus::Good)) => {
+                        Story::success(
+                            Expected::TLSCertificateNotRevoked(
+                                domain_name.to_string(),
+                                responder,
+                            ),
+                            notifier,
+                        )
+                    }
+                    Ok((_, RevocationStatus::Revoked(revocation_time))) => {
+                        Story::error(
+                            Unexpected::TLSCertificateRevoked(
+                                domain_name.to_string(),
+                                revocation_time,
+                            ),
+                            notifier,
+                        )
+                    }
+                    Ok((responder, RevocationStatus::Unknown)) => {
+                        internal_problem(Error::new(
+                            ErrorKind::NotFound,
+                            format!("Certificate is unknown to OCSP responder: {responder}"),
+                        ))
+                    }
+                    Err(err) => internal_problem(err),
+                }
+            }
+
+            DomainExpectation::RequireOcspStapling => {
+                let stapled = peer
+                    .stapled_ocsp
+                    .as_ref()
+                    .ok_or_else(|| Error::new(ErrorKind::NotFound, "No OCSP response stapled"))
+                    .and_then(|response| {
+                        let issuer = peer.issuer_certificate(domain.ca_bundle.as_deref())?;
+                        peer.revocation_status(response, &issuer)
+                    });
+                match stapled {
+                    Ok(RevocationStatus::Good) => {
+                        Story::success(
+                            Expected::TLSOcspStapled(domain_name.to_string()),
+                            notifier,
+                        )
+                    }
+                    Ok(RevocationStatus::Revoked(revocation_time)) => {
+                        Story::error(
+                            Unexpected::TLSCertificateRevoked(
+                                domain_name.to_string(),
+                                revocation_time,
+                            ),
+                            notifier,
+                        )
+                    }
+                    Ok(RevocationStatus::Unknown) => {
+                        Story::error(
+                            Unexpected::TLSOcspStaplingMissing(
+                                domain_name.to_string(),
+                                "Stapled OCSP response has unknown certificate status"
+                                    .to_string(),
+                            ),
+                            notifier,
+                        )
+                    }
+                    Err(err) => {
+                        Story::error(
+                            Unexpected::TLSOcspStaplingMissing(
+                                domain_name.to_string(),
+                                err.to_string(),
+                            ),
+                            notifier,
+                        )
+                    }
+                }
+            }
+
+            // registration expectations are checked without the TLS peer, by check_domain:
+            edge_case => {
+                Story::error(
+                    Unexpected::UnmatchedValidationCase(
+                        domain_name.to_string(),
+                        edge_case.to_string(),
+                    ),
+                    notifier,
+                )
+            }
+        }
     }
 
-    /// Build a Story from a Length PageExpectation
-    #[instrument]
+
+    /// Build a Story from a Length PageExpectation, measured on raw body bytes
+    #[instrument(skip(raw_page_content))]
     fn handle_page_length_expectation(
         url: &str,
-        raw_page_content: &str,
+        raw_page_content: &[u8],
         expected_content_length: &PageExpectation,
         notifier: Option<String>,
     ) -> Story {
@@ -177,9 +809,68 @@ pub trait GenericChecker {
                 )
             }
 
-            &PageExpectation::ValidNoLength => {
-                Story::success(Expected::NoContentLength(url.to_string()), notifier)
-            }
+            PageExpectation::ValidMaxLength(max_length)
+                if raw_page_content.len() <= *max_length =>
+            {
+                Story::success(
+                    Expected::ContentLengthMax(url.to_string(), *max_length),
+                    notifier,
+                )
+            }
+
+            PageExpectation::ValidMaxLength(max_length) => {
+                Story::error(
+                    Unexpected::ContentLengthTooLong(
+                        url.to_string(),
+                        raw_page_content.len(),
+                        *max_length,
+                    ),
+                    notifier,
+                )
+            }
+
+            PageExpectation::ValidLengthRange(min_length, max_length)
+                if (*min_length..=*max_length).contains(&raw_page_content.len()) =>
+            {
+                Story::success(
+                    Expected::ContentLengthRange(url.to_string(), *min_length, *max_length),
+                    notifier,
+                )
+            }
+
+            PageExpectation::ValidLengthRange(min_length, max_length) => {
+                Story::error(
+                    Unexpected::ContentLengthOutOfRange(
+                        url.to_string(),
+                        raw_page_content.len(),
+                        *min_length,
+                        *max_length,
+                    ),
+                    notifier,
+                )
+            }
+
+            PageExpectation::ValidExactLength(length) if raw_page_content.len() == *length => {
+                Story::success(
+                    Expected::ContentLengthExact(url.to_string(), *length),
+                    notifier,
+                )
+            }
+
+            PageExpectation::ValidExactLength(length) => {
+                Story::error(
+                    Unexpected::ContentLengthNotExact(
+                        url.to_string(),
+                        raw_page_content.len(),
+                        *length,
+                    ),
+                    notifier,
+                )
+            }
+
+            &PageExpectation::ValidNoLength => {
+                Story::success(Expected::NoContentLength(url.to_string()), notifier)
+            }
 
             edge_case => {
                 Story::error(
@@ -199,7 +890,15 @@ pub trait GenericChecker {
     fn find_code_validation(page_expectations: &[PageExpectation]) -> &PageExpectation {
         page_expectations
             .par_iter()
-            .find_any(|exp| matches!(exp, PageExpectation::ValidCode(_)))
+            .find_any(|exp| {
+                matches!(
+                    exp,
+                    PageExpectation::ValidCode(_)
+                        | PageExpectation::ValidCodeRange(..)
+                        | PageExpectation::ValidCodeIn(_)
+                        | PageExpectation::ValidCodeClass(_)
+                )
+            })
             .unwrap_or(&PageExpectation::ValidCode(
                 CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE,
             ))
@@ -211,21 +910,44 @@ pub trait GenericChecker {
     fn find_content_validations(page_expectations: &[PageExpectation]) -> PageExpectations {
         page_expectations
             .par_iter()
-            .filter(|exp| matches!(exp, PageExpectation::ValidContent(_)))
+            .filter(|exp| {
+                matches!(
+                    exp,
+                    PageExpectation::ValidContent(_)
+                        | PageExpectation::ValidContentRegex(_)
+                        | PageExpectation::ValidContentCaseInsensitive(_)
+                        | PageExpectation::ValidContentOccurrences(..)
+                        | PageExpectation::InvalidContent(_)
+                )
+            })
             .cloned()
             .collect()
     }
 
 
-    /// Find and extract content length validation from validations
+    /// Find and extract content length validations from validations
     #[instrument]
-    fn find_content_length_validation(
+    fn find_content_length_validations(
         page_expectations: &[PageExpectation],
-    ) -> &PageExpectation {
-        page_expectations
+    ) -> PageExpectations {
+        let length_validations: PageExpectations = page_expectations
             .par_iter()
-            .find_any(|exp| matches!(exp, PageExpectation::ValidLength(_)))
-            .unwrap_or(&PageExpectation::ValidNoLength)
+            .filter(|exp| {
+                matches!(
+                    exp,
+                    PageExpectation::ValidLength(_)
+                        | PageExpectation::ValidMaxLength(_)
+                        | PageExpectation::ValidLengthRange(..)
+                        | PageExpectation::ValidExactLength(_)
+                )
+            })
+            .cloned()
+            .collect();
+        if length_validations.is_empty() {
+            vec![PageExpectation::ValidNoLength]
+        } else {
+            length_validations
+        }
     }
 
 
@@ -239,6 +961,117 @@ pub trait GenericChecker {
     }
 
 
+    /// Find and extract header validations
+    #[instrument]
+    fn find_header_validations(page_expectations: &[PageExpectation]) -> PageExpectations {
+        page_expectations
+            .par_iter()
+            .filter(|exp| {
+                matches!(
+                    exp,
+                    PageExpectation::ValidHeader(..) | PageExpectation::InvalidHeader(_)
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+
+    /// Find and extract redirect validations
+    #[instrument]
+    fn find_redirect_validations(page_expectations: &[PageExpectation]) -> PageExpectations {
+        page_expectations
+            .par_iter()
+            .filter(|exp| {
+                matches!(
+                    exp,
+                    PageExpectation::ValidRedirectCount(_)
+                        | PageExpectation::ValidRedirectChain(_)
+                        | PageExpectation::RequireHttpsRedirect
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+
+    /// Find and extract HTTP version validations
+    #[instrument]
+    fn find_http_version_validations(
+        page_expectations: &[PageExpectation],
+    ) -> PageExpectations {
+        page_expectations
+            .par_iter()
+            .filter(|exp| matches!(exp, PageExpectation::ValidHttpVersion(_)))
+            .cloned()
+            .collect()
+    }
+
+
+    /// Find and extract JSON validations
+    #[instrument]
+    fn find_json_validations(page_expectations: &[PageExpectation]) -> PageExpectations {
+        page_expectations
+            .par_iter()
+            .filter(|exp| matches!(exp, PageExpectation::ValidJson(_)))
+            .cloned()
+            .collect()
+    }
+
+
+    /// Find and extract checksum and content change validations
+    #[instrument]
+    fn find_digest_validations(page_expectations: &[PageExpectation]) -> PageExpectations {
+        page_expectations
+            .par_iter()
+            .filter(|exp| {
+                matches!(
+                    exp,
+                    PageExpectation::ValidChecksum(_) | PageExpectation::DetectChange(_)
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+
+    /// Find and extract response time validations
+    #[instrument]
+    fn find_response_time_validations(
+        page_expectations: &[PageExpectation],
+    ) -> PageExpectations {
+        page_expectations
+            .par_iter()
+            .filter(|exp| matches!(exp, PageExpectation::ValidResponseTime(_)))
+            .cloned()
+            .collect()
+    }
+
+
+    /// Converts TLS related CurlError to specific Unexpected
+    #[instrument]
+    fn tls_failure(url: &str, err: &CurlError) -> Option<Unexpected> {
+        let details = err
+            .extra_description()
+            .unwrap_or_else(|| err.description())
+            .to_string();
+        let url = url.to_string();
+        if err.is_ssl_certproblem() {
+            Some(Unexpected::TLSClientCertificateInvalid(url, details))
+        } else if err.is_ssl_cacert_badfile() {
+            Some(Unexpected::TLSCaBundleInvalid(url, details))
+        } else if err.is_peer_failed_verification() || err.is_ssl_cacert() {
+            Some(Unexpected::TLSPeerVerificationFailed(url, details))
+        } else if err.code() == CURLE_SSL_PINNEDPUBKEYNOTMATCH {
+            Some(Unexpected::TLSPinnedKeyMismatch(url, details))
+        } else if err.is_ssl_connect_error() {
+            Some(Unexpected::TLSHandshakeFailed(url, details))
+        } else {
+            None
+        }
+    }
+
+
     /// Converts CurlError to Error
     #[instrument]
     fn produce_curl_response_error(err: CurlError) -> Error {
@@ -328,57 +1161,906 @@ pub trait GenericChecker {
             reason = "CURLE_HTTP2"
         } // Returns whether this error corresponds to CURLE_HTTP2.
 
-        Error::new(ErrorKind::Other, format!("{} ({})", err, reason))
+        Error::other(format!("{} ({})", err, reason))
+    }
+
+
+    /// Build a Story from a Content PageExpectation
+    #[instrument]
+    fn handle_page_content_expectations(
+        url: &str,
+        raw_page_content: &str,
+        expected_contents: &[PageExpectation],
+        notifier: Option<String>,
+    ) -> Stories {
+        expected_contents
+            .par_iter()
+            .map(|expectation| {
+                match expectation {
+                    PageExpectation::ValidContent(ref content)
+                        if raw_page_content.contains(content) =>
+                    {
+                        Story::success(
+                            Expected::Content(url.to_string(), content.to_string()),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidContent(ref content) => {
+                        Story::error(
+                            Unexpected::ContentInvalid(url.to_string(), content.to_string()),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidContentRegex(ref pattern) => {
+                        match Regex::new(pattern) {
+                            Ok(regex) => {
+                                match regex.find(raw_page_content) {
+                                    Some(found) => {
+                                        Story::success(
+                                            Expected::ContentRegex(
+                                                url.to_string(),
+                                                pattern.to_string(),
+                                                found.as_str().to_string(),
+                                            ),
+                                            notifier.clone(),
+                                        )
+                                    }
+                                    None => {
+                                        Story::error(
+                                            Unexpected::ContentRegexInvalid(
+                                                url.to_string(),
+                                                pattern.to_string(),
+                                            ),
+                                            notifier.clone(),
+                                        )
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                Story::error(
+                                    Unexpected::RegexMalformed(
+                                        url.to_string(),
+                                        pattern.to_string(),
+                                        err.to_string(),
+                                    ),
+                                    notifier.clone(),
+                                )
+                            }
+                        }
+                    }
+
+                    PageExpectation::ValidContentCaseInsensitive(ref content)
+                        if raw_page_content
+                            .to_lowercase()
+                            .contains(&content.to_lowercase()) =>
+                    {
+                        Story::success(
+                            Expected::ContentCaseInsensitive(
+                                url.to_string(),
+                                content.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidContentCaseInsensitive(ref content) => {
+                        Story::error(
+                            Unexpected::ContentCaseInsensitiveInvalid(
+                                url.to_string(),
+                                content.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidContentOccurrences(ref content, expected_count) => {
+                        let occurrences = if content.is_empty() {
+                            0
+                        } else {
+                            raw_page_content.matches(content.as_str()).count()
+                        };
+                        if occurrences >= *expected_count {
+                            Story::success(
+                                Expected::ContentOccurrences(
+                                    url.to_string(),
+                                    content.to_string(),
+                                    occurrences,
+                                ),
+                                notifier.clone(),
+                            )
+                        } else {
+                            Story::error(
+                                Unexpected::ContentOccurrencesInvalid(
+                                    url.to_string(),
+                                    content.to_string(),
+                                    occurrences,
+                                    *expected_count,
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+                    }
+
+                    PageExpectation::InvalidContent(ref content)
+                        if raw_page_content.contains(content) =>
+                    {
+                        Story::error(
+                            Unexpected::ContentForbidden(url.to_string(), content.to_string()),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::InvalidContent(ref content) => {
+                        Story::success(
+                            Expected::ContentAbsent(url.to_string(), content.to_string()),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidNoContent => {
+                        Story::success(
+                            Expected::EmptyContent(url.to_string()),
+                            notifier.clone(),
+                        )
+                    }
+
+                    edge_case => {
+                        Story::error(
+                            Unexpected::UnmatchedValidationCase(
+                                url.to_string(),
+                                format!("{:?}", edge_case),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+                }
+            })
+            .collect::<Stories>()
+    }
+
+
+    /// Build Stories from Checksum and DetectChange PageExpectations
+    #[instrument(skip(raw_page_bytes, raw_page_content))]
+    fn handle_page_digest_expectations(
+        url: &str,
+        raw_page_bytes: &[u8],
+        raw_page_content: &str,
+        expected_digests: &[PageExpectation],
+        notifier: Option<String>,
+    ) -> Stories {
+        expected_digests
+            .iter()
+            .map(|expectation| {
+                match expectation {
+                    PageExpectation::ValidChecksum(checksum) => {
+                        let digest = sha256_hex(raw_page_bytes);
+                        if digest.eq_ignore_ascii_case(checksum.trim()) {
+                            Story::success(
+                                Expected::Checksum(url.to_string(), digest),
+                                notifier.clone(),
+                            )
+                        } else {
+                            Story::error(
+                                Unexpected::ChecksumInvalid(
+                                    url.to_string(),
+                                    digest,
+                                    checksum.to_string(),
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+                    }
+
+                    PageExpectation::DetectChange(strip_patterns) => {
+                        let mut normalized = raw_page_content.to_string();
+                        for pattern in strip_patterns {
+                            match Regex::new(pattern) {
+                                Ok(regex) => {
+                                    normalized = regex.replace_all(&normalized, "").to_string()
+                                }
+                                Err(err) => {
+                                    return Story::error(
+                                        Unexpected::RegexMalformed(
+                                            url.to_string(),
+                                            pattern.to_string(),
+                                            err.to_string(),
+                                        ),
+                                        notifier.clone(),
+                                    );
+                                }
+                            }
+                        }
+                        let digest = sha256_hex(normalized.as_bytes());
+                        let digest_file = format!(
+                            "{HISTORY_DIR}/krecik-digest-{}.sha256",
+                            sha256_hex(format!("{url}{expectation}").as_bytes())
+                        );
+                        let store_digest = || {
+                            debug!("Storing content digest of: {url} in: {digest_file}");
+                            fs::write(&digest_file, &digest).map_err(|err| {
+                                error!(
+                                    "Couldn't write digest file: {digest_file}. Error: {err}"
+                                );
+                                err
+                            })
+                        };
+                        // Changed digest replaces the stored one - change is reported once:
+                        match read_text_file(&digest_file) {
+                            Ok(stored_digest) if stored_digest.trim() != digest => {
+                                store_digest().unwrap_or_default();
+                                Story::error(
+                                    Unexpected::ContentChanged(
+                                        url.to_string(),
+                                        stored_digest.trim().to_string(),
+                                        digest,
+                                    ),
+                                    notifier.clone(),
+                                )
+                            }
+                            Ok(_) => {
+                                Story::success(
+                                    Expected::ContentUnchanged(url.to_string(), digest),
+                                    notifier.clone(),
+                                )
+                            }
+                            Err(_) => {
+                                if let Err(err) = store_digest() {
+                                    return Story::error(
+                                        Unexpected::DigestUnwritable(
+                                            url.to_string(),
+                                            digest_file.to_string(),
+                                            err.to_string(),
+                                        ),
+                                        notifier.clone(),
+                                    );
+                                }
+                                Story::success(
+                                    Expected::ContentUnchanged(url.to_string(), digest),
+                                    notifier.clone(),
+                                )
+                            }
+                        }
+                    }
+
+                    edge_case => {
+                        Story::error(
+                            Unexpected::UnmatchedValidationCase(
+                                url.to_string(),
+                                edge_case.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+                }
+            })
+            .collect()
+    }
+
+
+    /// Find values of response header with given name (case-insensitive)
+    #[instrument]
+    fn header_values(headers: &[String], name: &str) -> Vec<String> {
+        headers
+            .iter()
+            .filter_map(|header| {
+                header.split_once(':').and_then(|(header_name, value)| {
+                    if header_name.trim().eq_ignore_ascii_case(name.trim()) {
+                        Some(value.trim().to_string())
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect()
+    }
+
+
+    /// Build redirect chain from status lines and Location headers of all responses.
+    /// Only redirects and the final response are hops (1xx and proxy CONNECT responses aren't)
+    #[instrument]
+    fn page_redirect_chain(
+        url: &str,
+        responses: &[(String, Option<String>)],
+    ) -> Vec<RedirectHop> {
+        let mut current_url = url.to_string();
+        let mut chain = vec![];
+        for (index, (status_line, location)) in responses.iter().enumerate() {
+            let code = status_line
+                .split_whitespace()
+                .nth(1)
+                .and_then(|code| code.parse::<u32>().ok())
+                .unwrap_or_default();
+            let last_response = index + 1 == responses.len();
+            match location {
+                Some(location) if (300..400).contains(&code) => {
+                    chain.push(RedirectHop {
+                        url: current_url.to_string(),
+                        code,
+                    });
+                    current_url = Url::parse(&current_url)
+                        .and_then(|base| base.join(location))
+                        .map(|next_url| next_url.to_string())
+                        .unwrap_or_else(|_| location.to_string());
+                }
+                _ if last_response => {
+                    chain.push(RedirectHop {
+                        url: current_url.to_string(),
+                        code,
+                    });
+                }
+                _ => (),
+            }
+        }
+        chain
+    }
+
+
+    /// Build Stories from Redirect PageExpectations
+    #[instrument]
+    fn handle_page_redirect_expectations(
+        url: &str,
+        redirect_chain: &[RedirectHop],
+        expected_redirects: &[PageExpectation],
+        notifier: Option<String>,
+    ) -> Stories {
+        let redirects = redirect_chain.len().saturating_sub(1);
+        let chain_description = redirect_chain_description(redirect_chain);
+        expected_redirects
+            .iter()
+            .map(|expectation| {
+                match expectation {
+                    PageExpectation::ValidRedirectCount(max) if redirects <= *max => {
+                        Story::success(
+                            Expected::RedirectCount(url.to_string(), redirects, *max),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidRedirectCount(max) => {
+                        Story::error(
+                            Unexpected::RedirectCountExceeded(
+                                url.to_string(),
+                                redirects,
+                                *max,
+                                chain_description.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidRedirectChain(expected_chain)
+                        if expected_chain == redirect_chain =>
+                    {
+                        Story::success(
+                            Expected::RedirectChain(
+                                url.to_string(),
+                                chain_description.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidRedirectChain(expected_chain) => {
+                        Story::error(
+                            Unexpected::RedirectChainMismatch(
+                                url.to_string(),
+                                chain_description.to_string(),
+                                redirect_chain_description(expected_chain),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::RequireHttpsRedirect => {
+                        match Self::https_redirect_problem(redirect_chain) {
+                            Some(problem) => {
+                                Story::error(
+                                    Unexpected::HttpsRedirectMissing(
+                                        url.to_string(),
+                                        format!("{problem}. Chain: {chain_description}"),
+                                    ),
+                                    notifier.clone(),
+                                )
+                            }
+                            None => {
+                                Story::success(
+                                    Expected::HttpsRedirect(
+                                        url.to_string(),
+                                        chain_description.to_string(),
+                                    ),
+                                    notifier.clone(),
+                                )
+                            }
+                        }
+                    }
+
+                    edge_case => {
+                        Story::error(
+                            Unexpected::UnmatchedValidationCase(
+                                url.to_string(),
+                                edge_case.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+                }
+            })
+            .collect()
+    }
+
+
+    /// Describe why redirect chain isn't a permanent plain HTTP to HTTPS redirect
+    #[instrument]
+    fn https_redirect_problem(redirect_chain: &[RedirectHop]) -> Option<String> {
+        let is_plain = |url: &str| url.to_lowercase().starts_with("http://");
+        match redirect_chain {
+            [first, ..] if !is_plain(&first.url) => {
+                Some(String::from("Checked URL isn't a plain HTTP URL"))
+            }
+            [] | [_] => Some(String::from("No redirect")),
+            [first, second, ..] if is_plain(&second.url) || first.code != 301 => {
+                Some(format!(
+                    "Redirected with code: {} to: {}",
+                    first.code, second.url
+                ))
+            }
+            [_, rest @ ..] => {
+                rest.iter()
+                    .find(|hop| is_plain(&hop.url))
+                    .map(|hop| format!("Redirected back to plain HTTP: {}", hop.url))
+            }
+        }
+    }
+
+
+    /// HTTP version negotiated by Curl, read from the status line of the final response
+    #[instrument]
+    fn negotiated_http_version(headers: &[String]) -> Option<String> {
+        headers
+            .iter()
+            .find(|header| header.starts_with("HTTP/"))
+            .and_then(|status_line| status_line.split_whitespace().next())
+            .map(|protocol| protocol.trim_start_matches("HTTP/").to_string())
+    }
+
+
+    /// Build Stories from HttpVersion PageExpectations
+    #[instrument]
+    fn handle_page_http_version_expectations(
+        url: &str,
+        headers: &[String],
+        expected_http_versions: &[PageExpectation],
+        notifier: Option<String>,
+    ) -> Stories {
+        // "2.0" and "2" are the same version
+        let normalized = |version: &str| {
+            match version.trim() {
+                "2.0" => String::from("2"),
+                "3.0" => String::from("3"),
+                version => version.to_string(),
+            }
+        };
+        let negotiated = Self::negotiated_http_version(headers).unwrap_or_default();
+        expected_http_versions
+            .iter()
+            .map(|expectation| {
+                match expectation {
+                    PageExpectation::ValidHttpVersion(expected)
+                        if normalized(expected) == normalized(&negotiated) =>
+                    {
+                        Story::success(
+                            Expected::HttpVersion(url.to_string(), negotiated.to_string()),
+                            notifier.clone(),
+                        )
+                    }
+
+                    PageExpectation::ValidHttpVersion(expected) => {
+                        Story::error(
+                            Unexpected::HttpVersionMismatch(
+                                url.to_string(),
+                                negotiated.to_string(),
+                                expected.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    edge_case => {
+                        Story::error(
+                            Unexpected::UnmatchedValidationCase(
+                                url.to_string(),
+                                edge_case.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+                }
+            })
+            .collect()
+    }
+
+
+    /// Decode page content using charset from Content-Type header or from
+    /// <meta charset> tag. Fallbacks to UTF-8 with invalid sequences replaced
+    #[instrument(skip(raw_page_bytes))]
+    fn decode_page_content(raw_page_bytes: &[u8], headers: &[String]) -> String {
+        let header_charset = Self::header_values(headers, "Content-Type")
+            .last()
+            .and_then(|content_type| {
+                CHARSET_REGEX
+                    .captures(content_type)
+                    .map(|captures| captures[1].to_string())
+            });
+        let charset = header_charset.or_else(|| {
+            let head = String::from_utf8_lossy(
+                &raw_page_bytes[..raw_page_bytes.len().min(CHECK_CHARSET_SNIFF_LENGTH)],
+            );
+            META_CHARSET_REGEX
+                .captures(&head)
+                .map(|captures| captures[1].to_string())
+        });
+        let encoding = charset
+            .and_then(|label| Encoding::for_label(label.as_bytes()))
+            .unwrap_or(UTF_8);
+        let (content, used_encoding, had_errors) = encoding.decode(raw_page_bytes);
+        if had_errors {
+            debug!(
+                "Content decoded as: {} contains malformed sequences",
+                used_encoding.name()
+            );
+        }
+        content.to_string()
     }
 
 
-    /// Build a Story from a Length PageExpectation
+    /// Build Stories from Header PageExpectations
     #[instrument]
-    fn handle_page_content_expectations(
+    fn handle_page_header_expectations(
         url: &str,
-        raw_page_content: &str,
-        expected_contents: &[PageExpectation],
+        headers: &[String],
+        expected_headers: &[PageExpectation],
         notifier: Option<String>,
     ) -> Stories {
-        expected_contents
+        expected_headers
             .par_iter()
             .map(|expectation| {
                 match expectation {
-                    PageExpectation::ValidContent(ref content)
-                        if raw_page_content.contains(content) =>
-                    {
-                        Story::success(
-                            Expected::Content(url.to_string(), content.to_string()),
-                            notifier.clone(),
-                        )
+                    PageExpectation::ValidHeader(name, pattern) => {
+                        let values = Self::header_values(headers, name);
+                        let regex = match Regex::new(pattern) {
+                            Ok(regex) => regex,
+                            Err(err) => {
+                                return Story::error(
+                                    Unexpected::RegexMalformed(
+                                        url.to_string(),
+                                        pattern.to_string(),
+                                        err.to_string(),
+                                    ),
+                                    notifier.clone(),
+                                );
+                            }
+                        };
+                        if values.is_empty() {
+                            Story::error(
+                                Unexpected::HeaderMissing(url.to_string(), name.to_string()),
+                                notifier.clone(),
+                            )
+                        } else {
+                            match values.iter().find(|value| regex.is_match(value)) {
+                                Some(value) => {
+                                    Story::success(
+                                        Expected::Header(
+                                            url.to_string(),
+                                            name.to_string(),
+                                            value.to_string(),
+                                        ),
+                                        notifier.clone(),
+                                    )
+                                }
+                                None => {
+                                    Story::error(
+                                        Unexpected::HeaderInvalid(
+                                            url.to_string(),
+                                            name.to_string(),
+                                            values.join(", "),
+                                            pattern.to_string(),
+                                        ),
+                                        notifier.clone(),
+                                    )
+                                }
+                            }
+                        }
                     }
 
-                    PageExpectation::ValidContent(ref content) => {
+                    PageExpectation::InvalidHeader(name) => {
+                        let values = Self::header_values(headers, name);
+                        if values.is_empty() {
+                            Story::success(
+                                Expected::HeaderAbsent(url.to_string(), name.to_string()),
+                                notifier.clone(),
+                            )
+                        } else {
+                            Story::error(
+                                Unexpected::HeaderForbidden(
+                                    url.to_string(),
+                                    name.to_string(),
+                                    values.join(", "),
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+                    }
+
+                    edge_case => {
                         Story::error(
-                            Unexpected::ContentInvalid(url.to_string(), content.to_string()),
+                            Unexpected::UnmatchedValidationCase(
+                                url.to_string(),
+                                edge_case.to_string(),
+                            ),
                             notifier.clone(),
                         )
                     }
+                }
+            })
+            .collect()
+    }
+
+
+    /// Build Stories from Json PageExpectations
+    #[instrument]
+    fn handle_page_json_expectations(
+        url: &str,
+        raw_page_content: &str,
+        expected_json: &[PageExpectation],
+        notifier: Option<String>,
+    ) -> Stories {
+        if expected_json.is_empty() {
+            return vec![];
+        }
+        let document = serde_json::from_str::<Value>(raw_page_content);
+        expected_json
+            .par_iter()
+            .map(|expectation| {
+                match (expectation, &document) {
+                    (PageExpectation::ValidJson(json_expectation), Ok(json)) => {
+                        Self::handle_json_assertion(url, json, json_expectation, notifier.clone())
+                    }
 
-                    PageExpectation::ValidNoContent => {
-                        Story::success(
-                            Expected::EmptyContent(url.to_string()),
+                    (PageExpectation::ValidJson(json_expectation), Err(err)) => {
+                        Story::error(
+                            Unexpected::JsonAssertionFailed(
+                                url.to_string(),
+                                json_expectation.path.to_string(),
+                                format!("Body is not valid JSON: {err}"),
+                            ),
                             notifier.clone(),
                         )
                     }
 
-                    edge_case => {
+                    (edge_case, _) => {
                         Story::error(
                             Unexpected::UnmatchedValidationCase(
                                 url.to_string(),
-                                format!("{:?}", edge_case),
+                                edge_case.to_string(),
                             ),
                             notifier.clone(),
                         )
                     }
                 }
             })
-            .collect::<Stories>()
+            .collect()
+    }
+
+
+    /// Build a Story from single JSON assertion on parsed JSON document
+    #[instrument(skip(json))]
+    fn handle_json_assertion(
+        url: &str,
+        json: &Value,
+        json_expectation: &JsonExpectation,
+        notifier: Option<String>,
+    ) -> Story {
+        let path = &json_expectation.path;
+        let failure = |details: String| {
+            Story::error(
+                Unexpected::JsonAssertionFailed(url.to_string(), path.to_string(), details),
+                notifier.clone(),
+            )
+        };
+        let selected = match select_json_values(json, path) {
+            Ok(selected) => selected,
+            Err(err) => return failure(err.to_string()),
+        };
+        let found = match selected.as_slice() {
+            [] => None,
+            [single] => Some((*single).clone()),
+            many => Some(Value::Array(many.iter().map(|value| (*value).clone()).collect())),
+        };
+        match (found, json_expectation.exists) {
+            (None, Some(false)) => {
+                Story::success(
+                    Expected::JsonAbsent(url.to_string(), path.to_string()),
+                    notifier.clone(),
+                )
+            }
+
+            (None, _) => failure(String::from("No value found")),
+
+            (Some(value), Some(false)) => failure(format!("Unexpected value found: {value}")),
+
+            (Some(value), _) => {
+                if let Some(expected_value) = &json_expectation.equals {
+                    if expected_value != &value {
+                        return failure(format!(
+                            "Value: {value} differs from expected: {expected_value}"
+                        ));
+                    }
+                }
+                if let Some(pattern) = &json_expectation.matches {
+                    let text = match &value {
+                        Value::String(text) => text.to_string(),
+                        other => other.to_string(),
+                    };
+                    match Regex::new(pattern) {
+                        Ok(regex) if regex.is_match(&text) => {}
+                        Ok(_) => {
+                            return failure(format!(
+                                "Value: {value} doesn't match expected regex: \"{pattern}\""
+                            ));
+                        }
+                        Err(err) => {
+                            return Story::error(
+                                Unexpected::RegexMalformed(
+                                    url.to_string(),
+                                    pattern.to_string(),
+                                    err.to_string(),
+                                ),
+                                notifier.clone(),
+                            );
+                        }
+                    }
+                }
+                Story::success(
+                    Expected::Json(url.to_string(), path.to_string(), value.to_string()),
+                    notifier.clone(),
+                )
+            }
+        }
+    }
+
+
+    /// Cross-check size of the received body with Content-Length declared by the server
+    #[instrument(skip(result_handler))]
+    fn handle_page_declared_length(
+        url: &str,
+        result_handler: &mut Easy2<Collector>,
+        notifier: Option<String>,
+    ) -> Stories {
+        let response_code = result_handler.response_code().unwrap_or_default();
+        let declared = result_handler.content_length_download().unwrap_or(-1.0);
+        let received = result_handler.download_size().unwrap_or_default();
+        if declared < 0.0
+            || declared == received
+            || response_code < 200
+            || response_code == 204
+            || response_code == 304
+        {
+            vec![]
+        } else {
+            vec![Story::error(
+                Unexpected::ContentLengthMismatch(
+                    url.to_string(),
+                    declared as u64,
+                    received as u64,
+                ),
+                notifier,
+            )]
+        }
+    }
+
+
+    /// Extract request timings from finished Curl handler
+    #[instrument(skip(result_handler))]
+    fn page_timings(result_handler: &mut Easy2<Collector>) -> Option<PageTimings> {
+        let namelookup = result_handler.namelookup_time().ok()?;
+        let connect = result_handler.connect_time().ok()?;
+        let appconnect = result_handler.appconnect_time().ok()?;
+        let starttransfer = result_handler.starttransfer_time().ok()?;
+        let total = result_handler.total_time().ok()?;
+
+        // Curl timings are cumulative, since the start of the request
+        Some(PageTimings {
+            dns: namelookup.as_millis() as u64,
+            connect: connect.saturating_sub(namelookup).as_millis() as u64,
+            tls: appconnect.saturating_sub(connect).as_millis() as u64,
+            ttfb: starttransfer.as_millis() as u64,
+            total: total.as_millis() as u64,
+        })
+    }
+
+
+    /// Extract effective remote address (IP:port) from finished Curl handler
+    #[instrument(skip(result_handler))]
+    fn page_remote_address(result_handler: &mut Easy2<Collector>) -> Option<String> {
+        let port = result_handler.primary_port().ok()?;
+        let ip = result_handler.primary_ip().ok()??;
+        match ip {
+            "" => None,
+            ipv6 if ipv6.contains(':') => Some(format!("[{ipv6}]:{port}")),
+            ipv4 => Some(format!("{ipv4}:{port}")),
+        }
+    }
+
+
+    /// Build Stories from ResponseTime PageExpectations
+    #[instrument]
+    fn handle_page_response_time_expectations(
+        url: &str,
+        timings: Option<PageTimings>,
+        expected_response_times: &[PageExpectation],
+        notifier: Option<String>,
+    ) -> Stories {
+        expected_response_times
+            .iter()
+            .flat_map(|expectation| {
+                match (expectation, timings) {
+                    (PageExpectation::ValidResponseTime(limits), Some(timings)) => {
+                        let exceeded = [
+                            ("DNS lookup", timings.dns, limits.dns),
+                            ("TCP connect", timings.connect, limits.connect),
+                            ("TLS handshake", timings.tls, limits.tls),
+                            ("time-to-first-byte", timings.ttfb, limits.ttfb),
+                            ("total", timings.total, limits.total),
+                        ]
+                        .into_iter()
+                        .filter_map(|(phase, measured, limit)| {
+                            match limit {
+                                Some(limit) if measured > limit => {
+                                    Some(Story::error(
+                                        Unexpected::ResponseTimeExceeded(
+                                            url.to_string(),
+                                            phase.to_string(),
+                                            measured,
+                                            limit,
+                                        ),
+                                        notifier.clone(),
+                                    ))
+                                }
+                                _ => None,
+                            }
+                        })
+                        .collect::<Stories>();
+                        if exceeded.is_empty() {
+                            vec![Story::success(
+                                Expected::ResponseTime(url.to_string(), timings.total),
+                                notifier.clone(),
+                            )]
+                        } else {
+                            exceeded
+                        }
+                    }
+
+                    (PageExpectation::ValidResponseTime(_), None) => {
+                        vec![Story::minor(UnexpectedMinor::InternalProtocolProblem(
+                            url.to_string(),
+                            String::from("Curl timings unavailable"),
+                        ))]
+                    }
+
+                    (edge_case, _) => {
+                        vec![Story::error(
+                            Unexpected::UnmatchedValidationCase(
+                                url.to_string(),
+                                edge_case.to_string(),
+                            ),
+                            notifier.clone(),
+                        )]
+                    }
+                }
+            })
+            .collect()
     }
 
 
@@ -429,6 +2111,23 @@ pub trait GenericChecker {
     }
 
 
+    /// Check if responded HTTP code is accepted by given code expectation
+    #[instrument]
+    fn http_code_accepted(expected_code: &PageExpectation, responded_code: u32) -> bool {
+        match expected_code {
+            &PageExpectation::ValidCode(the_code) => responded_code == the_code,
+            &PageExpectation::ValidCodeRange(from, to) => {
+                (from..=to).contains(&responded_code)
+            }
+            PageExpectation::ValidCodeIn(codes) => codes.contains(&responded_code),
+            PageExpectation::ValidCodeClass(class) if CODE_CLASS_REGEX.is_match(class) => {
+                class.starts_with(&(responded_code / 100).to_string())
+            }
+            _ => false,
+        }
+    }
+
+
     /// Build a Story from a HttpCode PageExpectation
     #[instrument]
     fn handle_page_httpcode_expectation(
@@ -441,6 +2140,18 @@ pub trait GenericChecker {
         match response_code {
             Ok(responded_code) => {
                 match expected_code {
+                    PageExpectation::ValidCodeClass(class)
+                        if !CODE_CLASS_REGEX.is_match(class) =>
+                    {
+                        Story::error(
+                            Unexpected::HttpCodeClassMalformed(
+                                url.to_string(),
+                                class.to_string(),
+                            ),
+                            notifier,
+                        )
+                    }
+
                     &PageExpectation::ValidCode(the_code) if responded_code == the_code => {
                         Story::success(Expected::HttpCode(url.to_string(), the_code), notifier)
                     }
@@ -458,7 +2169,35 @@ pub trait GenericChecker {
                         )
                     }
 
-                    &PageExpectation::ValidCode(_the_code) if responded_code == 0 => {
+                    PageExpectation::ValidCodeRange(..)
+                    | PageExpectation::ValidCodeIn(_)
+                    | PageExpectation::ValidCodeClass(_)
+                        if responded_code > 0
+                            || Self::http_code_accepted(expected_code, responded_code) =>
+                    {
+                        if Self::http_code_accepted(expected_code, responded_code) {
+                            Story::success(
+                                Expected::HttpCode(url.to_string(), responded_code),
+                                notifier,
+                            )
+                        } else {
+                            Story::error(
+                                Unexpected::HttpCodeNotAccepted(
+                                    url.to_string(),
+                                    responded_code,
+                                    expected_code.to_string(),
+                                ),
+                                notifier,
+                            )
+                        }
+                    }
+
+                    PageExpectation::ValidCode(_)
+                    | PageExpectation::ValidCodeRange(..)
+                    | PageExpectation::ValidCodeIn(_)
+                    | PageExpectation::ValidCodeClass(_)
+                        if responded_code == 0 =>
+                    {
                         match connect_oserror {
                             Some(error) => {
                                 Story::minor(UnexpectedMinor::OSError(
@@ -500,28 +2239,58 @@ pub trait GenericChecker {
     }
 
 
-    /// Process Curl page requests using given handler
-    #[instrument]
+    /// Process Curl page requests using given handler. Address is set for fanned out pages
+    #[instrument(skip(page_check, handler), fields(url = %page_check.url))]
     fn process_page_handler(
         page_check: &Page,
+        address: Option<IpAddr>,
         handler: CurlHandler,
+        transfer_error: Option<CurlError>,
         multi: &Multi,
         notifier: Option<String>,
     ) -> Stories {
         let page_expectations = page_check.clone().expects;
-        let url = &page_check.url;
+        let url = &Self::page_label(page_check, address);
+        let head_request = matches!(
+            page_check
+                .options
+                .as_ref()
+                .and_then(|options| options.method.as_ref()),
+            Some(Method::Head)
+        );
+
+        let redirect_chain = handler
+            .as_ref()
+            .map(|handle| Self::page_redirect_chain(&page_check.url, &handle.get_ref().2))
+            .unwrap_or_default();
+
+        // TLS failures are reported with their own specific stories:
+        if let Some(tls_failure) = transfer_error
+            .as_ref()
+            .and_then(|err| Self::tls_failure(url, err))
+        {
+            error!(target: "checks", "{tls_failure}");
+            return vec![Story::error(tls_failure, notifier)];
+        }
+
+        // Redirect loops end up with maximum amount of redirects reached:
+        if transfer_error
+            .as_ref()
+            .is_some_and(|err| err.is_too_many_redirects())
+        {
+            let redirect_loop = Unexpected::RedirectLoop(
+                url.to_string(),
+                redirect_chain_description(&redirect_chain),
+            );
+            error!(target: "checks", "{redirect_loop}");
+            return vec![
+                Story::error(redirect_loop, notifier).with_redirect_chain(&redirect_chain)
+            ];
+        }
 
         // take control over curl handler, perform validations, produce stories…
         let a_handler = match handler {
-            Ok(handle) => {
-                if handle.get_ref().0.is_empty() {
-                    let fail = format!("Site is down: {url}");
-                    error!(target: "checks", "{fail}");
-                    return vec![Story::error(Unexpected::HandlerFailed(fail), notifier)];
-                } else {
-                    handle
-                }
-            }
+            Ok(handle) => handle,
             Err(err) => {
                 error!("Couldn't connect to: {url}. Error details: {err}",);
                 return vec![Story::error(
@@ -531,26 +2300,71 @@ pub trait GenericChecker {
             }
         };
 
-        let handle = a_handler.get_ref().0.to_owned();
-        let raw_page_content = String::from_utf8(handle).unwrap_or_default();
+        let empty_body = a_handler.get_ref().0.is_empty() && !head_request;
+        let raw_page_bytes = a_handler.get_ref().0.to_owned();
+        let headers = a_handler.get_ref().1.to_owned();
+        let raw_page_content = Self::decode_page_content(&raw_page_bytes, &headers);
         let expected_code = Self::find_code_validation(&page_expectations);
         let expected_contents = Self::find_content_validations(&page_expectations);
-        let expected_content_length = Self::find_content_length_validation(&page_expectations);
+        let expected_content_lengths =
+            Self::find_content_length_validations(&page_expectations);
         let expected_final_address = Self::find_address_validation(&page_expectations);
+        let expected_response_times = Self::find_response_time_validations(&page_expectations);
+        let expected_headers = Self::find_header_validations(&page_expectations);
+        let expected_json = Self::find_json_validations(&page_expectations);
+        let expected_digests = Self::find_digest_validations(&page_expectations);
+        let expected_http_versions = Self::find_http_version_validations(&page_expectations);
+        let expected_redirects = Self::find_redirect_validations(&page_expectations);
 
         // Gather Story from expectations
         let content_stories = Self::handle_page_content_expectations(
-            &page_check.url,
+            url,
             &raw_page_content,
             &expected_contents,
             notifier.clone(),
         );
-        let content_length_story = vec![Self::handle_page_length_expectation(
-            &page_check.url,
+        let header_stories = Self::handle_page_header_expectations(
+            url,
+            &headers,
+            &expected_headers,
+            notifier.clone(),
+        );
+        let http_version_stories = Self::handle_page_http_version_expectations(
+            url,
+            &headers,
+            &expected_http_versions,
+            notifier.clone(),
+        );
+        let redirect_stories = Self::handle_page_redirect_expectations(
+            url,
+            &redirect_chain,
+            &expected_redirects,
+            notifier.clone(),
+        );
+        let json_stories = Self::handle_page_json_expectations(
+            url,
             &raw_page_content,
-            expected_content_length,
+            &expected_json,
             notifier.clone(),
-        )];
+        );
+        let digest_stories = Self::handle_page_digest_expectations(
+            url,
+            &raw_page_bytes,
+            &raw_page_content,
+            &expected_digests,
+            notifier.clone(),
+        );
+        let content_length_stories = expected_content_lengths
+            .iter()
+            .map(|expected_content_length| {
+                Self::handle_page_length_expectation(
+                    url,
+                    &raw_page_bytes,
+                    expected_content_length,
+                    notifier.clone(),
+                )
+            })
+            .collect::<Stories>();
 
         let mut result_handler = match multi.remove2(a_handler) {
             Ok(res_handler) => res_handler,
@@ -566,9 +2380,45 @@ pub trait GenericChecker {
                 )];
             }
         };
+        let response_code = result_handler
+            .response_code()
+            .map_err(Self::produce_curl_response_error);
+
+        // Empty body is fine for responses without content, and for accepted HTTP codes:
+        if empty_body
+            && !matches!(
+                response_code,
+                Ok(code) if code > 0
+                    && (code == 204
+                        || code == 304
+                        || Self::http_code_accepted(expected_code, code))
+            )
+        {
+            let fail = format!("Site is down: {url}");
+            error!(target: "checks", "{fail}");
+            return vec![Story::error(Unexpected::HandlerFailed(fail), notifier)];
+        }
+        let timings = Self::page_timings(&mut result_handler);
+        let remote_address = Self::page_remote_address(&mut result_handler);
+        // HEAD responses declare Content-Length of the body they don't send
+        let declared_length_stories = if head_request {
+            vec![]
+        } else {
+            Self::handle_page_declared_length(
+                url,
+                &mut result_handler,
+                notifier.clone(),
+            )
+        };
+        let response_time_stories = Self::handle_page_response_time_expectations(
+            url,
+            timings,
+            &expected_response_times,
+            notifier.clone(),
+        );
         let result_final_address = result_handler.effective_url().unwrap_or_default();
         let result_final_address_story = vec![Self::handle_page_address_expectation(
-            &page_check.url,
+            url,
             result_final_address.unwrap_or_default(),
             expected_final_address,
             notifier.clone(),
@@ -579,27 +2429,99 @@ pub trait GenericChecker {
             Err(error) => Some(Error::from_raw_os_error(error.code() as i32)),
         };
         let result_handler_story = vec![Self::handle_page_httpcode_expectation(
-            &page_check.url,
+            url,
             connect_oserror,
-            result_handler
-                .response_code()
-                .map_err(Self::produce_curl_response_error),
+            response_code,
             expected_code,
             notifier,
         )];
 
         trace!(
-            "process_page_handler::page_expectations: {page_expectations:?}. process_page_handler::expected_code: {expected_code:?}. process_page_handler::expected_contents: {expected_contents:?}. process_page_handler::expected_content_length: {expected_content_length:?}. process_page_handler::expected_final_address: {expected_final_address:?}. process_page_handler::content_story: {content_stories:?}. process_page_handler::content_length_story: {content_length_story:?}. process_page_handler::result_final_address_story: {result_final_address_story:?}. process_page_handler::handle_page_httpcode_expectation: {result_handler_story:?}. process_page_handler::raw_page_content: {raw_page_content:?}."
+            "process_page_handler::page_expectations: {page_expectations:?}. process_page_handler::raw_page_content: {raw_page_content:?}."
         );
 
         // Collect the history results
-        [
+        let stories = [
             content_stories,
-            content_length_story,
+            content_length_stories,
+            declared_length_stories,
             result_handler_story,
             result_final_address_story,
+            response_time_stories,
+            header_stories,
+            http_version_stories,
+            redirect_stories,
+            json_stories,
+            digest_stories,
         ]
         .concat()
+        .into_iter()
+        .map(|story| {
+            story
+                .with_timings(timings)
+                .with_remote_address(remote_address.clone())
+                .with_redirect_chain(&redirect_chain)
+        })
+        .collect::<Stories>();
+        trace!("process_page_handler::stories: {stories:?}.");
+        stories
+    }
+
+
+    /// Prepare page request: read request body, resolve authentication and TLS client key
+    /// passphrase secrets. Failures are reported for given URL
+    #[instrument(skip(page_check))]
+    fn prepare_request_for(
+        page_check: &Page,
+        url: &str,
+    ) -> Result<PageRequest, Unexpected> {
+        let request_body = Self::request_body_for(page_check).map_err(|err| {
+            Unexpected::RequestBodyUnreadable(url.to_string(), err.to_string())
+        })?;
+        let auth = Self::resolve_auth_for(page_check).map_err(|err| {
+            Unexpected::AuthenticationFailed(url.to_string(), err.to_string())
+        })?;
+        let key_passphrase = page_check
+            .options
+            .as_ref()
+            .and_then(|options| options.client_key_passphrase.as_ref())
+            .map(Secret::resolve)
+            .transpose()
+            .map_err(|err| {
+                Unexpected::ClientKeyPassphraseUnavailable(url.to_string(), err.to_string())
+            })?;
+        Ok((request_body, auth, key_passphrase))
+    }
+
+
+    /// Read page request body: from body_file, or post_data if not given
+    #[instrument(skip(page_check))]
+    fn request_body_for(page_check: &Page) -> Result<Vec<u8>, Error> {
+        let options = page_check.options.clone().unwrap_or_default();
+        match options.body_file {
+            Some(body_file) => {
+                trace!("Reading request body from file: {body_file}");
+                fs::read(&body_file).map_err(|err| {
+                    Error::new(
+                        err.kind(),
+                        format!("Body file: {body_file} is unreadable: {err}"),
+                    )
+                })
+            }
+            None => Ok(options.post_data.unwrap_or_default().into_bytes()),
+        }
+    }
+
+
+    /// Resolve page authentication secrets (and fetch OAuth2 token if necessary)
+    #[instrument(skip(page_check))]
+    fn resolve_auth_for(page_check: &Page) -> Result<Option<ResolvedAuth>, Error> {
+        page_check
+            .options
+            .as_ref()
+            .and_then(|options| options.auth.as_ref())
+            .map(|auth| auth.resolve())
+            .transpose()
     }
 
 
@@ -618,6 +2540,18 @@ pub trait GenericChecker {
     }
 
 
+    /// Build resolve/connect-to overrides List for Curl
+    #[instrument]
+    fn list_of_overrides(overrides: Option<Vec<String>>) -> List {
+        let mut list = List::new();
+        for entry in overrides.unwrap_or_default() {
+            debug!("Setting Curl override: {entry}");
+            list.append(&entry).unwrap_or_default();
+        }
+        list
+    }
+
+
     /// Build cookies list for Curl
     #[instrument]
     fn list_of_cookies(headers: Option<Vec<String>>) -> String {
@@ -631,10 +2565,19 @@ pub trait GenericChecker {
 
 
     /// Load page check handler
-    #[instrument]
-    fn load_handler_for(page_check: &Page, multi: &Multi) -> CurlHandler {
+    #[instrument(
+        skip(page_check, request_body, auth, key_passphrase),
+        fields(url = %page_check.url)
+    )]
+    fn load_handler_for(
+        page_check: &Page,
+        request_body: Vec<u8>,
+        auth: Option<ResolvedAuth>,
+        key_passphrase: Option<String>,
+        multi: &Multi,
+    ) -> CurlHandler {
         // Initialize Curl, set URL
-        let mut curl = Easy2::new(Collector(Vec::new()));
+        let mut curl = Easy2::new(Collector::default());
         curl.url(&page_check.url).unwrap_or_default();
         trace!("Curl URL: {}", page_check.url);
 
@@ -658,7 +2601,7 @@ pub trait GenericChecker {
             }
         }
 
-        trace!("Curl options: {}", curl_options.to_string());
+        trace!("Curl options: {curl_options:?}");
 
         // Setup Curl configuration based on given options
         if curl_options.follow_redirects.unwrap_or(true) {
@@ -678,29 +2621,41 @@ pub trait GenericChecker {
         }
 
         // Setup Curl configuration based on given options
-        let post_data = curl_options.post_data.unwrap_or_default();
         match curl_options.method {
-            Some(Method::Put) => {
-                trace!("Curl method: {}", "PUT");
-                curl.get(false).unwrap_or_default();
-                curl.post(false).unwrap_or_default();
-                curl.put(true).unwrap_or_default();
-                trace!("Curl PUT data: {}", post_data);
-                curl.post_fields_copy(post_data.as_bytes())
-                    .unwrap_or_default();
+            Some(Method::Head) => {
+                trace!("Curl method: {}", "HEAD");
+                curl.get(true).unwrap_or_default();
+                curl.nobody(true).unwrap_or_default();
             }
             Some(Method::Post) => {
                 trace!("Curl method: {}", "POST");
                 curl.get(false).unwrap_or_default();
                 curl.put(false).unwrap_or_default();
                 curl.post(true).unwrap_or_default();
-                trace!("Curl POST data: {}", post_data);
-                curl.post_fields_copy(post_data.as_bytes())
-                    .unwrap_or_default();
+                trace!("Curl POST data: {}", String::from_utf8_lossy(&request_body));
+                curl.post_fields_copy(&request_body).unwrap_or_default();
             }
-
-            // fallbacks to GET
-            Some(_) | None => {
+            Some(
+                method @ (Method::Put
+                | Method::Delete
+                | Method::Patch
+                | Method::Options
+                | Method::Custom(_)),
+            ) => {
+                let method_name = method.name();
+                trace!("Curl method: {method_name}");
+                curl.get(false).unwrap_or_default();
+                curl.put(false).unwrap_or_default();
+                curl.custom_request(&method_name).unwrap_or_default();
+                if !request_body.is_empty() {
+                    trace!(
+                        "Curl {method_name} data: {}",
+                        String::from_utf8_lossy(&request_body)
+                    );
+                    curl.post_fields_copy(&request_body).unwrap_or_default();
+                }
+            }
+            Some(Method::Get) | None => {
                 trace!("Curl method: {}", "GET");
                 curl.put(false).unwrap_or_default();
                 curl.post(false).unwrap_or_default();
@@ -708,9 +2663,59 @@ pub trait GenericChecker {
             }
         };
 
+        // Set HTTP protocol version
+        match curl_options.http_version {
+            Some(HttpVersion::Http10) => {
+                curl.http_version(CurlHttpVersion::V10).unwrap_or_default()
+            }
+            Some(HttpVersion::Http11) => {
+                curl.http_version(CurlHttpVersion::V11).unwrap_or_default()
+            }
+            Some(HttpVersion::Http2) => {
+                curl.http_version(CurlHttpVersion::V2).unwrap_or_default()
+            }
+            Some(HttpVersion::Http2PriorKnowledge) => {
+                curl.http_version(CurlHttpVersion::V2PriorKnowledge)
+                    .unwrap_or_default()
+            }
+            None => (),
+        }
+
+        // Negotiate all compression methods supported by Curl (gzip, brotli, zstd…)
+        curl.accept_encoding("").unwrap_or_default();
+
+        // Setup authentication
+        let mut headers = Self::list_of_headers(curl_options.headers);
+        match auth {
+            Some(ResolvedAuth::Basic(username, password)) => {
+                trace!("Curl auth: Basic, username: {username}");
+                curl.username(&username).unwrap_or_default();
+                curl.password(&password).unwrap_or_default();
+                curl.http_auth(Auth::new().basic(true)).unwrap_or_default();
+            }
+            Some(ResolvedAuth::Digest(username, password)) => {
+                trace!("Curl auth: Digest, username: {username}");
+                curl.username(&username).unwrap_or_default();
+                curl.password(&password).unwrap_or_default();
+                curl.http_auth(Auth::new().digest(true)).unwrap_or_default();
+            }
+            Some(ResolvedAuth::Bearer(token)) => {
+                trace!("Curl auth: Bearer");
+                headers
+                    .append(&format!("Authorization: Bearer {token}"))
+                    .unwrap_or_default();
+            }
+            Some(ResolvedAuth::AwsSigV4(provider, access_key, secret_key)) => {
+                trace!("Curl auth: AWS SigV4, provider: {provider}");
+                curl.username(&access_key).unwrap_or_default();
+                curl.password(&secret_key).unwrap_or_default();
+                curl.aws_sigv4(&provider).unwrap_or_default();
+            }
+            None => (),
+        }
+
         // Pass headers and cookies
-        curl.http_headers(Self::list_of_headers(curl_options.headers))
-            .unwrap_or_default();
+        curl.http_headers(headers).unwrap_or_default();
         curl.cookie(&Self::list_of_cookies(curl_options.cookies))
             .unwrap_or_default();
 
@@ -744,6 +2749,55 @@ pub trait GenericChecker {
             curl.ssl_verify_host(false).unwrap_or_default();
         }
 
+        // Client certificate and key (mutual TLS)
+        if let Some(client_cert) = &curl_options.client_cert {
+            trace!("Setting TLS client certificate: {client_cert}");
+            curl.ssl_cert(client_cert).unwrap_or_default();
+        }
+        if let Some(client_key) = &curl_options.client_key {
+            trace!("Setting TLS client key: {client_key}");
+            curl.ssl_key(client_key).unwrap_or_default();
+        }
+        if let Some(passphrase) = key_passphrase {
+            curl.key_password(&passphrase).unwrap_or_default();
+        }
+
+        // Custom CA bundle and pinned public key
+        if let Some(ca_bundle) = &curl_options.ca_bundle {
+            trace!("Setting CA bundle: {ca_bundle}");
+            curl.cainfo(ca_bundle).unwrap_or_default();
+        }
+        if let Some(pinned_public_key) = &curl_options.pinned_public_key {
+            trace!("Setting pinned public key: {pinned_public_key}");
+            curl.pinned_public_key(pinned_public_key)
+                .unwrap_or_default();
+        }
+
+        // Proxy and source address
+        if let Some(proxy) = &curl_options.proxy {
+            trace!("Setting proxy: {proxy}");
+            curl.proxy(proxy).unwrap_or_default();
+        }
+        if let Some(interface) = &curl_options.interface {
+            trace!("Setting outgoing interface: {interface}");
+            curl.interface(interface).unwrap_or_default();
+        }
+        match curl_options.ip_version {
+            Some(IpVersion::V4) => curl.ip_resolve(IpResolve::V4).unwrap_or_default(),
+            Some(IpVersion::V6) => curl.ip_resolve(IpResolve::V6).unwrap_or_default(),
+            Some(IpVersion::Any) | None => curl.ip_resolve(IpResolve::Any).unwrap_or_default(),
+        }
+
+        // Host name resolve and connection target overrides
+        if curl_options.resolve.is_some() {
+            curl.resolve(Self::list_of_overrides(curl_options.resolve))
+                .unwrap_or_default();
+        }
+        if curl_options.connect_to.is_some() {
+            curl.connect_to(Self::list_of_overrides(curl_options.connect_to))
+                .unwrap_or_default();
+        }
+
         // Max connections is 10 per check
         curl.max_connects(CHECK_MAX_CONNECTIONS).unwrap_or_default();
 
@@ -755,3 +2809,8 @@ pub trait GenericChecker {
         multi.add2(curl)
     }
 }
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
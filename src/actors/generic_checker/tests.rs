@@ -0,0 +1,1566 @@
+use openssl::{base64, sha::sha256, symm::Cipher};
+use std::{
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use url::Url;
+
+use crate::{
+    actors::{generic_checker::GenericChecker, multi_checker::MultiChecker},
+    test_fixtures::*,
+    *,
+};
+
+
+const URL: &str = "http://localhost/";
+const CONTENT: &str =
+    "<html><body>Krecik Digs Tunnels. Tunnel 1, tunnel 2, tunnel 3.</body></html>";
+const TIMINGS: PageTimings = PageTimings {
+    dns: 5,
+    connect: 10,
+    tls: 0,
+    ttfb: 300,
+    total: 320,
+};
+
+
+/// Checks single page expectation against CONTENT
+fn content_story(expectation: PageExpectation) -> Story {
+    MultiChecker::handle_page_content_expectations(URL, CONTENT, &[expectation], None)
+        .remove(0)
+}
+
+
+#[test]
+fn test_content_regex_reports_first_match() {
+    let story =
+        content_story(PageExpectation::ValidContentRegex(r"[Tt]unnel \d+".to_string()));
+    assert_eq!(
+        story.success,
+        Some(Expected::ContentRegex(
+            URL.to_string(),
+            r"[Tt]unnel \d+".to_string(),
+            "Tunnel 1".to_string()
+        ))
+    );
+}
+
+
+#[test]
+fn test_content_regex_without_match() {
+    let story = content_story(PageExpectation::ValidContentRegex(r"^Fatal".to_string()));
+    assert_eq!(
+        story.error,
+        Some(Unexpected::ContentRegexInvalid(URL.to_string(), r"^Fatal".to_string()))
+    );
+}
+
+
+#[test]
+fn test_content_regex_malformed() {
+    let story = content_story(PageExpectation::ValidContentRegex(r"(unclosed".to_string()));
+    assert!(matches!(
+        story.error,
+        Some(Unexpected::RegexMalformed(_, ref pattern, _)) if pattern == "(unclosed"
+    ));
+}
+
+
+#[test]
+fn test_content_case_insensitive() {
+    let story =
+        content_story(PageExpectation::ValidContentCaseInsensitive("KRECIK digs".to_string()));
+    assert!(story.success.is_some());
+
+    let story = content_story(PageExpectation::ValidContentCaseInsensitive(
+        "maintenance mode".to_string(),
+    ));
+    assert!(matches!(story.error, Some(Unexpected::ContentCaseInsensitiveInvalid(..))));
+}
+
+
+#[test]
+fn test_content_occurrences_are_case_sensitive() {
+    let story =
+        content_story(PageExpectation::ValidContentOccurrences("tunnel".to_string(), 2));
+    assert!(story.success.is_some());
+
+    let story =
+        content_story(PageExpectation::ValidContentOccurrences("tunnel".to_string(), 3));
+    assert_eq!(
+        story.error,
+        Some(Unexpected::ContentOccurrencesInvalid(
+            URL.to_string(),
+            "tunnel".to_string(),
+            2,
+            3
+        ))
+    );
+}
+
+
+#[test]
+fn test_content_forbidden() {
+    let story = content_story(PageExpectation::InvalidContent("Fatal error".to_string()));
+    assert!(story.success.is_some());
+
+    let story = content_story(PageExpectation::InvalidContent("Digs".to_string()));
+    assert_eq!(
+        story.error,
+        Some(Unexpected::ContentForbidden(URL.to_string(), "Digs".to_string()))
+    );
+}
+
+
+/// Checks page with given expectations
+fn check_page(url: &str, expects: PageExpectations) -> Stories {
+    check_page_with_options(url, expects, PageOptions::default())
+}
+
+
+/// Checks page with given expectations and options
+fn check_page_with_options(
+    url: &str,
+    expects: PageExpectations,
+    options: PageOptions,
+) -> Stories {
+    MultiChecker::check_pages(&[Check {
+        pages: Some(vec![Page {
+            url: url.to_string(),
+            expects,
+            options: Some(options),
+            fan_out_dns: None,
+        }]),
+        ..Check::default()
+    }])
+}
+
+
+/// Collects error stories
+fn errors(stories: &[Story]) -> Vec<Unexpected> {
+    stories
+        .iter()
+        .filter_map(|story| story.error.clone())
+        .collect()
+}
+
+
+#[test]
+fn test_response_time_within_limits() {
+    let stories = MultiChecker::handle_page_response_time_expectations(
+        URL,
+        Some(TIMINGS),
+        &[PageExpectation::ValidResponseTime(ResponseTimeLimits {
+            dns: Some(100),
+            ttfb: Some(500),
+            total: Some(500),
+            ..ResponseTimeLimits::default()
+        })],
+        None,
+    );
+    assert_eq!(stories.len(), 1);
+    assert_eq!(stories[0].success, Some(Expected::ResponseTime(URL.to_string(), 320)));
+}
+
+
+#[test]
+fn test_response_time_reports_every_exceeded_phase() {
+    let stories = MultiChecker::handle_page_response_time_expectations(
+        URL,
+        Some(TIMINGS),
+        &[PageExpectation::ValidResponseTime(ResponseTimeLimits {
+            dns: Some(100),
+            ttfb: Some(100),
+            total: Some(200),
+            ..ResponseTimeLimits::default()
+        })],
+        None,
+    );
+    assert_eq!(
+        errors(&stories),
+        vec![
+            Unexpected::ResponseTimeExceeded(
+                URL.to_string(),
+                "time-to-first-byte".to_string(),
+                300,
+                100
+            ),
+            Unexpected::ResponseTimeExceeded(URL.to_string(), "total".to_string(), 320, 200),
+        ]
+    );
+}
+
+
+#[test]
+fn test_response_time_without_timings() {
+    let stories = MultiChecker::handle_page_response_time_expectations(
+        URL,
+        None,
+        &[PageExpectation::ValidResponseTime(ResponseTimeLimits::default())],
+        None,
+    );
+    assert!(stories[0].minor.is_some());
+}
+
+
+#[test]
+fn test_page_stories_carry_timings() {
+    let url = serve_http(|_request| {
+        thread::sleep(Duration::from_millis(300));
+        http_response(200, &[], b"<html><body>Slow but up</body></html>")
+    });
+    let stories = check_page(
+        &url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::ValidResponseTime(ResponseTimeLimits {
+                ttfb: Some(100),
+                ..ResponseTimeLimits::default()
+            }),
+        ],
+    );
+    for story in &stories {
+        let timings = story.timings.unwrap();
+        assert!(timings.ttfb >= 300);
+        assert!(timings.total >= timings.ttfb);
+    }
+    assert!(matches!(
+        &errors(&stories)[..],
+        [Unexpected::ResponseTimeExceeded(_, phase, _, 100)] if phase == "time-to-first-byte"
+    ));
+}
+
+
+/// Checks single header expectation against given response headers
+fn header_story(headers: &[&str], expectation: PageExpectation) -> Story {
+    let headers = headers
+        .iter()
+        .map(|header| header.to_string())
+        .collect::<Vec<_>>();
+    MultiChecker::handle_page_header_expectations(URL, &headers, &[expectation], None)
+        .remove(0)
+}
+
+
+#[test]
+fn test_header_name_is_case_insensitive() {
+    let story = header_story(
+        &["Content-Type: text/html; charset=utf-8"],
+        PageExpectation::ValidHeader("content-type".to_string(), "^text/html".to_string()),
+    );
+    assert_eq!(
+        story.success,
+        Some(Expected::Header(
+            URL.to_string(),
+            "content-type".to_string(),
+            "text/html; charset=utf-8".to_string()
+        ))
+    );
+}
+
+
+#[test]
+fn test_header_value_mismatch() {
+    let story = header_story(
+        &["X-Backend: node-2"],
+        PageExpectation::ValidHeader("X-Backend".to_string(), "node-1".to_string()),
+    );
+    assert_eq!(
+        story.error,
+        Some(Unexpected::HeaderInvalid(
+            URL.to_string(),
+            "X-Backend".to_string(),
+            "node-2".to_string(),
+            "node-1".to_string()
+        ))
+    );
+}
+
+
+#[test]
+fn test_header_repeated_any_value_matches() {
+    let story = header_story(
+        &["Vary: Accept-Encoding", "Vary: Origin"],
+        PageExpectation::ValidHeader("Vary".to_string(), "^Origin$".to_string()),
+    );
+    assert!(story.success.is_some());
+}
+
+
+#[test]
+fn test_header_missing() {
+    let story = header_story(
+        &["X-Backend: node-2"],
+        PageExpectation::ValidHeader(
+            "Strict-Transport-Security".to_string(),
+            "max-age".to_string(),
+        ),
+    );
+    assert_eq!(
+        story.error,
+        Some(Unexpected::HeaderMissing(
+            URL.to_string(),
+            "Strict-Transport-Security".to_string()
+        ))
+    );
+}
+
+
+#[test]
+fn test_header_forbidden() {
+    let expectation = PageExpectation::InvalidHeader("X-Powered-By".to_string());
+    assert!(header_story(&["X-Backend: node-2"], expectation.clone())
+        .success
+        .is_some());
+    assert_eq!(
+        header_story(&["X-Powered-By: PHP/5.6"], expectation).error,
+        Some(Unexpected::HeaderForbidden(
+            URL.to_string(),
+            "X-Powered-By".to_string(),
+            "PHP/5.6".to_string()
+        ))
+    );
+}
+
+
+#[test]
+fn test_page_headers_of_response() {
+    let url = serve_http(|_request| {
+        http_response(200, &["cache-control: no-cache"], b"<html><body>Headers</body></html>")
+    });
+    let stories = check_page(
+        &url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::ValidHeader("Cache-Control".to_string(), "no-cache".to_string()),
+        ],
+    );
+    assert!(errors(&stories).is_empty());
+    assert!(stories.iter().any(|story| {
+        story.success
+            == Some(Expected::Header(
+                url.clone(),
+                "Cache-Control".to_string(),
+                "no-cache".to_string(),
+            ))
+    }));
+}
+
+
+const JSON_BODY: &str =
+    r#"{"data": {"health": {"status": "ok", "nodes": [{"id": 1}, {"id": 2}]}}}"#;
+
+
+/// Checks single JSON expectation (given as JSON) against JSON_BODY
+fn json_story(expectation: &str) -> Story {
+    let expectation = serde_json::from_str::<PageExpectation>(expectation).unwrap();
+    MultiChecker::handle_page_json_expectations(URL, JSON_BODY, &[expectation], None).remove(0)
+}
+
+
+#[test]
+fn test_json_path_and_pointer_equals() {
+    assert!(json_story(r#"{"ValidJson": {"path": "$.data.health.status", "equals": "ok"}}"#)
+        .success
+        .is_some());
+    assert!(json_story(r#"{"ValidJson": {"path": "/data/health/nodes/1/id", "equals": 2}}"#)
+        .success
+        .is_some());
+}
+
+
+#[test]
+fn test_json_path_wildcard_and_negative_index() {
+    assert!(
+        json_story(r#"{"ValidJson": {"path": "$.data.health.nodes[*].id", "equals": [1, 2]}}"#)
+            .success
+            .is_some()
+    );
+    assert!(json_story(r#"{"ValidJson": {"path": "$['data'].health.nodes[-1]"}}"#)
+        .success
+        .is_some());
+}
+
+
+#[test]
+fn test_json_value_matches() {
+    let story = json_story(
+        r#"{"ValidJson": {"path": "$.data.health.status", "matches": "^(ok|up)$"}}"#,
+    );
+    assert!(story.success.is_some());
+}
+
+
+#[test]
+fn test_json_value_mismatch() {
+    let story =
+        json_story(r#"{"ValidJson": {"path": "$.data.health.status", "equals": "down"}}"#);
+    assert!(matches!(story.error, Some(Unexpected::JsonAssertionFailed(..))));
+}
+
+
+#[test]
+fn test_json_path_existence() {
+    assert!(json_story(r#"{"ValidJson": {"path": "$.errors", "exists": false}}"#)
+        .success
+        .is_some());
+    assert!(matches!(
+        json_story(r#"{"ValidJson": {"path": "$.data", "exists": false}}"#).error,
+        Some(Unexpected::JsonAssertionFailed(..))
+    ));
+    assert!(matches!(
+        json_story(r#"{"ValidJson": {"path": "$.data.missing"}}"#).error,
+        Some(Unexpected::JsonAssertionFailed(..))
+    ));
+}
+
+
+#[test]
+fn test_json_path_malformed() {
+    let story = json_story(r#"{"ValidJson": {"path": "data.health"}}"#);
+    assert!(matches!(story.error, Some(Unexpected::JsonAssertionFailed(..))));
+}
+
+
+#[test]
+fn test_json_body_invalid() {
+    let expectation = serde_json::from_str::<PageExpectation>(
+        r#"{"ValidJson": {"path": "$.data.health.status", "equals": "ok"}}"#,
+    )
+    .unwrap();
+    let stories = MultiChecker::handle_page_json_expectations(
+        URL,
+        "<html>Not JSON</html>",
+        &[expectation],
+        None,
+    );
+    assert!(matches!(
+        &stories[0].error,
+        Some(Unexpected::JsonAssertionFailed(_, _, details))
+            if details.starts_with("Body is not valid JSON")
+    ));
+}
+
+
+/// Checks single HTTP code expectation against given response code
+fn code_story(code: u32, expectation: &PageExpectation) -> Story {
+    MultiChecker::handle_page_httpcode_expectation(URL, None, Ok(code), expectation, None)
+}
+
+
+#[test]
+fn test_code_range_is_inclusive() {
+    let range = PageExpectation::ValidCodeRange(200, 299);
+    assert_eq!(
+        code_story(204, &range).success,
+        Some(Expected::HttpCode(URL.to_string(), 204))
+    );
+    assert!(code_story(299, &range).success.is_some());
+    assert!(code_story(300, &range).error.is_some());
+}
+
+
+#[test]
+fn test_code_set() {
+    let set = PageExpectation::ValidCodeIn(vec![200, 301, 304]);
+    assert!(code_story(304, &set).success.is_some());
+    assert_eq!(
+        code_story(302, &set).error,
+        Some(Unexpected::HttpCodeNotAccepted(URL.to_string(), 302, set.to_string()))
+    );
+}
+
+
+#[test]
+fn test_code_class() {
+    let class = PageExpectation::ValidCodeClass("3xx".to_string());
+    assert!(code_story(302, &class).success.is_some());
+    assert!(code_story(404, &class).error.is_some());
+
+}
+
+
+#[test]
+fn test_code_class_malformed() {
+    for class in ["2x", "200", "6xx", "2XX"] {
+        let malformed_class = PageExpectation::ValidCodeClass(class.to_string());
+        assert_eq!(
+            code_story(200, &malformed_class).error,
+            Some(Unexpected::HttpCodeClassMalformed(URL.to_string(), class.to_string()))
+        );
+    }
+}
+
+
+#[test]
+fn test_code_without_connection() {
+    let story = code_story(0, &PageExpectation::ValidCodeRange(200, 299));
+    assert!(matches!(story.error, Some(Unexpected::HttpConnectionFailed(..))));
+}
+
+
+#[test]
+fn test_code_validation_found_among_expectations() {
+    let expectations: PageExpectations =
+        serde_json::from_str(r#"[{"ValidLength": 0}, {"ValidCodeIn": [200, 304]}]"#).unwrap();
+    assert_eq!(
+        MultiChecker::find_code_validation(&expectations),
+        &PageExpectation::ValidCodeIn(vec![200, 304])
+    );
+}
+
+
+#[test]
+fn test_page_no_content_with_accepted_code() {
+    let url = serve_http(|_request| http_response(204, &[], b""));
+    for expectation in [
+        PageExpectation::ValidCode(204),
+        PageExpectation::ValidCodeClass("2xx".to_string()),
+        PageExpectation::ValidCodeIn(vec![200, 204]),
+    ] {
+        let stories = check_page_with_options(
+            &url,
+            vec![expectation],
+            PageOptions {
+                method: Some(Method::Delete),
+                ..PageOptions::default()
+            },
+        );
+        assert!(errors(&stories).is_empty(), "{stories:?}");
+        assert!(stories
+            .iter()
+            .any(|story| story.success == Some(Expected::HttpCode(url.to_string(), 204))));
+    }
+}
+
+
+#[test]
+fn test_page_empty_body_with_unexpected_code() {
+    let url = serve_http(|_request| http_response(503, &[], b""));
+    let stories = check_page(&url, vec![PageExpectation::ValidCode(200)]);
+    assert_eq!(
+        errors(&stories),
+        vec![Unexpected::HandlerFailed(format!("Site is down: {url}"))]
+    );
+}
+
+
+/// Checks single length expectation against given body
+fn length_story(body: &str, expectation: PageExpectation) -> Story {
+    MultiChecker::handle_page_length_expectation(URL, body.as_bytes(), &expectation, None)
+}
+
+
+#[test]
+fn test_length_counts_bytes() {
+    let body = "Zdeněk Miler";
+    assert_eq!(
+        length_story(body, PageExpectation::ValidExactLength(13)).success,
+        Some(Expected::ContentLengthExact(URL.to_string(), 13))
+    );
+    assert_eq!(
+        length_story(body, PageExpectation::ValidExactLength(12)).error,
+        Some(Unexpected::ContentLengthNotExact(URL.to_string(), 13, 12))
+    );
+}
+
+
+#[test]
+fn test_length_minimum_and_maximum() {
+    assert!(length_story(CONTENT, PageExpectation::ValidLength(10))
+        .success
+        .is_some());
+    assert!(length_story(CONTENT, PageExpectation::ValidMaxLength(100))
+        .success
+        .is_some());
+    assert_eq!(
+        length_story(CONTENT, PageExpectation::ValidMaxLength(10)).error,
+        Some(Unexpected::ContentLengthTooLong(URL.to_string(), CONTENT.len(), 10))
+    );
+}
+
+
+#[test]
+fn test_length_range_is_inclusive() {
+    assert!(length_story("12345", PageExpectation::ValidLengthRange(1, 5))
+        .success
+        .is_some());
+    assert_eq!(
+        length_story("123456", PageExpectation::ValidLengthRange(1, 5)).error,
+        Some(Unexpected::ContentLengthOutOfRange(URL.to_string(), 6, 1, 5))
+    );
+}
+
+
+#[test]
+fn test_page_truncated_body() {
+    // server declares more than it sends, then drops the connection:
+    let url = serve_http(|_request| {
+        b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\nConnection: close\r\n\r\n<html><body>Trunc"
+            .to_vec()
+    });
+    let stories = check_page(&url, vec![PageExpectation::ValidCode(200)]);
+    assert_eq!(
+        errors(&stories),
+        vec![Unexpected::ContentLengthMismatch(url, 1000, 17)]
+    );
+}
+
+
+/// Checks digest expectations against given page content
+fn digest_stories(url: &str, content: &str, expectation: &PageExpectation) -> Stories {
+    MultiChecker::handle_page_digest_expectations(
+        url,
+        content.as_bytes(),
+        content,
+        std::slice::from_ref(expectation),
+        None,
+    )
+}
+
+
+/// Removes digest stored by DetectChange expectation of given URL
+fn remove_digest(url: &str, expectation: &PageExpectation) {
+    let digest_file = format!(
+        "{HISTORY_DIR}/krecik-digest-{}.sha256",
+        sha256_hex(format!("{url}{expectation}").as_bytes())
+    );
+    std::fs::remove_file(digest_file).unwrap_or_default();
+}
+
+
+#[test]
+fn test_checksum_is_case_insensitive() {
+    let checksum = sha256_hex(CONTENT.as_bytes()).to_uppercase();
+    let stories = digest_stories(URL, CONTENT, &PageExpectation::ValidChecksum(checksum));
+    assert!(stories[0].success.is_some());
+
+    let stories = digest_stories(
+        URL,
+        "<html><body>Hacked by Mole</body></html>",
+        &PageExpectation::ValidChecksum(sha256_hex(CONTENT.as_bytes())),
+    );
+    assert!(matches!(stories[0].error, Some(Unexpected::ChecksumInvalid(..))));
+}
+
+
+#[test]
+fn test_change_detection_ignores_stripped_content() {
+    let url = "http://localhost/change-detection-stripped";
+    let expectation = PageExpectation::DetectChange(vec![r"\d{2}:\d{2}:\d{2}".to_string()]);
+    remove_digest(url, &expectation);
+
+    let first = digest_stories(url, "Generated at: 12:00:01", &expectation);
+    assert!(first[0].success.is_some());
+    let second = digest_stories(url, "Generated at: 12:00:21", &expectation);
+    assert!(second[0].success.is_some());
+    remove_digest(url, &expectation);
+}
+
+
+#[test]
+fn test_change_detection_reports_change_once() {
+    let url = "http://localhost/change-detection-refresh";
+    let expectation = PageExpectation::DetectChange(vec![]);
+    remove_digest(url, &expectation);
+
+    assert!(digest_stories(url, "Landing page", &expectation)[0]
+        .success
+        .is_some());
+    assert_eq!(
+        digest_stories(url, "Hacked by Mole", &expectation)[0].error,
+        Some(Unexpected::ContentChanged(
+            url.to_string(),
+            sha256_hex(b"Landing page"),
+            sha256_hex(b"Hacked by Mole")
+        ))
+    );
+
+    // digest of changed content is stored, so next run compares against it:
+    assert_eq!(
+        digest_stories(url, "Hacked by Mole", &expectation)[0].success,
+        Some(Expected::ContentUnchanged(url.to_string(), sha256_hex(b"Hacked by Mole")))
+    );
+    remove_digest(url, &expectation);
+}
+
+
+#[test]
+fn test_change_detection_reports_unwritable_digest() {
+    let url = "http://localhost/change-detection-unwritable";
+    let expectation = PageExpectation::DetectChange(vec![]);
+    let digest_file = format!(
+        "{HISTORY_DIR}/krecik-digest-{}.sha256",
+        sha256_hex(format!("{url}{expectation}").as_bytes())
+    );
+    // directory in place of digest file can be neither read nor written:
+    std::fs::create_dir_all(&digest_file).unwrap();
+
+    let stories = digest_stories(url, CONTENT, &expectation);
+    std::fs::remove_dir(&digest_file).unwrap_or_default();
+    assert!(matches!(
+        &stories[0].error,
+        Some(Unexpected::DigestUnwritable(story_url, file, _))
+            if story_url == url && *file == digest_file
+    ));
+}
+
+
+#[test]
+fn test_change_detection_malformed_pattern() {
+    let expectation = PageExpectation::DetectChange(vec![r"(unclosed".to_string()]);
+    let stories = digest_stories(URL, CONTENT, &expectation);
+    assert!(matches!(stories[0].error, Some(Unexpected::RegexMalformed(..))));
+}
+
+
+const POLISH_TEXT: &str = "Zażółć gęślą jaźń";
+
+
+/// Decodes body with given headers
+fn decoded(body: &[u8], headers: &[&str]) -> String {
+    let headers = headers
+        .iter()
+        .map(|header| header.to_string())
+        .collect::<Vec<_>>();
+    MultiChecker::decode_page_content(body, &headers)
+}
+
+
+#[test]
+fn test_decode_charset_of_content_type() {
+    let body = encoding_rs::WINDOWS_1250.encode(POLISH_TEXT).0;
+    assert_eq!(
+        decoded(&body, &["Content-Type: text/html; charset=\"Windows-1250\""]),
+        POLISH_TEXT
+    );
+}
+
+
+#[test]
+fn test_decode_charset_of_meta_tag() {
+    let html = format!("<html><head><meta charset=\"iso-8859-2\"></head>{POLISH_TEXT}</html>");
+    let body = encoding_rs::ISO_8859_2.encode(&html).0;
+    assert_eq!(decoded(&body, &["Content-Type: text/html"]), html);
+}
+
+
+#[test]
+fn test_decode_content_type_charset_before_meta_tag() {
+    let html = format!("<html><head><meta charset=\"iso-8859-2\"></head>{POLISH_TEXT}</html>");
+    let body = encoding_rs::WINDOWS_1250.encode(&html).0;
+    assert_eq!(decoded(&body, &["Content-Type: text/html; charset=windows-1250"]), html);
+}
+
+
+#[test]
+fn test_decode_defaults_to_utf8() {
+    assert_eq!(decoded(POLISH_TEXT.as_bytes(), &[]), POLISH_TEXT);
+    assert_eq!(decoded(b"Krecik \xff", &["Content-Type: text/plain"]), "Krecik \u{fffd}");
+}
+
+
+#[test]
+fn test_page_compressed_content() {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let body = format!("<html><body>{POLISH_TEXT}</body></html>");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    let gzipped_body = encoder.finish().unwrap();
+    let url = serve_http(move |request| {
+        if request.to_lowercase().contains("accept-encoding: ") {
+            http_response(200, &["Content-Encoding: gzip"], &gzipped_body)
+        } else {
+            http_response(406, &[], b"")
+        }
+    });
+
+    // length expectations apply to decompressed body:
+    let stories = check_page(
+        &url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::ValidContent(POLISH_TEXT.to_string()),
+            PageExpectation::ValidExactLength(body.len()),
+        ],
+    );
+    assert!(errors(&stories).is_empty());
+}
+
+
+/// Spawns local HTTP server echoing request method and body
+fn serve_echo() -> String {
+    serve_http(|request| {
+        let method = request.split_whitespace().next().unwrap_or_default();
+        let body = request.split_once("\r\n\r\n").unwrap_or_default().1;
+        http_response(200, &[], format!("{method} {body}").as_bytes())
+    })
+}
+
+
+/// Checks page requested with given method and options, expecting given content
+fn method_stories(url: &str, method: Method, options: PageOptions, expected: &str) -> Stories {
+    check_page_with_options(
+        url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::ValidContent(expected.to_string()),
+        ],
+        PageOptions {
+            method: Some(method),
+            ..options
+        },
+    )
+}
+
+
+#[test]
+fn test_page_methods_without_body() {
+    let url = serve_echo();
+    for (method, expected) in [
+        (Method::Delete, "DELETE "),
+        (Method::Options, "OPTIONS "),
+        (Method::Custom("PURGE".to_string()), "PURGE "),
+    ] {
+        let stories = method_stories(&url, method, PageOptions::default(), expected);
+        assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+    }
+}
+
+
+#[test]
+fn test_page_custom_method_is_sent_verbatim() {
+    let url = serve_http(|request| {
+        assert_eq!(request.lines().next(), Some("PurgeCache / HTTP/1.1"));
+        http_response(200, &[], b"Purged")
+    });
+    let stories = method_stories(
+        &url,
+        Method::Custom("PurgeCache".to_string()),
+        PageOptions::default(),
+        "Purged",
+    );
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+}
+
+
+#[test]
+fn test_page_methods_with_post_data() {
+    let url = serve_echo();
+    let methods = [(Method::Patch, r#"{"patch": 1}"#), (Method::Put, "put-data")];
+    for (method, post_data) in methods {
+        let options = PageOptions {
+            post_data: Some(post_data.to_string()),
+            ..PageOptions::default()
+        };
+        let expected = format!("{} {post_data}", method.name());
+        let stories = method_stories(&url, method, options, &expected);
+        assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+    }
+}
+
+
+#[test]
+fn test_page_head_without_body() {
+    let url = serve_http(|_request| http_response(200, &[], b""));
+    let stories = check_page_with_options(
+        &url,
+        vec![PageExpectation::ValidCode(200)],
+        PageOptions {
+            method: Some(Method::Head),
+            ..PageOptions::default()
+        },
+    );
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+}
+
+
+#[test]
+fn test_page_body_file() {
+    let url = serve_echo();
+    let body_file = fixture_file("body.json", br#"{"from": "file"}"#);
+    let options = PageOptions {
+        body_file: Some(body_file.to_string()),
+        post_data: Some("ignored".to_string()),
+        ..PageOptions::default()
+    };
+    let stories = method_stories(&url, Method::Post, options, r#"POST {"from": "file"}"#);
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+}
+
+
+#[test]
+fn test_page_body_file_unreadable() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counted_requests = requests.clone();
+    let url = serve_http(move |_request| {
+        counted_requests.fetch_add(1, Ordering::SeqCst);
+        http_response(200, &[], b"")
+    });
+    let options = PageOptions {
+        body_file: Some("/nonexistent/krecik-body.json".to_string()),
+        ..PageOptions::default()
+    };
+    let stories = method_stories(&url, Method::Post, options, "POST");
+    assert!(matches!(
+        &errors(&stories)[..],
+        [Unexpected::RequestBodyUnreadable(an_url, details)]
+            if an_url == &url && details.contains("/nonexistent/krecik-body.json")
+    ));
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+}
+
+
+/// Spawns local HTTP server echoing Authorization header
+fn serve_authorization_echo(requests: Arc<AtomicUsize>) -> String {
+    serve_http(move |request| {
+        requests.fetch_add(1, Ordering::SeqCst);
+        let authorization = request
+            .lines()
+            .filter_map(|line| line.split_once(": "))
+            .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+            .map(|(_, value)| value.to_string())
+            .unwrap_or_default();
+        http_response(200, &[], format!("auth={authorization}").as_bytes())
+    })
+}
+
+
+/// Checks page with given authentication, expecting given Authorization header
+fn auth_stories(url: &str, auth: PageAuth, expected: &str) -> Stories {
+    check_page_with_options(
+        url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::ValidContent(format!("auth={expected}")),
+        ],
+        PageOptions {
+            auth: Some(auth),
+            ..PageOptions::default()
+        },
+    )
+}
+
+
+#[test]
+fn test_page_basic_auth() {
+    let url = serve_authorization_echo(Arc::new(AtomicUsize::new(0)));
+    std::env::set_var("KRECIK_TEST_BASIC_PASSWORD", "s3cr3t");
+    let auth = PageAuth::Basic {
+        username: "krecik".to_string(),
+        password: Secret::Env("KRECIK_TEST_BASIC_PASSWORD".to_string()),
+    };
+    let stories = auth_stories(&url, auth, "Basic a3JlY2lrOnMzY3IzdA==");
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+}
+
+
+#[test]
+fn test_page_bearer_auth() {
+    let url = serve_authorization_echo(Arc::new(AtomicUsize::new(0)));
+    let auth = PageAuth::Bearer {
+        token: Secret::Value("inline-token".to_string()),
+    };
+    let stories = auth_stories(&url, auth, "Bearer inline-token");
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+}
+
+
+#[test]
+fn test_page_auth_missing_secret() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let url = serve_authorization_echo(requests.clone());
+    let auth = PageAuth::Bearer {
+        token: Secret::Env("KRECIK_TEST_UNDEFINED_TOKEN".to_string()),
+    };
+    let stories = auth_stories(&url, auth, "");
+    assert!(matches!(
+        &errors(&stories)[..],
+        [Unexpected::AuthenticationFailed(an_url, details)]
+            if an_url == &url && details.contains("KRECIK_TEST_UNDEFINED_TOKEN")
+    ));
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+}
+
+
+/// Spawns HTTPS server of private CA, requiring client certificate. Returns its URL,
+/// options of page authenticating with passphrase protected client key, server public
+/// key pin and written files
+fn serve_mutual_tls(name: &str) -> (String, PageOptions, String, Vec<FixtureFile>) {
+    let (ca, ca_key) = issue_certificate("Krecik Test CA", None);
+    let (server_cert, server_key) = issue_certificate("localhost", Some((&ca, &ca_key)));
+    let (client_cert, client_key) = issue_certificate("krecik", Some((&ca, &ca_key)));
+    let url = serve_https(&server_cert, &server_key, Some(&ca), |_| {
+        http_response(200, &[], b"mutual-tls-content")
+    });
+    let ca_bundle = fixture_file(&format!("{name}-ca.pem"), &ca.to_pem().unwrap());
+    let client_cert_file =
+        fixture_file(&format!("{name}-client.pem"), &client_cert.to_pem().unwrap());
+    let client_key_file = fixture_file(
+        &format!("{name}-client.key"),
+        &client_key
+            .private_key_to_pem_pkcs8_passphrase(Cipher::aes_256_cbc(), b"passphrase")
+            .unwrap(),
+    );
+    let options = PageOptions {
+        client_cert: Some(client_cert_file.to_string()),
+        client_key: Some(client_key_file.to_string()),
+        client_key_passphrase: Some(Secret::Value("passphrase".to_string())),
+        ca_bundle: Some(ca_bundle.to_string()),
+        ..PageOptions::default()
+    };
+    let server_pin = format!(
+        "sha256//{}",
+        base64::encode_block(&sha256(&server_key.public_key_to_der().unwrap()))
+    );
+    (url, options, server_pin, vec![ca_bundle, client_cert_file, client_key_file])
+}
+
+
+/// Checks mutual TLS page with given options, removes written files
+fn mutual_tls_stories(url: &str, options: PageOptions, files: Vec<FixtureFile>) -> Stories {
+    let stories = check_page_with_options(
+        url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::ValidContent("mutual-tls-content".to_string()),
+        ],
+        options,
+    );
+    drop(files);
+    stories
+}
+
+
+#[test]
+fn test_page_mutual_tls_with_pinned_key() {
+    let (url, options, server_pin, files) = serve_mutual_tls("mtls-valid");
+    std::env::set_var("KRECIK_TEST_CLIENT_KEY_PASSPHRASE", "passphrase");
+    let options = PageOptions {
+        client_key_passphrase: Some(Secret::Env(
+            "KRECIK_TEST_CLIENT_KEY_PASSPHRASE".to_string(),
+        )),
+        pinned_public_key: Some(server_pin),
+        ..options
+    };
+    let stories = mutual_tls_stories(&url, options, files);
+    assert_eq!(stories.len(), 4);
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+}
+
+
+#[test]
+fn test_page_private_ca_without_bundle() {
+    let (url, options, _, files) = serve_mutual_tls("mtls-system-ca");
+    let options = PageOptions {
+        ca_bundle: None,
+        ..options
+    };
+    let stories = mutual_tls_stories(&url, options, files);
+    assert!(matches!(
+        stories[0].error,
+        Some(Unexpected::TLSPeerVerificationFailed(..))
+    ));
+}
+
+
+#[test]
+fn test_page_ca_bundle_broken() {
+    let (url, options, _, mut files) = serve_mutual_tls("mtls-broken-ca");
+    let broken_ca_bundle = fixture_file("mtls-broken-ca.pem", b"not a certificate");
+    let options = PageOptions {
+        ca_bundle: Some(broken_ca_bundle.to_string()),
+        ..options
+    };
+    files.push(broken_ca_bundle);
+    let stories = mutual_tls_stories(&url, options, files);
+    assert!(matches!(stories[0].error, Some(Unexpected::TLSCaBundleInvalid(..))));
+}
+
+
+#[test]
+fn test_page_client_key_passphrase_wrong() {
+    let (url, options, _, files) = serve_mutual_tls("mtls-wrong-passphrase");
+    let options = PageOptions {
+        client_key_passphrase: Some(Secret::Value("wrong".to_string())),
+        ..options
+    };
+    let stories = mutual_tls_stories(&url, options, files);
+    assert!(matches!(
+        stories[0].error,
+        Some(Unexpected::TLSClientCertificateInvalid(..))
+    ));
+}
+
+
+#[test]
+fn test_page_client_key_passphrase_missing() {
+    let (url, options, _, files) = serve_mutual_tls("mtls-missing-passphrase");
+    let options = PageOptions {
+        client_key_passphrase: Some(Secret::Env(
+            "KRECIK_TEST_UNDEFINED_PASSPHRASE".to_string(),
+        )),
+        ..options
+    };
+    let stories = mutual_tls_stories(&url, options, files);
+    assert!(matches!(
+        &errors(&stories)[..],
+        [Unexpected::ClientKeyPassphraseUnavailable(an_url, details)]
+            if an_url == &url && details.contains("KRECIK_TEST_UNDEFINED_PASSPHRASE")
+    ));
+}
+
+
+#[test]
+fn test_page_pinned_key_mismatch() {
+    let (url, options, _, files) = serve_mutual_tls("mtls-pinned");
+    let options = PageOptions {
+        pinned_public_key: Some(
+            "sha256//AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".to_string(),
+        ),
+        ..options
+    };
+    let stories = mutual_tls_stories(&url, options, files);
+    assert!(matches!(stories[0].error, Some(Unexpected::TLSPinnedKeyMismatch(..))));
+}
+
+
+/// Spawns local HTTP server echoing request line and Host header. Returns its URL and port
+fn serve_request_line_echo() -> (String, u16) {
+    let url = serve_http(|request| {
+        let request_line = request.lines().next().unwrap_or_default();
+        let host = request
+            .lines()
+            .find(|line| line.to_lowercase().starts_with("host:"))
+            .unwrap_or_default();
+        http_response(200, &[], format!("{request_line}\n{host}").as_bytes())
+    });
+    let port = Url::parse(&url).unwrap().port().unwrap();
+    (url, port)
+}
+
+
+/// Checks page with given options, expecting given content
+fn network_stories(url: &str, expected: &str, options: PageOptions) -> Stories {
+    check_page_with_options(
+        url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::ValidContent(expected.to_string()),
+        ],
+        options,
+    )
+}
+
+
+#[test]
+fn test_page_resolve_override() {
+    let (_, port) = serve_request_line_echo();
+    let stories = network_stories(
+        &format!("http://krecik-resolve.test:{port}/"),
+        &format!("Host: krecik-resolve.test:{port}"),
+        PageOptions {
+            resolve: Some(vec![format!("krecik-resolve.test:{port}:127.0.0.1")]),
+            ip_version: Some(IpVersion::V4),
+            interface: Some("127.0.0.1".to_string()),
+            ..PageOptions::default()
+        },
+    );
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+    for story in stories {
+        assert_eq!(story.remote_address, Some(format!("127.0.0.1:{port}")));
+    }
+}
+
+
+#[test]
+fn test_page_connect_to_override() {
+    let (_, port) = serve_request_line_echo();
+    let stories = network_stories(
+        "http://krecik-connect.test/",
+        "Host: krecik-connect.test",
+        PageOptions {
+            connect_to: Some(vec![format!("krecik-connect.test:80:127.0.0.1:{port}")]),
+            ..PageOptions::default()
+        },
+    );
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+}
+
+
+#[test]
+fn test_page_proxy() {
+    let (url, port) = serve_request_line_echo();
+    let stories = network_stories(
+        "http://krecik-proxied.test/path",
+        "GET http://krecik-proxied.test/path HTTP/1.1",
+        PageOptions {
+            proxy: Some(url),
+            ..PageOptions::default()
+        },
+    );
+    assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+    for story in stories {
+        assert_eq!(story.remote_address, Some(format!("127.0.0.1:{port}")));
+    }
+}
+
+
+/// Page with fan_out_dns set and given options
+fn fanned_out_page(url: &str, options: PageOptions) -> Page {
+    Page {
+        url: url.to_string(),
+        expects: vec![PageExpectation::ValidCode(200)],
+        options: Some(options),
+        fan_out_dns: Some(true),
+    }
+}
+
+
+/// Addresses and resolve overrides of fanned out page
+fn fanned_out(page: &Page) -> Vec<(Option<IpAddr>, Option<Vec<String>>)> {
+    MultiChecker::fan_out_page(page)
+        .into_iter()
+        .map(|(page, address)| (address, page.options.and_then(|options| options.resolve)))
+        .collect()
+}
+
+
+#[test]
+fn test_fan_out_disabled() {
+    let page = Page {
+        fan_out_dns: None,
+        ..fanned_out_page("http://localhost/", PageOptions::default())
+    };
+    assert_eq!(MultiChecker::fan_out_page(&page), vec![(page, None)]);
+}
+
+
+#[test]
+fn test_fan_out_pinned_addresses() {
+    let page = fanned_out_page(
+        "http://krecik-fanout.test/",
+        PageOptions {
+            resolve: Some(vec![
+                "other.test:80:127.0.0.9".to_string(),
+                "krecik-fanout.test:80:127.0.0.2,[::1],127.0.0.1".to_string(),
+            ]),
+            ..PageOptions::default()
+        },
+    );
+    let override_of = |address: &str| {
+        Some(vec![
+            "other.test:80:127.0.0.9".to_string(),
+            format!("krecik-fanout.test:80:{address}"),
+        ])
+    };
+    assert_eq!(
+        fanned_out(&page),
+        vec![
+            (Some("127.0.0.1".parse().unwrap()), override_of("127.0.0.1")),
+            (Some("127.0.0.2".parse().unwrap()), override_of("127.0.0.2")),
+            (Some("::1".parse().unwrap()), override_of("[::1]")),
+        ]
+    );
+}
+
+
+#[test]
+fn test_fan_out_pinned_addresses_of_ip_version() {
+    let page = |ip_version| {
+        fanned_out_page(
+            "http://krecik-fanout.test/",
+            PageOptions {
+                resolve: Some(vec!["krecik-fanout.test:80:127.0.0.1,[::1]".to_string()]),
+                ip_version: Some(ip_version),
+                ..PageOptions::default()
+            },
+        )
+    };
+    let addresses = |page: &Page| {
+        fanned_out(page)
+            .into_iter()
+            .map(|(address, _)| address)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        addresses(&page(IpVersion::V4)),
+        vec![Some("127.0.0.1".parse().unwrap())]
+    );
+    assert_eq!(addresses(&page(IpVersion::V6)), vec![Some("::1".parse().unwrap())]);
+}
+
+
+#[test]
+fn test_fan_out_resolved_addresses_of_ip_version() {
+    let page = fanned_out_page(
+        "http://localhost/",
+        PageOptions {
+            ip_version: Some(IpVersion::V4),
+            ..PageOptions::default()
+        },
+    );
+    let fanned_out_pages = fanned_out(&page);
+    assert!(!fanned_out_pages.is_empty());
+    for (address, resolve) in fanned_out_pages {
+        let address = address.unwrap();
+        assert!(address.is_ipv4());
+        assert_eq!(resolve, Some(vec![format!("localhost:80:{address}")]));
+    }
+}
+
+
+#[test]
+fn test_page_fan_out_stories_of_each_address() {
+    let node_a = serve_http(|_| http_response(200, &[], b"node-a"));
+    let port = Url::parse(&node_a).unwrap().port().unwrap();
+    serve_http_on(&format!("127.0.0.2:{port}"), |_| http_response(200, &[], b"node-b"));
+    // 127.0.0.3 is a dead node:
+    let url = format!("http://krecik-fanout.test:{port}/");
+    let stories = MultiChecker::check_pages(&[Check {
+        pages: Some(vec![fanned_out_page(
+            &url,
+            PageOptions {
+                resolve: Some(vec![format!(
+                    "krecik-fanout.test:{port}:127.0.0.1,127.0.0.2,127.0.0.3"
+                )]),
+                ..PageOptions::default()
+            },
+        )]),
+        ..Check::default()
+    }]);
+    let (failures, successes): (Vec<_>, Vec<_>) =
+        stories.into_iter().partition(|story| story.error.is_some());
+    assert_eq!(failures.len(), 1, "Unexpected failures: {failures:?}");
+    assert!(failures[0]
+        .error
+        .as_ref()
+        .unwrap()
+        .to_string()
+        .contains(&format!("{url} (127.0.0.3)")));
+    assert_eq!(
+        successes
+            .iter()
+            .filter(|story| matches!(story.success, Some(Expected::HttpCode(..))))
+            .map(|story| (story.success.clone(), story.remote_address.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Some(Expected::HttpCode(format!("{url} (127.0.0.1)"), 200)),
+                Some(format!("127.0.0.1:{port}"))
+            ),
+            (
+                Some(Expected::HttpCode(format!("{url} (127.0.0.2)"), 200)),
+                Some(format!("127.0.0.2:{port}"))
+            ),
+        ]
+    );
+}
+
+
+/// Checks HTTP version expectation against given status line
+fn http_version_story(status_line: &str, expected: &str) -> Story {
+    MultiChecker::handle_page_http_version_expectations(
+        URL,
+        &[status_line.to_string(), "Content-Type: text/html".to_string()],
+        &[PageExpectation::ValidHttpVersion(expected.to_string())],
+        None,
+    )
+    .remove(0)
+}
+
+
+#[test]
+fn test_http_version_of_status_line() {
+    assert_eq!(
+        http_version_story("HTTP/2 200", "2.0").success,
+        Some(Expected::HttpVersion(URL.to_string(), "2".to_string()))
+    );
+    assert!(http_version_story("HTTP/1.0 200 OK", "1.0").success.is_some());
+    assert_eq!(
+        http_version_story("HTTP/1.1 200 OK", "2").error,
+        Some(Unexpected::HttpVersionMismatch(
+            URL.to_string(),
+            "1.1".to_string(),
+            "2".to_string()
+        ))
+    );
+}
+
+
+#[test]
+fn test_page_http_version_option() {
+    // respond with the HTTP version of the request:
+    let url = serve_http(|request| {
+        let request_line = request.lines().next().unwrap_or_default().to_string();
+        let version = request_line.rsplit(' ').next().unwrap_or_default().to_string();
+        let response = http_response(200, &[], request_line.as_bytes());
+        [version.as_bytes(), &response["HTTP/1.1".len()..]].concat()
+    });
+    for (http_version, expected) in [
+        (Some(HttpVersion::Http10), "1.0"),
+        (Some(HttpVersion::Http11), "1.1"),
+        (None, "1.1"),
+    ] {
+        let stories = check_page_with_options(
+            &url,
+            vec![
+                PageExpectation::ValidCode(200),
+                PageExpectation::ValidHttpVersion(expected.to_string()),
+            ],
+            PageOptions {
+                http_version,
+                ..PageOptions::default()
+            },
+        );
+        assert!(errors(&stories).is_empty(), "Unexpected failure: {stories:?}");
+    }
+}
+
+
+/// Redirect chain of given (url, code) hops
+fn redirect_chain(hops: &[(&str, u32)]) -> Vec<RedirectHop> {
+    hops.iter()
+        .map(|(url, code)| {
+            RedirectHop {
+                url: url.to_string(),
+                code: *code,
+            }
+        })
+        .collect()
+}
+
+
+/// Checks single redirect expectation against given chain
+fn redirect_story(chain: &[(&str, u32)], expectation: PageExpectation) -> Story {
+    MultiChecker::handle_page_redirect_expectations(
+        URL,
+        &redirect_chain(chain),
+        &[expectation],
+        None,
+    )
+    .remove(0)
+}
+
+
+const HTTPS_REDIRECT: [(&str, u32); 3] = [
+    ("http://localhost/", 301),
+    ("https://localhost/", 302),
+    ("https://localhost/home", 200),
+];
+
+
+#[test]
+fn test_redirect_count() {
+    let story = redirect_story(&HTTPS_REDIRECT, PageExpectation::ValidRedirectCount(2));
+    assert_eq!(story.success, Some(Expected::RedirectCount(URL.to_string(), 2, 2)));
+
+    let story = redirect_story(&HTTPS_REDIRECT, PageExpectation::ValidRedirectCount(1));
+    assert!(matches!(story.error, Some(Unexpected::RedirectCountExceeded(_, 2, 1, _))));
+}
+
+
+#[test]
+fn test_redirect_chain() {
+    let story = redirect_story(
+        &HTTPS_REDIRECT,
+        PageExpectation::ValidRedirectChain(redirect_chain(&HTTPS_REDIRECT)),
+    );
+    assert!(story.success.is_some());
+
+    let story = redirect_story(
+        &HTTPS_REDIRECT,
+        PageExpectation::ValidRedirectChain(redirect_chain(&HTTPS_REDIRECT[1..])),
+    );
+    assert!(matches!(story.error, Some(Unexpected::RedirectChainMismatch(..))));
+}
+
+
+#[test]
+fn test_https_redirect_required() {
+    let story = redirect_story(&HTTPS_REDIRECT, PageExpectation::RequireHttpsRedirect);
+    assert!(story.success.is_some());
+
+    for chain in [
+        // no redirect:
+        &[("http://localhost/", 200)][..],
+        // temporary redirect:
+        &[("http://localhost/", 302), ("https://localhost/", 200)][..],
+        // redirect to other plain HTTP page:
+        &[("http://localhost/", 301), ("http://localhost/home", 200)][..],
+        // checked page is HTTPS already:
+        &[("https://localhost/", 200)][..],
+    ] {
+        let story = redirect_story(chain, PageExpectation::RequireHttpsRedirect);
+        assert!(matches!(story.error, Some(Unexpected::HttpsRedirectMissing(..))));
+    }
+}
+
+
+#[test]
+fn test_https_redirect_back_to_plain_http() {
+    let story = redirect_story(
+        &[
+            ("http://localhost/", 301),
+            ("https://localhost/", 301),
+            ("http://localhost/end", 200),
+        ],
+        PageExpectation::RequireHttpsRedirect,
+    );
+    assert!(matches!(
+        story.error,
+        Some(Unexpected::HttpsRedirectMissing(_, ref details))
+            if details.contains("back to plain HTTP")
+    ));
+}
+
+
+#[test]
+fn test_page_redirect_chain_recorded() {
+    let (ca, ca_key) = issue_certificate("Krecik Test CA", None);
+    let (server_cert, server_key) = issue_certificate("localhost", Some((&ca, &ca_key)));
+    let ca_bundle = fixture_file("redirect-ca.pem", &ca.to_pem().unwrap());
+    let https_url = serve_https(&server_cert, &server_key, None, |request| {
+        match request.split_whitespace().nth(1).unwrap_or_default() {
+            "/" => http_response(302, &["Location: /home"], b""),
+            _ => http_response(200, &[], b"secure-content"),
+        }
+    });
+    let location = format!("Location: {https_url}");
+    let http_url = serve_http(move |_request| http_response(301, &[&location], b""));
+    let stories = check_page_with_options(
+        &http_url,
+        vec![
+            PageExpectation::ValidCode(200),
+            PageExpectation::RequireHttpsRedirect,
+        ],
+        PageOptions {
+            ca_bundle: Some(ca_bundle.to_string()),
+            ..PageOptions::default()
+        },
+    );
+    assert!(errors(&stories).is_empty(), "Unexpected failures: {stories:?}");
+    assert_eq!(
+        stories[0].redirect_chain,
+        Some(redirect_chain(&[
+            (&http_url, 301),
+            (&https_url, 302),
+            (&format!("{https_url}home"), 200),
+        ]))
+    );
+}
+
+
+#[test]
+fn test_page_redirect_loop() {
+    let url = serve_http(|_request| http_response(301, &["Location: /loop"], b""));
+    let stories = check_page(&url, vec![PageExpectation::ValidCode(200)]);
+    assert!(matches!(&stories[..], [Story { error: Some(Unexpected::RedirectLoop(..)), .. }]));
+}
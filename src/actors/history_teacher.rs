@@ -1,4 +1,4 @@
-use crate::{actors::results_warden::*, debug, utilities, Notificator, Stories};
+use crate::{actors::results_warden::*, debug, utilities, Notificator, Stories, HISTORY_DIR};
 use actix::prelude::*;
 use chrono::Local;
 
@@ -26,7 +26,7 @@ impl Handler<Results> for HistoryTeacher {
             .join(",");
         let history_json = format!("[{}]", stories_listof_json);
         let timestamp = Local::now().to_rfc3339();
-        let stories_output = format!("/tmp/krecik-history-{}.json", timestamp);
+        let stories_output = format!("{HISTORY_DIR}/krecik-history-{timestamp}.json");
         debug!("Storing check result stories to file: {}", stories_output);
         utilities::write_append(&stories_output, &history_json);
         // then send message to ResultsWarden to validate results after stories were saved to a file
@@ -1,5 +1,12 @@
 use super::generic_checker::GenericChecker;
-use crate::{checks::check::*, products::story::*, trace};
+use crate::{
+    checks::{
+        check::*, database::check_databases, dns::check_dns,
+        email_domain::check_email_domains, ssh::check_ssh, tcp::check_tcp,
+    },
+    products::story::*,
+    trace,
+};
 use actix::prelude::*;
 
 
@@ -20,9 +27,29 @@ impl Handler<Checks> for MultiChecker {
     fn handle(&mut self, checks: Checks, _ctx: &mut Self::Context) -> Self::Result {
         let stories_from_domains = Self::check_domains(&checks.0);
         trace!("stories_from_domains: {:?}", stories_from_domains);
-        let stories_from_pages = Self::check_pages(&checks.0);
+        // pages and TCP services are checked concurrently:
+        let (stories_from_pages, stories_from_tcp) =
+            rayon::join(|| Self::check_pages(&checks.0), || check_tcp(&checks.0));
         trace!("stories_from_pages: {:?}", stories_from_pages);
-        Ok([stories_from_domains, stories_from_pages].concat())
+        trace!("stories_from_tcp: {:?}", stories_from_tcp);
+        let stories_from_dns = check_dns(&checks.0);
+        trace!("stories_from_dns: {:?}", stories_from_dns);
+        let stories_from_ssh = check_ssh(&checks.0);
+        trace!("stories_from_ssh: {:?}", stories_from_ssh);
+        let stories_from_databases = check_databases(&checks.0);
+        trace!("stories_from_databases: {:?}", stories_from_databases);
+        let stories_from_email_domains = check_email_domains(&checks.0);
+        trace!("stories_from_email_domains: {:?}", stories_from_email_domains);
+        Ok([
+            stories_from_domains,
+            stories_from_pages,
+            stories_from_tcp,
+            stories_from_dns,
+            stories_from_ssh,
+            stories_from_databases,
+            stories_from_email_domains,
+        ]
+        .concat())
     }
 }
 
@@ -1,6 +1,7 @@
 use crate::{
     utilities::{produce_list_absolute, read_text_file},
-    Notificator, Notify, Stories, STORIES_TO_KEEP_COUNT, STORIES_TO_VALIDATE_COUNT,
+    Notificator, Notify, Stories, HISTORY_DIR, STORIES_TO_KEEP_COUNT,
+    STORIES_TO_VALIDATE_COUNT,
 };
 use actix::prelude::*;
 use std::fs;
@@ -23,8 +24,8 @@ impl Handler<ValidateResults> for ResultsWarden {
 
     fn handle(&mut self, val: ValidateResults, _ctx: &mut Self::Context) -> Self::Result {
         debug!("ResultsWarden validates results…");
-        let stories_glob = "/tmp/krecik-history-*.json";
-        let files_list = produce_list_absolute(stories_glob)
+        let stories_glob = format!("{HISTORY_DIR}/krecik-history-*.json");
+        let files_list = produce_list_absolute(&stories_glob)
             .into_iter()
             .rev()
             .take(STORIES_TO_VALIDATE_COUNT)
@@ -56,7 +57,7 @@ impl Handler<ValidateResults> for ResultsWarden {
                 "Validating last stories from {STORIES_TO_VALIDATE_COUNT} recent files: {files_list:?}"
             );
 
-            let old_files_list = produce_list_absolute(stories_glob)
+            let old_files_list = produce_list_absolute(&stories_glob)
                 .into_iter()
                 .rev()
                 .skip(STORIES_TO_KEEP_COUNT)
@@ -2,9 +2,9 @@ use crate::*;
 use rayon::prelude::*;
 
 
-/**
+/*
    Public library API for Krecik remote-checks functionality
-**/
+*/
 
 /// Return checks from path, excluding remotes
 #[instrument]
@@ -45,6 +45,11 @@ pub fn all_checks_pongo_merged() -> Vec<Check> {
             Check {
                 pages: Some(pongo_checks),
                 domains: Some(domain_checks),
+                dns: None,
+                email_domains: None,
+                tcp: None,
+                ssh: None,
+                databases: None,
                 notifier: mapper.notifier,
             }
         })
@@ -0,0 +1,291 @@
+use crate::*;
+use curl::easy::{Auth, Easy2};
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Debug, Formatter},
+    io::{Error, ErrorKind},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+
+/// OAuth2 tokens cache: (token url, client id, scope) => (access token, expiry)
+type OAuth2Tokens = HashMap<(String, String, String), (String, Instant)>;
+
+
+lazy_static! {
+    /// Cache of OAuth2 tokens
+    static ref OAUTH2_TOKENS: Mutex<OAuth2Tokens> = Mutex::new(HashMap::new());
+}
+
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Secret value - read from environment variable, file, or given inline
+pub enum Secret {
+    /// Name of environment variable holding the secret
+    Env(String),
+
+    /// Path to a file holding the secret
+    File(String),
+
+    /// Inline secret value (not recommended)
+    Value(String),
+}
+
+
+/// Inline secret values are never printed (ex. by tracing)
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Secret::Env(variable) => f.debug_tuple("Env").field(variable).finish(),
+            Secret::File(path) => f.debug_tuple("File").field(path).finish(),
+            Secret::Value(_) => f.write_str("Value(<redacted>)"),
+        }
+    }
+}
+
+
+impl Secret {
+    /// Read the secret value
+    #[instrument(skip(self))]
+    pub fn resolve(&self) -> Result<String, Error> {
+        match self {
+            Secret::Env(variable) => {
+                env::var(variable).map_err(|err| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!("Environment variable: {variable} is unavailable: {err}"),
+                    )
+                })
+            }
+            Secret::File(path) => {
+                read_text_file(path)
+                    .map(|contents| contents.trim().to_string())
+                    .map_err(|err| {
+                        Error::new(
+                            err.kind(),
+                            format!("Secret file: {path} is unreadable: {err}"),
+                        )
+                    })
+            }
+            Secret::Value(value) => Ok(value.to_string()),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// Page check authentication schemes
+pub enum PageAuth {
+    /// HTTP Basic authentication
+    Basic {
+        /// User name
+        username: String,
+
+        /// Password
+        password: Secret,
+    },
+
+    /// HTTP Digest authentication
+    Digest {
+        /// User name
+        username: String,
+
+        /// Password
+        password: Secret,
+    },
+
+    /// Bearer token authentication
+    Bearer {
+        /// Token
+        token: Secret,
+    },
+
+    /// AWS Signature Version 4 (signed by Curl)
+    AwsSigV4 {
+        /// Provider, ex. "aws:amz:eu-west-1:s3"
+        provider: String,
+
+        /// Access key id
+        access_key: Secret,
+
+        /// Secret access key
+        secret_key: Secret,
+    },
+
+    /// OAuth2 client-credentials grant. Token is fetched and cached before the check
+    OAuth2 {
+        /// Token endpoint URL
+        token_url: String,
+
+        /// Client id
+        client_id: String,
+
+        /// Client secret
+        client_secret: Secret,
+
+        /// Requested scope
+        #[serde(skip_serializing_if = "Option::is_none")]
+        scope: Option<String>,
+    },
+}
+
+
+#[derive(Clone, PartialEq, Eq)]
+/// Authentication with all secrets resolved, ready to be passed to Curl
+pub enum ResolvedAuth {
+    /// HTTP Basic (username, password)
+    Basic(String, String),
+
+    /// HTTP Digest (username, password)
+    Digest(String, String),
+
+    /// Bearer token
+    Bearer(String),
+
+    /// AWS SigV4 (provider, access key, secret key)
+    AwsSigV4(String, String, String),
+}
+
+
+/// Resolved secrets are never printed (ex. by tracing)
+impl Debug for ResolvedAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolvedAuth::Basic(username, _) => write!(f, "Basic({username:?}, <redacted>)"),
+            ResolvedAuth::Digest(username, _) => write!(f, "Digest({username:?}, <redacted>)"),
+            ResolvedAuth::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            ResolvedAuth::AwsSigV4(provider, ..) => {
+                write!(f, "AwsSigV4({provider:?}, <redacted>, <redacted>)")
+            }
+        }
+    }
+}
+
+
+impl PageAuth {
+    /// Resolve all secrets. OAuth2 token is fetched (or taken from cache)
+    #[instrument(skip(self))]
+    pub fn resolve(&self) -> Result<ResolvedAuth, Error> {
+        match self {
+            PageAuth::Basic { username, password } => {
+                Ok(ResolvedAuth::Basic(username.to_string(), password.resolve()?))
+            }
+            PageAuth::Digest { username, password } => {
+                Ok(ResolvedAuth::Digest(username.to_string(), password.resolve()?))
+            }
+            PageAuth::Bearer { token } => Ok(ResolvedAuth::Bearer(token.resolve()?)),
+            PageAuth::AwsSigV4 {
+                provider,
+                access_key,
+                secret_key,
+            } => {
+                Ok(ResolvedAuth::AwsSigV4(
+                    provider.to_string(),
+                    access_key.resolve()?,
+                    secret_key.resolve()?,
+                ))
+            }
+            PageAuth::OAuth2 {
+                token_url,
+                client_id,
+                client_secret,
+                scope,
+            } => {
+                oauth2_token(
+                    token_url,
+                    client_id,
+                    &client_secret.resolve()?,
+                    &scope.clone().unwrap_or_default(),
+                )
+                .map(ResolvedAuth::Bearer)
+            }
+        }
+    }
+}
+
+
+/// Get OAuth2 access token using client-credentials grant. Tokens are cached until they expire
+#[instrument(skip(client_secret))]
+pub fn oauth2_token(
+    token_url: &str,
+    client_id: &str,
+    client_secret: &str,
+    scope: &str,
+) -> Result<String, Error> {
+    let cache_key = (
+        token_url.to_string(),
+        client_id.to_string(),
+        scope.to_string(),
+    );
+    if let Some((token, expiry)) = OAUTH2_TOKENS
+        .lock()
+        .map_err(|err| Error::other(err.to_string()))?
+        .get(&cache_key)
+    {
+        if Instant::now() < *expiry {
+            trace!("Using cached OAuth2 token for client: {client_id}");
+            return Ok(token.to_string());
+        }
+    }
+
+    debug!("Fetching OAuth2 token for client: {client_id} from: {token_url}");
+    let curl_error = |err: curl::Error| Error::other(format!("{token_url}: {err}"));
+    let mut curl = Easy2::new(Collector::default());
+    let mut form = format!(
+        "grant_type=client_credentials&client_id={}",
+        curl.url_encode(client_id.as_bytes())
+    );
+    if !scope.is_empty() {
+        form.push_str(&format!("&scope={}", curl.url_encode(scope.as_bytes())));
+    }
+    curl.url(token_url).map_err(curl_error)?;
+    curl.timeout(Duration::from_secs(CHECK_TIMEOUT))
+        .map_err(curl_error)?;
+    curl.username(client_id).map_err(curl_error)?;
+    curl.password(client_secret).map_err(curl_error)?;
+    curl.http_auth(Auth::new().basic(true))
+        .map_err(curl_error)?;
+    curl.post(true).map_err(curl_error)?;
+    curl.post_fields_copy(form.as_bytes()).map_err(curl_error)?;
+    curl.perform().map_err(curl_error)?;
+
+    let response_code = curl.response_code().map_err(curl_error)?;
+    let response = String::from_utf8_lossy(&curl.get_ref().0).to_string();
+    if response_code != CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("OAuth2 token endpoint: {token_url} returned error: {response_code}"),
+        ));
+    }
+    let token_response: serde_json::Value = serde_json::from_str(&response)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let token = token_response["access_token"]
+        .as_str()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("OAuth2 token endpoint: {token_url} returned no access_token"),
+            )
+        })?
+        .to_string();
+    let expires_in = token_response["expires_in"]
+        .as_u64()
+        .unwrap_or(CHECK_OAUTH2_DEFAULT_TOKEN_TTL);
+
+    // refresh the token a bit before it expires
+    let expiry = Instant::now()
+        + Duration::from_secs(expires_in.saturating_sub(CHECK_OAUTH2_TOKEN_REFRESH_MARGIN));
+    OAUTH2_TOKENS
+        .lock()
+        .map_err(|err| Error::other(err.to_string()))?
+        .insert(cache_key, (token.to_string(), expiry));
+    Ok(token)
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
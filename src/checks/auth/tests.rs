@@ -0,0 +1,98 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use super::*;
+use crate::test_fixtures::*;
+
+
+#[test]
+fn test_secret_from_env_and_file() {
+    env::set_var("KRECIK_TEST_SECRET", "s3cr3t");
+    assert_eq!(Secret::Env("KRECIK_TEST_SECRET".to_string()).resolve().unwrap(), "s3cr3t");
+
+    let secret_file = fixture_file("secret", b"file-secret\n");
+    let secret = Secret::File(secret_file.to_string()).resolve();
+    assert_eq!(secret.unwrap(), "file-secret");
+}
+
+
+#[test]
+fn test_missing_secret_is_named() {
+    let err = Secret::Env("KRECIK_TEST_UNDEFINED_SECRET".to_string())
+        .resolve()
+        .unwrap_err();
+    assert!(err.to_string().contains("KRECIK_TEST_UNDEFINED_SECRET"));
+
+    let err = Secret::File("/nonexistent/krecik-secret".to_string())
+        .resolve()
+        .unwrap_err();
+    assert!(err.to_string().contains("/nonexistent/krecik-secret"));
+}
+
+
+#[test]
+fn test_secret_value_is_redacted() {
+    let secret = Secret::Value("s3cr3t".to_string());
+    assert_eq!(format!("{secret:?}"), "Value(<redacted>)");
+    let secret = Secret::Env("KRECIK_PASSWORD".to_string());
+    assert_eq!(format!("{secret:?}"), r#"Env("KRECIK_PASSWORD")"#);
+}
+
+
+#[test]
+fn test_resolved_auth_is_redacted() {
+    let resolved = [
+        ResolvedAuth::Basic("krecik".to_string(), "s3cr3t".to_string()),
+        ResolvedAuth::Digest("krecik".to_string(), "s3cr3t".to_string()),
+        ResolvedAuth::Bearer("s3cr3t".to_string()),
+        ResolvedAuth::AwsSigV4(
+            "aws:amz:eu-west-1:s3".to_string(),
+            "s3cr3t".to_string(),
+            "s3cr3t".to_string(),
+        ),
+    ];
+    for auth in resolved {
+        assert!(!format!("{auth:?}").contains("s3cr3t"));
+    }
+}
+
+
+/// Spawns fake OAuth2 token endpoint counting token requests
+fn serve_token_endpoint(token_requests: Arc<AtomicUsize>) -> String {
+    serve_http(move |request| {
+        token_requests.fetch_add(1, Ordering::SeqCst);
+        let body = request.split_once("\r\n\r\n").unwrap_or_default().1;
+        if body.contains("grant_type=client_credentials") && body.contains("scope=read") {
+            http_response(
+                200,
+                &["Content-Type: application/json"],
+                br#"{"access_token": "oauth2-token", "expires_in": 3600}"#,
+            )
+        } else {
+            http_response(400, &[], b"invalid_grant")
+        }
+    })
+}
+
+
+#[test]
+fn test_oauth2_token_is_cached() {
+    let token_requests = Arc::new(AtomicUsize::new(0));
+    let token_url = serve_token_endpoint(token_requests.clone());
+    for _ in 0..2 {
+        assert_eq!(
+            oauth2_token(&token_url, "krecik", "client-secret", "read").unwrap(),
+            "oauth2-token"
+        );
+    }
+    assert_eq!(token_requests.load(Ordering::SeqCst), 1);
+}
+
+
+#[test]
+fn test_oauth2_token_rejected() {
+    let token_url = serve_token_endpoint(Arc::new(AtomicUsize::new(0)));
+    assert!(oauth2_token(&token_url, "krecik", "client-secret", "write").is_err());
+}
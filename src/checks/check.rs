@@ -1,5 +1,5 @@
 use crate::{
-    checks::{domain::*, page::*},
+    checks::{database::*, dns::*, domain::*, email_domain::*, page::*, ssh::*, tcp::*},
     products::story::*,
     *,
 };
@@ -18,6 +18,26 @@ pub struct Check {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pages: Option<Pages>,
 
+    /// DNS records to check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns: Option<DnsRecords>,
+
+    /// Email domains to check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_domains: Option<EmailDomains>,
+
+    /// Raw TCP services to check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp: Option<TcpChecks>,
+
+    /// SSH servers to check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshChecks>,
+
+    /// Databases to check (PostgreSQL, MySQL, Redis)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub databases: Option<DatabaseChecks>,
+
     /// Notifier id - taken from config
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notifier: Option<String>,
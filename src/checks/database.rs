@@ -0,0 +1,1031 @@
+use crate::{checks::tls::read_line, *};
+use openssl::{
+    base64,
+    hash::{hash, MessageDigest},
+    pkcs5::pbkdf2_hmac,
+    pkey::PKey,
+    rand::rand_bytes,
+    rsa::{Padding, Rsa},
+    sign::Signer,
+};
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    fmt::{self, Formatter},
+    io::{Error, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+
+/// PostgreSQL protocol version 3.0
+const POSTGRES_PROTOCOL_VERSION: u32 = 196608;
+
+/// MySQL capability flags used by the check
+const MYSQL_CLIENT_LONG_PASSWORD: u32 = 0x0000_0001;
+const MYSQL_CLIENT_CONNECT_WITH_DB: u32 = 0x0000_0008;
+const MYSQL_CLIENT_PROTOCOL_41: u32 = 0x0000_0200;
+const MYSQL_CLIENT_SECURE_CONNECTION: u32 = 0x0000_8000;
+const MYSQL_CLIENT_PLUGIN_AUTH: u32 = 0x0008_0000;
+
+/// MySQL commands used by the check
+const MYSQL_COM_QUIT: u8 = 0x01;
+const MYSQL_COM_PING: u8 = 0x0e;
+
+/// MySQL utf8_general_ci character set
+const MYSQL_CHARSET_UTF8: u8 = 33;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// Database liveness check structure
+pub struct DatabaseCheck {
+    /// Database engine
+    pub engine: DatabaseEngine,
+
+    /// Database server address: "host" or "host:port" (default port of the engine)
+    pub address: String,
+
+    /// User name (default: "postgres" for PostgreSQL, "root" for MySQL, none for Redis)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Password secret, ex. {"Env": "DB_PASSWORD"} or {"File": "/etc/krecik/db-password"}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<Secret>,
+
+    /// Database name (PostgreSQL, MySQL)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+
+    /// Database expectations (liveness is checked by default)
+    #[serde(default)]
+    pub expects: DatabaseExpectations,
+}
+
+
+/// Database checks type
+pub type DatabaseChecks = Vec<DatabaseCheck>;
+
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// Database engines supported by database checks
+pub enum DatabaseEngine {
+    /// PostgreSQL: startup (with authentication) and "SELECT 1"
+    Postgres,
+
+    /// MySQL (and MariaDB): handshake (with authentication) and COM_PING
+    Mysql,
+
+    /// Redis: PING and INFO replication
+    Redis,
+}
+
+
+impl fmt::Display for DatabaseEngine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseEngine::Postgres => write!(f, "PostgreSQL"),
+            DatabaseEngine::Mysql => write!(f, "MySQL"),
+            DatabaseEngine::Redis => write!(f, "Redis"),
+        }
+    }
+}
+
+
+impl DatabaseEngine {
+    /// Default port of the database engine
+    pub fn default_port(&self) -> u16 {
+        match self {
+            DatabaseEngine::Postgres => CHECK_DEFAULT_POSTGRES_PORT,
+            DatabaseEngine::Mysql => CHECK_DEFAULT_MYSQL_PORT,
+            DatabaseEngine::Redis => CHECK_DEFAULT_REDIS_PORT,
+        }
+    }
+}
+
+
+impl DatabaseCheck {
+    /// Database server address with port
+    pub fn server_address(&self) -> String {
+        match self.address.rsplit_once(':') {
+            Some((_, port)) if port.parse::<u16>().is_ok() => self.address.to_string(),
+            _ => format!("{}:{}", self.address, self.engine.default_port()),
+        }
+    }
+
+
+    /// Database label used in stories, ex. "PostgreSQL db.local:5432"
+    pub fn label(&self) -> String {
+        format!("{} {}", self.engine, self.server_address())
+    }
+
+
+    /// Check if database server is alive. Returns server version for PostgreSQL and MySQL,
+    /// and replication information for Redis ("INFO replication" fields)
+    #[instrument(skip(self), fields(database = %self.label()))]
+    pub fn ping(&self) -> Result<DatabaseStatus, Error> {
+        let password = self.password.as_ref().map(Secret::resolve).transpose()?;
+        let mut stream = database_connect(&self.server_address())?;
+        match self.engine {
+            DatabaseEngine::Postgres => {
+                let username = self.username.as_deref().unwrap_or(CHECK_DEFAULT_POSTGRES_USER);
+                postgres_ping(
+                    &mut stream,
+                    username,
+                    password.as_deref(),
+                    self.database.as_deref(),
+                )
+                .map(DatabaseStatus::Version)
+            }
+
+            DatabaseEngine::Mysql => {
+                let username = self.username.as_deref().unwrap_or(CHECK_DEFAULT_MYSQL_USER);
+                mysql_ping(
+                    &mut stream,
+                    username,
+                    password.as_deref(),
+                    self.database.as_deref(),
+                )
+                .map(DatabaseStatus::Version)
+            }
+
+            DatabaseEngine::Redis => {
+                redis_replication(&mut stream, self.username.as_deref(), password.as_deref())
+                    .map(DatabaseStatus::Replication)
+            }
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Status of alive database server
+pub enum DatabaseStatus {
+    /// Server version (PostgreSQL, MySQL)
+    Version(String),
+
+    /// Replication information (Redis "INFO replication" fields)
+    Replication(HashMap<String, String>),
+}
+
+
+/// Executes database checks, returns Stories
+#[instrument(skip(checks))]
+pub fn check_databases(checks: &[Check]) -> Stories {
+    checks
+        .into_par_iter()
+        .flat_map(|check| {
+            let notifier = check.notifier.clone();
+            check
+                .databases
+                .par_iter()
+                .flat_map(|database_checks| {
+                    let mut all_database_checks = database_checks.clone();
+                    all_database_checks.sort();
+                    all_database_checks.dedup();
+                    all_database_checks
+                        .par_iter()
+                        .flat_map(|database_check| {
+                            check_database(database_check, notifier.clone())
+                        })
+                        .collect::<Stories>()
+                })
+                .collect::<Stories>()
+        })
+        .collect()
+}
+
+
+/// Check database server is alive, and check all expectations on its replication
+#[instrument(skip(database_check), fields(database = %database_check.label()))]
+fn check_database(database_check: &DatabaseCheck, notifier: Option<String>) -> Stories {
+    let label = database_check.label();
+    let replication = match database_check.ping() {
+        Ok(DatabaseStatus::Version(version)) => {
+            debug!("Database: {label} server version: {version}");
+            HashMap::new()
+        }
+
+        Ok(DatabaseStatus::Replication(replication)) => {
+            debug!("Database: {label} replication: {replication:?}");
+            replication
+        }
+
+        Err(err) => {
+            return vec![Story::error(
+                Unexpected::DatabaseUnavailable(label, err.to_string()),
+                notifier,
+            )]
+        }
+    };
+    let mut stories = vec![Story::success(
+        Expected::DatabaseAlive(label.to_string()),
+        notifier.clone(),
+    )];
+    if database_check.engine != DatabaseEngine::Redis
+        && !database_check.expects.is_empty()
+    {
+        stories.push(Story::minor(UnexpectedMinor::InternalProtocolProblem(
+            label,
+            format!(
+                "Replication expectations aren't supported for: {}",
+                database_check.engine
+            ),
+        )));
+        return stories;
+    }
+    let role = replication.get("role").cloned().unwrap_or_default();
+    stories.extend(database_check.expects.iter().map(|expectation| {
+        match expectation {
+            DatabaseExpectation::ValidReplicationRole(expected_role) => {
+                // Redis calls replicas "slaves" in INFO replication:
+                let expected = match expected_role.as_str() {
+                    "replica" => "slave",
+                    expected_role => expected_role,
+                };
+                if role == expected {
+                    Story::success(
+                        Expected::ReplicationRole(label.to_string(), role.to_string()),
+                        notifier.clone(),
+                    )
+                } else {
+                    Story::error(
+                        Unexpected::ReplicationRoleMismatch(
+                            label.to_string(),
+                            role.to_string(),
+                            expected_role.to_string(),
+                        ),
+                        notifier.clone(),
+                    )
+                }
+            }
+
+            DatabaseExpectation::ValidReplicationLag(limit) => {
+                match redis_replication_lag(&replication) {
+                    Ok(lag) if lag <= *limit => {
+                        Story::success(
+                            Expected::ReplicationLag(label.to_string(), lag, *limit),
+                            notifier.clone(),
+                        )
+                    }
+
+                    Ok(lag) => {
+                        Story::error(
+                            Unexpected::ReplicationLagExceeded(
+                                label.to_string(),
+                                lag,
+                                *limit,
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    Err(details) => {
+                        Story::error(
+                            Unexpected::ReplicationBroken(label.to_string(), details),
+                            notifier.clone(),
+                        )
+                    }
+                }
+            }
+        }
+    }));
+    stories
+}
+
+
+/// Connect to database server
+fn database_connect(address: &str) -> Result<TcpStream, Error> {
+    let socket_address = address.to_socket_addrs()?.next().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("Couldn't resolve any address from: {address}"),
+        )
+    })?;
+    let stream = TcpStream::connect_timeout(
+        &socket_address,
+        Duration::from_secs(CHECK_CONNECTION_TIMEOUT),
+    )?;
+    let timeout = Duration::from_secs(CHECK_TIMEOUT);
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+
+/// Error of database protocol (unexpected or malformed message)
+fn protocol_error(details: String) -> Error {
+    Error::new(ErrorKind::InvalidData, details)
+}
+
+
+/// Error reported by database server
+fn server_error(details: String) -> Error {
+    Error::new(ErrorKind::PermissionDenied, details)
+}
+
+
+/// HMAC-SHA256 of data with given key
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let key = PKey::hmac(key).map_err(Error::other)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).map_err(Error::other)?;
+    signer.update(data).map_err(Error::other)?;
+    signer.sign_to_vec().map_err(Error::other)
+}
+
+
+/// Digest of data
+fn digest(digest: MessageDigest, data: &[u8]) -> Result<Vec<u8>, Error> {
+    hash(digest, data)
+        .map(|digest| digest.to_vec())
+        .map_err(Error::other)
+}
+
+
+/// XOR of two byte strings (second one is repeated when shorter)
+fn xor(data: &[u8], key: &[u8]) -> Vec<u8> {
+    data.iter()
+        .zip(key.iter().cycle())
+        .map(|(byte, key)| byte ^ key)
+        .collect()
+}
+
+
+/// PostgreSQL startup (with authentication), followed by "SELECT 1" query.
+/// Returns server version
+#[instrument(skip(stream, password))]
+pub fn postgres_ping(
+    stream: &mut TcpStream,
+    username: &str,
+    password: Option<&str>,
+    database: Option<&str>,
+) -> Result<String, Error> {
+    let mut startup = POSTGRES_PROTOCOL_VERSION.to_be_bytes().to_vec();
+    let mut parameters = vec![("user", username), ("application_name", "krecik")];
+    if let Some(database) = database {
+        parameters.push(("database", database));
+    }
+    for (name, value) in parameters {
+        startup.extend_from_slice(name.as_bytes());
+        startup.push(0);
+        startup.extend_from_slice(value.as_bytes());
+        startup.push(0);
+    }
+    startup.push(0);
+    write_postgres_message(stream, None, &startup)?;
+
+    let password_required = || {
+        server_error(format!(
+            "PostgreSQL server requires password of user: {username}"
+        ))
+    };
+    let mut scram = None;
+    let mut server_version = String::new();
+    loop {
+        let (message, body) = read_postgres_message(stream)?;
+        match message {
+            b'R' => {
+                let mut body = PostgresReader(&body);
+                match body.u32()? {
+                    0 => trace!("PostgreSQL authentication succeeded"),
+
+                    3 => {
+                        let password = password.ok_or_else(password_required)?;
+                        write_postgres_message(
+                            stream,
+                            Some(b'p'),
+                            &[password.as_bytes(), b"\0"].concat(),
+                        )?;
+                    }
+
+                    5 => {
+                        let password = password.ok_or_else(password_required)?;
+                        let salt = body.take(4)?;
+                        let inner = hex(&digest(
+                            MessageDigest::md5(),
+                            &[password.as_bytes(), username.as_bytes()].concat(),
+                        )?);
+                        let outer = hex(&digest(
+                            MessageDigest::md5(),
+                            &[inner.as_bytes(), salt].concat(),
+                        )?);
+                        write_postgres_message(
+                            stream,
+                            Some(b'p'),
+                            format!("md5{outer}\0").as_bytes(),
+                        )?;
+                    }
+
+                    10 => {
+                        let password = password.ok_or_else(password_required)?;
+                        let mechanisms = String::from_utf8_lossy(body.0).to_string();
+                        if !mechanisms.split('\0').any(|name| name == "SCRAM-SHA-256") {
+                            return Err(server_error(format!(
+                                "Unsupported PostgreSQL SASL mechanisms: {}",
+                                mechanisms.trim_end_matches('\0').replace('\0', ", ")
+                            )));
+                        }
+                        let client = Scram::new(password)?;
+                        let client_first = client.client_first();
+                        let mut response = b"SCRAM-SHA-256\0".to_vec();
+                        response.extend_from_slice(&(client_first.len() as u32).to_be_bytes());
+                        response.extend_from_slice(client_first.as_bytes());
+                        write_postgres_message(stream, Some(b'p'), &response)?;
+                        scram = Some(client);
+                    }
+
+                    11 => {
+                        let client = scram.as_mut().ok_or_else(|| {
+                            protocol_error("Unexpected SASL continuation".to_string())
+                        })?;
+                        let client_final =
+                            client.client_final(&String::from_utf8_lossy(body.0))?;
+                        write_postgres_message(stream, Some(b'p'), client_final.as_bytes())?;
+                    }
+
+                    12 => {
+                        let client = scram.as_ref().ok_or_else(|| {
+                            protocol_error("Unexpected SASL final".to_string())
+                        })?;
+                        client.verify_server_final(&String::from_utf8_lossy(body.0))?;
+                    }
+
+                    method => {
+                        return Err(server_error(format!(
+                            "Unsupported PostgreSQL authentication method: {method}"
+                        )))
+                    }
+                }
+            }
+
+            b'S' => {
+                let mut body = PostgresReader(&body);
+                if body.string()? == "server_version" {
+                    server_version = body.string()?;
+                }
+            }
+
+            b'E' => return Err(postgres_error(&body)),
+
+            b'Z' => break,
+
+            _ => trace!("Skipped PostgreSQL message: {}", message as char),
+        }
+    }
+
+    write_postgres_message(stream, Some(b'Q'), b"SELECT 1\0")?;
+    let mut result = None;
+    loop {
+        let (message, body) = read_postgres_message(stream)?;
+        match message {
+            b'D' => {
+                let mut body = PostgresReader(&body);
+                body.take(2)?; // number of columns
+                let length = body.u32()? as usize;
+                result = Some(String::from_utf8_lossy(body.take(length)?).to_string());
+            }
+
+            b'E' => return Err(postgres_error(&body)),
+
+            b'Z' => break,
+
+            _ => trace!("Skipped PostgreSQL message: {}", message as char),
+        }
+    }
+    write_postgres_message(stream, Some(b'X'), b"").unwrap_or_default();
+    match result.as_deref() {
+        Some("1") => Ok(server_version),
+        result => {
+            Err(protocol_error(format!(
+                "PostgreSQL query: \"SELECT 1\" returned: {result:?}"
+            )))
+        }
+    }
+}
+
+
+/// Error from PostgreSQL ErrorResponse fields
+fn postgres_error(body: &[u8]) -> Error {
+    let field = |code: u8| {
+        body.split(|byte| *byte == 0)
+            .find(|field| field.first() == Some(&code))
+            .map(|field| String::from_utf8_lossy(&field[1..]).to_string())
+            .unwrap_or_default()
+    };
+    server_error(format!(
+        "PostgreSQL server error: {} {}: {}",
+        field(b'S'),
+        field(b'C'),
+        field(b'M')
+    ))
+}
+
+
+/// Read PostgreSQL message: type and body
+pub(crate) fn read_postgres_message(stream: &mut TcpStream) -> Result<(u8, Vec<u8>), Error> {
+    let mut header = [0u8; 5];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if !(4..=CHECK_DATABASE_MAX_MESSAGE_SIZE).contains(&length) {
+        return Err(protocol_error(format!(
+            "Invalid PostgreSQL message length: {length}"
+        )));
+    }
+    let mut body = vec![0u8; length - 4];
+    stream.read_exact(&mut body)?;
+    Ok((header[0], body))
+}
+
+
+/// Write PostgreSQL message of given type (startup message has no type)
+pub(crate) fn write_postgres_message(
+    stream: &mut TcpStream,
+    message: Option<u8>,
+    body: &[u8],
+) -> Result<(), Error> {
+    let mut data = Vec::with_capacity(body.len() + 5);
+    data.extend(message);
+    data.extend_from_slice(&(body.len() as u32 + 4).to_be_bytes());
+    data.extend_from_slice(body);
+    stream.write_all(&data)
+}
+
+
+/// Reader of PostgreSQL message fields
+struct PostgresReader<'a>(&'a [u8]);
+
+
+impl<'a> PostgresReader<'a> {
+    /// Read int32
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+
+    /// Read NUL terminated string
+    fn string(&mut self) -> Result<String, Error> {
+        let length = self
+            .0
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| protocol_error("Unterminated PostgreSQL string".to_string()))?;
+        let string = String::from_utf8_lossy(self.take(length)?).to_string();
+        self.take(1)?;
+        Ok(string)
+    }
+
+
+    /// Take given number of bytes
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < length {
+            return Err(protocol_error("Truncated PostgreSQL message".to_string()));
+        }
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(bytes)
+    }
+}
+
+
+/// Lowercase hex representation of bytes
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+
+/// SCRAM-SHA-256 client exchange (RFC 5802, RFC 7677)
+struct Scram {
+    /// Client password
+    password: String,
+
+    /// Client nonce
+    nonce: String,
+
+    /// Expected server signature, known after client final message
+    server_signature: Option<Vec<u8>>,
+}
+
+
+impl Scram {
+    /// New SCRAM exchange with random nonce
+    fn new(password: &str) -> Result<Self, Error> {
+        let mut nonce = [0u8; 18];
+        rand_bytes(&mut nonce).map_err(Error::other)?;
+        Ok(Scram {
+            password: password.to_string(),
+            nonce: base64::encode_block(&nonce),
+            server_signature: None,
+        })
+    }
+
+
+    /// Client first message bare (user name is taken from startup message)
+    fn client_first_bare(&self) -> String {
+        format!("n=,r={}", self.nonce)
+    }
+
+
+    /// Client first message
+    fn client_first(&self) -> String {
+        format!("n,,{}", self.client_first_bare())
+    }
+
+
+    /// Client final message with proof, for given server first message
+    fn client_final(&mut self, server_first: &str) -> Result<String, Error> {
+        let attribute = |name: &str| {
+            server_first
+                .split(',')
+                .find_map(|attribute| attribute.strip_prefix(name))
+                .ok_or_else(|| {
+                    protocol_error(format!("Invalid SCRAM server message: {server_first}"))
+                })
+        };
+        let nonce = attribute("r=")?;
+        if !nonce.starts_with(&self.nonce) {
+            return Err(protocol_error("Invalid SCRAM server nonce".to_string()));
+        }
+        let salt = base64::decode_block(attribute("s=")?)
+            .map_err(|err| protocol_error(format!("Invalid SCRAM salt: {err}")))?;
+        let iterations = attribute("i=")?
+            .parse::<usize>()
+            .map_err(|err| protocol_error(format!("Invalid SCRAM iterations: {err}")))?;
+
+        let mut salted_password = [0u8; 32];
+        pbkdf2_hmac(
+            self.password.as_bytes(),
+            &salt,
+            iterations,
+            MessageDigest::sha256(),
+            &mut salted_password,
+        )
+        .map_err(Error::other)?;
+        let client_key = hmac_sha256(&salted_password, b"Client Key")?;
+        let stored_key = digest(MessageDigest::sha256(), &client_key)?;
+        let client_final_without_proof = format!("c=biws,r={nonce}");
+        let auth_message = format!(
+            "{},{server_first},{client_final_without_proof}",
+            self.client_first_bare()
+        );
+        let client_signature = hmac_sha256(&stored_key, auth_message.as_bytes())?;
+        let server_key = hmac_sha256(&salted_password, b"Server Key")?;
+        self.server_signature = Some(hmac_sha256(&server_key, auth_message.as_bytes())?);
+        Ok(format!(
+            "{client_final_without_proof},p={}",
+            base64::encode_block(&xor(&client_key, &client_signature))
+        ))
+    }
+
+
+    /// Verify server final message
+    fn verify_server_final(&self, server_final: &str) -> Result<(), Error> {
+        let signature = server_final
+            .strip_prefix("v=")
+            .and_then(|signature| base64::decode_block(signature).ok());
+        if signature.is_some() && signature == self.server_signature {
+            Ok(())
+        } else {
+            Err(protocol_error(format!(
+                "Invalid SCRAM server signature: {server_final}"
+            )))
+        }
+    }
+}
+
+
+/// MySQL handshake (with authentication), followed by COM_PING. Returns server version
+#[instrument(skip(stream, password))]
+pub fn mysql_ping(
+    stream: &mut TcpStream,
+    username: &str,
+    password: Option<&str>,
+    database: Option<&str>,
+) -> Result<String, Error> {
+    let (_, handshake) = read_mysql_packet(stream)?;
+    if handshake.first() == Some(&0xff) {
+        return Err(mysql_error(&handshake));
+    }
+    if handshake.first() != Some(&10) {
+        return Err(protocol_error(format!(
+            "Unsupported MySQL protocol version: {:?}",
+            handshake.first()
+        )));
+    }
+    let mut reader = MysqlReader(&handshake[1..]);
+    let server_version = reader.string()?;
+    reader.take(4)?; // connection id
+    let mut scramble = reader.take(8)?.to_vec();
+    reader.take(1)?; // filler
+    reader.take(2)?; // capability flags (lower)
+    reader.take(1)?; // character set
+    reader.take(2)?; // status flags
+    reader.take(2)?; // capability flags (upper)
+    let scramble_length = reader.take(1)?[0] as usize;
+    reader.take(10)?; // reserved
+    let rest = reader.take(scramble_length.saturating_sub(8).max(13))?;
+    scramble.extend_from_slice(rest.strip_suffix(&[0]).unwrap_or(rest));
+    let mut plugin = reader.string().unwrap_or_default();
+    if plugin.is_empty() {
+        plugin = "mysql_native_password".to_string();
+    }
+
+    let password = password.unwrap_or_default();
+    let mut capabilities = MYSQL_CLIENT_LONG_PASSWORD
+        | MYSQL_CLIENT_PROTOCOL_41
+        | MYSQL_CLIENT_SECURE_CONNECTION
+        | MYSQL_CLIENT_PLUGIN_AUTH;
+    if database.is_some() {
+        capabilities |= MYSQL_CLIENT_CONNECT_WITH_DB;
+    }
+    let auth_response = mysql_auth_response(&plugin, password, &scramble)?;
+    let mut response = capabilities.to_le_bytes().to_vec();
+    response.extend_from_slice(&CHECK_DATABASE_MAX_MESSAGE_SIZE.to_le_bytes()[..4]);
+    response.push(MYSQL_CHARSET_UTF8);
+    response.extend_from_slice(&[0u8; 23]);
+    response.extend_from_slice(username.as_bytes());
+    response.push(0);
+    response.push(auth_response.len() as u8);
+    response.extend_from_slice(&auth_response);
+    if let Some(database) = database {
+        response.extend_from_slice(database.as_bytes());
+        response.push(0);
+    }
+    response.extend_from_slice(plugin.as_bytes());
+    response.push(0);
+    write_mysql_packet(stream, 1, &response)?;
+
+    loop {
+        let (sequence, packet) = read_mysql_packet(stream)?;
+        let reply = sequence.wrapping_add(1);
+        match packet.first() {
+            Some(0x00) => break,
+
+            Some(0xff) => return Err(mysql_error(&packet)),
+
+            Some(0xfe) => {
+                // authentication method switch:
+                let mut reader = MysqlReader(&packet[1..]);
+                plugin = reader.string()?;
+                scramble = reader.0.strip_suffix(&[0]).unwrap_or(reader.0).to_vec();
+                let auth_response = mysql_auth_response(&plugin, password, &scramble)?;
+                write_mysql_packet(stream, reply, &auth_response)?;
+            }
+
+            Some(0x01) if packet.get(1) == Some(&0x03) => {
+                trace!("MySQL fast authentication succeeded")
+            }
+
+            Some(0x01) if packet.get(1) == Some(&0x04) => {
+                // full authentication: password is sent encrypted with server public key
+                write_mysql_packet(stream, reply, &[0x02])?;
+            }
+
+            Some(0x01) if packet[1..].starts_with(b"-----BEGIN") => {
+                let public_key = Rsa::public_key_from_pem(&packet[1..]).map_err(|err| {
+                    protocol_error(format!("Invalid MySQL server public key: {err}"))
+                })?;
+                let data = xor(&[password.as_bytes(), b"\0"].concat(), &scramble);
+                let mut encrypted = vec![0u8; public_key.size() as usize];
+                let length = public_key
+                    .public_encrypt(&data, &mut encrypted, Padding::PKCS1_OAEP)
+                    .map_err(Error::other)?;
+                write_mysql_packet(stream, reply, &encrypted[..length])?;
+            }
+
+            _ => {
+                return Err(protocol_error(format!(
+                    "Unexpected MySQL authentication packet: {:?}",
+                    packet.first()
+                )))
+            }
+        }
+    }
+
+    write_mysql_packet(stream, 0, &[MYSQL_COM_PING])?;
+    let (_, packet) = read_mysql_packet(stream)?;
+    write_mysql_packet(stream, 0, &[MYSQL_COM_QUIT]).unwrap_or_default();
+    match packet.first() {
+        Some(0x00) => Ok(server_version),
+        Some(0xff) => Err(mysql_error(&packet)),
+        _ => Err(protocol_error("Unexpected MySQL ping response".to_string())),
+    }
+}
+
+
+/// Authentication response of given MySQL authentication plugin
+fn mysql_auth_response(
+    plugin: &str,
+    password: &str,
+    scramble: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if password.is_empty() {
+        return Ok(Vec::new());
+    }
+    match plugin {
+        "mysql_native_password" => {
+            // SHA1(password) XOR SHA1(scramble + SHA1(SHA1(password))):
+            let password_hash = digest(MessageDigest::sha1(), password.as_bytes())?;
+            let double_hash = digest(MessageDigest::sha1(), &password_hash)?;
+            let scramble_hash =
+                digest(MessageDigest::sha1(), &[scramble, &double_hash].concat())?;
+            Ok(xor(&password_hash, &scramble_hash))
+        }
+
+        "caching_sha2_password" => {
+            // SHA256(password) XOR SHA256(SHA256(SHA256(password)) + scramble):
+            let password_hash = digest(MessageDigest::sha256(), password.as_bytes())?;
+            let double_hash = digest(MessageDigest::sha256(), &password_hash)?;
+            let scramble_hash =
+                digest(MessageDigest::sha256(), &[&double_hash, scramble].concat())?;
+            Ok(xor(&password_hash, &scramble_hash))
+        }
+
+        "mysql_clear_password" => Ok([password.as_bytes(), b"\0"].concat()),
+
+        plugin => {
+            Err(server_error(format!(
+                "Unsupported MySQL authentication plugin: {plugin}"
+            )))
+        }
+    }
+}
+
+
+/// Error from MySQL ERR packet
+fn mysql_error(packet: &[u8]) -> Error {
+    let code = packet
+        .get(1..3)
+        .map(|code| u16::from_le_bytes([code[0], code[1]]))
+        .unwrap_or_default();
+    let message = packet.get(3..).unwrap_or_default();
+    let message = match message.first() {
+        Some(b'#') => message.get(6..).unwrap_or_default(),
+        _ => message,
+    };
+    server_error(format!(
+        "MySQL server error: {code}: {}",
+        String::from_utf8_lossy(message)
+    ))
+}
+
+
+/// Read MySQL packet: sequence number and payload
+pub(crate) fn read_mysql_packet(stream: &mut TcpStream) -> Result<(u8, Vec<u8>), Error> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload)?;
+    Ok((header[3], payload))
+}
+
+
+/// Write MySQL packet with given sequence number
+pub(crate) fn write_mysql_packet(
+    stream: &mut TcpStream,
+    sequence: u8,
+    payload: &[u8],
+) -> Result<(), Error> {
+    let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+    packet.push(sequence);
+    packet.extend_from_slice(payload);
+    stream.write_all(&packet)
+}
+
+
+/// Reader of MySQL packet fields
+struct MysqlReader<'a>(&'a [u8]);
+
+
+impl<'a> MysqlReader<'a> {
+    /// Read NUL terminated string (or the rest of the packet)
+    fn string(&mut self) -> Result<String, Error> {
+        let length = self
+            .0
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(self.0.len());
+        let string = String::from_utf8_lossy(self.take(length)?).to_string();
+        self.take(1).unwrap_or_default();
+        Ok(string)
+    }
+
+
+    /// Take given number of bytes
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < length {
+            return Err(protocol_error("Truncated MySQL packet".to_string()));
+        }
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(bytes)
+    }
+}
+
+
+/// Redis PING (with authentication) followed by "INFO replication".
+/// Returns replication information fields, ex. "role" => "master"
+#[instrument(skip(stream, password))]
+pub fn redis_replication(
+    stream: &mut TcpStream,
+    username: Option<&str>,
+    password: Option<&str>,
+) -> Result<HashMap<String, String>, Error> {
+    if let Some(password) = password {
+        let mut command = vec!["AUTH"];
+        command.extend(username);
+        command.push(password);
+        redis_command(stream, &command)?;
+    }
+    let pong = redis_command(stream, &["PING"])?;
+    if pong != "PONG" {
+        return Err(protocol_error(format!(
+            "Redis PING returned: \"{pong}\""
+        )));
+    }
+    let info = redis_command(stream, &["INFO", "replication"])?;
+    redis_command(stream, &["QUIT"]).unwrap_or_default();
+    Ok(info
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_string(), value.trim().to_string()))
+        .collect())
+}
+
+
+/// Send Redis command, returns simple string or bulk string reply
+fn redis_command(stream: &mut TcpStream, arguments: &[&str]) -> Result<String, Error> {
+    let mut command = format!("*{}\r\n", arguments.len());
+    for argument in arguments {
+        command.push_str(&format!("${}\r\n{argument}\r\n", argument.len()));
+    }
+    stream.write_all(command.as_bytes())?;
+
+    let line = read_line(stream)?;
+    match line.split_at_checked(1) {
+        Some(("+" | ":", reply)) => Ok(reply.to_string()),
+
+        Some(("-", error)) => Err(server_error(format!("Redis server error: {error}"))),
+
+        Some(("$", length)) => {
+            let length = length
+                .parse::<usize>()
+                .ok()
+                .filter(|length| *length <= CHECK_DATABASE_MAX_MESSAGE_SIZE)
+                .ok_or_else(|| protocol_error(format!("Invalid Redis reply: {line}")))?;
+            let mut reply = vec![0u8; length + 2];
+            stream.read_exact(&mut reply)?;
+            reply.truncate(length);
+            Ok(String::from_utf8_lossy(&reply).to_string())
+        }
+
+        _ => Err(protocol_error(format!("Unexpected Redis reply: {line}"))),
+    }
+}
+
+
+/// Redis replication lag in seconds: time since last interaction with master (on replica),
+/// or the biggest lag of connected replicas (on master)
+pub fn redis_replication_lag(replication: &HashMap<String, String>) -> Result<u64, String> {
+    let field = |name: &str| replication.get(name).map(String::as_str).unwrap_or_default();
+    match field("role") {
+        "slave" => {
+            if field("master_link_status") != "up" {
+                return Err(format!(
+                    "Link to master: {}:{} is {}",
+                    field("master_host"),
+                    field("master_port"),
+                    field("master_link_status")
+                ));
+            }
+            field("master_last_io_seconds_ago")
+                .parse()
+                .map_err(|_| "Unknown time since last interaction with master".to_string())
+        }
+
+        "master" => {
+            replication
+                .iter()
+                .filter(|(name, _)| {
+                    name.strip_prefix("slave")
+                        .is_some_and(|index| index.parse::<u32>().is_ok())
+                })
+                .filter_map(|(_, replica)| {
+                    replica
+                        .split(',')
+                        .find_map(|field| field.strip_prefix("lag="))
+                        .and_then(|lag| lag.parse::<u64>().ok())
+                })
+                .max()
+                .ok_or_else(|| "No connected replicas".to_string())
+        }
+
+        role => Err(format!("Unknown replication role: {role}")),
+    }
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,497 @@
+use openssl::{
+    hash::{hash, MessageDigest},
+    rsa::{Padding, Rsa},
+};
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use super::*;
+use crate::test_fixtures::*;
+
+
+/// Hex representation of bytes, as used by PostgreSQL MD5 authentication
+fn hex_digest(digest: MessageDigest, data: &[u8]) -> String {
+    hash(digest, data)
+        .unwrap()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+
+/// Spawns minimal fake PostgreSQL server with MD5 authentication, answering "SELECT 1"
+fn serve_postgres(password: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut length = [0u8; 4];
+            stream.read_exact(&mut length).unwrap_or_default();
+            let mut startup = vec![0u8; (u32::from_be_bytes(length) as usize).max(8) - 4];
+            stream.read_exact(&mut startup).unwrap_or_default();
+            let parameters: Vec<String> = startup[4..]
+                .split(|byte| *byte == 0)
+                .map(|field| String::from_utf8_lossy(field).to_string())
+                .collect();
+            let user = parameters
+                .iter()
+                .position(|name| name == "user")
+                .map(|index| parameters[index + 1].to_string())
+                .unwrap_or_default();
+
+            let salt = [7u8, 3, 1, 9];
+            let request = [&5u32.to_be_bytes()[..], &salt].concat();
+            write_postgres_message(&mut stream, Some(b'R'), &request).unwrap_or_default();
+            let Ok((b'p', response)) = read_postgres_message(&mut stream) else {
+                continue;
+            };
+            let inner =
+                hex_digest(MessageDigest::md5(), format!("{password}{user}").as_bytes());
+            let expected = format!(
+                "md5{}\0",
+                hex_digest(MessageDigest::md5(), &[inner.as_bytes(), &salt].concat())
+            );
+            if response != expected.as_bytes() {
+                let error = format!(
+                    "SFATAL\0C28P01\0Mpassword authentication failed for: {user}\0\0"
+                );
+                write_postgres_message(&mut stream, Some(b'E'), error.as_bytes())
+                    .unwrap_or_default();
+                continue;
+            }
+            for (message, body) in [
+                (b'R', &0u32.to_be_bytes()[..]),
+                (b'S', b"server_version\x0015.0 (Krecik)\0"),
+                (b'Z', b"I"),
+            ] {
+                write_postgres_message(&mut stream, Some(message), body)
+                    .unwrap_or_default();
+            }
+            if let Ok((b'Q', query)) = read_postgres_message(&mut stream) {
+                assert_eq!(query, b"SELECT 1\0");
+                for (message, body) in [
+                    (b'D', &[0u8, 1, 0, 0, 0, 1, b'1'][..]),
+                    (b'C', b"SELECT 1\0"),
+                    (b'Z', b"I"),
+                ] {
+                    write_postgres_message(&mut stream, Some(message), body)
+                        .unwrap_or_default();
+                }
+            }
+            read_postgres_message(&mut stream).unwrap_or_default(); // terminate
+        }
+    });
+    address
+}
+
+
+/// Spawns minimal fake MySQL server with given authentication plugin:
+/// "mysql_native_password", or "caching_sha2_password" (full authentication).
+/// Answers COM_PING
+fn serve_mysql(plugin: &'static str, password: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server_key = Rsa::generate(2048).unwrap();
+    let scramble = b"Krecik-scramble-1234";
+    let sha = |digest: MessageDigest, data: &[u8]| hash(digest, data).unwrap().to_vec();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut handshake = b"\x0a8.0.36-krecik\0\x01\0\0\0".to_vec();
+            handshake.extend_from_slice(&scramble[..8]);
+            handshake.extend_from_slice(&[0, 0xff, 0xff, 33, 2, 0, 0xff, 0xff, 21]);
+            handshake.extend_from_slice(&[0u8; 10]);
+            handshake.extend_from_slice(&scramble[8..]);
+            handshake.push(0);
+            handshake.extend_from_slice(plugin.as_bytes());
+            handshake.push(0);
+            write_mysql_packet(&mut stream, 0, &handshake).unwrap_or_default();
+            let Ok((_, response)) = read_mysql_packet(&mut stream) else {
+                continue;
+            };
+            let user_end = 32 + response[32..].iter().position(|byte| *byte == 0).unwrap();
+            let user = String::from_utf8_lossy(&response[32..user_end]).to_string();
+            let auth_length = response[user_end + 1] as usize;
+            let auth = &response[user_end + 2..user_end + 2 + auth_length];
+
+            let (authenticated, sequence) = match plugin {
+                "mysql_native_password" => {
+                    let password_hash = sha(MessageDigest::sha1(), password.as_bytes());
+                    let double_hash = sha(MessageDigest::sha1(), &password_hash);
+                    let scramble_hash = sha(
+                        MessageDigest::sha1(),
+                        &[&scramble[..], &double_hash].concat(),
+                    );
+                    let expected: Vec<u8> = password_hash
+                        .iter()
+                        .zip(scramble_hash)
+                        .map(|(a, b)| a ^ b)
+                        .collect();
+                    (auth == expected, 2)
+                }
+
+                _ => {
+                    // full authentication, with password encrypted with server public key:
+                    write_mysql_packet(&mut stream, 2, &[0x01, 0x04]).unwrap_or_default();
+                    let Ok((_, request)) = read_mysql_packet(&mut stream) else {
+                        continue;
+                    };
+                    assert_eq!(request, [0x02]);
+                    let public_key = server_key.public_key_to_pem().unwrap();
+                    let public_key = [&[0x01][..], &public_key].concat();
+                    write_mysql_packet(&mut stream, 4, &public_key).unwrap_or_default();
+                    let Ok((_, encrypted)) = read_mysql_packet(&mut stream) else {
+                        continue;
+                    };
+                    let mut decrypted = vec![0u8; server_key.size() as usize];
+                    let length = server_key
+                        .private_decrypt(&encrypted, &mut decrypted, Padding::PKCS1_OAEP)
+                        .unwrap();
+                    let decrypted: Vec<u8> = decrypted[..length]
+                        .iter()
+                        .zip(scramble.iter().cycle())
+                        .map(|(a, b)| a ^ b)
+                        .collect();
+                    (decrypted == [password.as_bytes(), b"\0"].concat(), 6)
+                }
+            };
+            if !authenticated {
+                let error = format!("#28000Access denied for user '{user}'");
+                let error = [&[0xff, 0x15, 0x04][..], error.as_bytes()].concat();
+                write_mysql_packet(&mut stream, sequence, &error).unwrap_or_default();
+                continue;
+            }
+            let ok = [0u8, 0, 0, 2, 0, 0, 0];
+            write_mysql_packet(&mut stream, sequence, &ok).unwrap_or_default();
+            if let Ok((0, command)) = read_mysql_packet(&mut stream) {
+                assert_eq!(command, [0x0e]);
+                write_mysql_packet(&mut stream, 1, &ok).unwrap_or_default();
+            }
+            read_mysql_packet(&mut stream).unwrap_or_default(); // quit
+        }
+    });
+    address
+}
+
+
+/// Spawns minimal fake Redis server, with optional password, replying to INFO with info
+fn serve_redis(password: Option<&'static str>, info: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut authenticated = password.is_none();
+            while let Some(count) = read_client_line(&mut stream)
+                .strip_prefix('*')
+                .and_then(|count| count.parse::<usize>().ok())
+            {
+                let command: Vec<String> = (0..count)
+                    .map(|_| {
+                        read_client_line(&mut stream); // argument length
+                        read_client_line(&mut stream)
+                    })
+                    .collect();
+                let reply = match command[0].as_str() {
+                    "AUTH" if password == command.last().map(String::as_str) => {
+                        authenticated = true;
+                        "+OK\r\n".to_string()
+                    }
+                    "AUTH" => "-WRONGPASS invalid username-password pair\r\n".to_string(),
+                    _ if !authenticated => {
+                        "-NOAUTH Authentication required.\r\n".to_string()
+                    }
+                    "PING" => "+PONG\r\n".to_string(),
+                    "INFO" => format!("${}\r\n{info}\r\n", info.len()),
+                    _ => "+OK\r\n".to_string(),
+                };
+                stream.write_all(reply.as_bytes()).unwrap_or_default();
+            }
+        }
+    });
+    address
+}
+
+
+/// Replication section of INFO of Redis master with two replicas
+const REDIS_MASTER_INFO: &str = "# Replication\r\nrole:master\r\nconnected_slaves:2\r\n\
+    slave0:ip=10.0.0.2,port=6379,state=online,offset=1337,lag=1\r\n\
+    slave1:ip=10.0.0.3,port=6379,state=online,offset=1337,lag=0\r\n";
+
+
+/// Replication section of INFO of Redis replica, with link to master up
+const REDIS_REPLICA_INFO: &str = "# Replication\r\nrole:slave\r\nmaster_host:10.0.0.1\r\n\
+    master_port:6379\r\nmaster_link_status:up\r\nmaster_last_io_seconds_ago:3\r\n";
+
+
+/// Replication section of INFO of Redis replica, with link to master down
+const REDIS_BROKEN_REPLICA_INFO: &str = "# Replication\r\nrole:slave\r\n\
+    master_host:10.0.0.1\r\nmaster_port:6379\r\nmaster_link_status:down\r\n\
+    master_last_io_seconds_ago:-1\r\n";
+
+
+/// Database check of "krecik" user
+fn database(
+    engine: DatabaseEngine,
+    address: &str,
+    password: Option<Secret>,
+    expects: DatabaseExpectations,
+) -> DatabaseCheck {
+    DatabaseCheck {
+        engine,
+        address: address.to_string(),
+        username: Some("krecik".to_string()),
+        password,
+        database: None,
+        expects,
+    }
+}
+
+
+/// Checks given databases
+fn database_stories(database_checks: DatabaseChecks) -> Stories {
+    check_databases(&[Check {
+        databases: Some(database_checks),
+        ..Check::default()
+    }])
+}
+
+
+/// Asserts that single story reports given database unavailable, with details containing text
+fn assert_unavailable(history: &Stories, expected_label: &str, expected_details: &str) {
+    assert_eq!(history.len(), 1, "{history:?}");
+    assert!(
+        matches!(
+            &history[0].error,
+            Some(Unexpected::DatabaseUnavailable(label, details))
+                if label == expected_label && details.contains(expected_details)
+        ),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_database_postgres_alive() {
+    let address = serve_postgres("pg-s3cr3t");
+    std::env::set_var("KRECIK_TEST_POSTGRES_PASSWORD", "pg-s3cr3t");
+    let history = database_stories(vec![database(
+        DatabaseEngine::Postgres,
+        &address,
+        Some(Secret::Env("KRECIK_TEST_POSTGRES_PASSWORD".to_string())),
+        vec![],
+    )]);
+    assert_eq!(history.len(), 1);
+    assert_eq!(
+        history[0].success,
+        Some(Expected::DatabaseAlive(format!("PostgreSQL {address}")))
+    );
+}
+
+
+#[test]
+fn test_database_postgres_wrong_password() {
+    let address = serve_postgres("pg-s3cr3t");
+    let history = database_stories(vec![database(
+        DatabaseEngine::Postgres,
+        &address,
+        Some(Secret::Value("wrong".to_string())),
+        vec![],
+    )]);
+    assert_unavailable(&history, &format!("PostgreSQL {address}"), "28P01");
+}
+
+
+#[test]
+fn test_database_mysql_native_password() {
+    let address = serve_mysql("mysql_native_password", "my-s3cr3t");
+    let password_file = fixture_file("mysql-password", b"my-s3cr3t\n");
+    let history = database_stories(vec![database(
+        DatabaseEngine::Mysql,
+        &address,
+        Some(Secret::File(password_file.to_string())),
+        vec![],
+    )]);
+    assert_eq!(history.len(), 1);
+    assert_eq!(
+        history[0].success,
+        Some(Expected::DatabaseAlive(format!("MySQL {address}")))
+    );
+}
+
+
+#[test]
+fn test_database_mysql_caching_sha2_password() {
+    let address = serve_mysql("caching_sha2_password", "sha2-s3cr3t");
+    let history = database_stories(vec![database(
+        DatabaseEngine::Mysql,
+        &address,
+        Some(Secret::Value("sha2-s3cr3t".to_string())),
+        vec![],
+    )]);
+    assert_eq!(history.len(), 1);
+    assert_eq!(
+        history[0].success,
+        Some(Expected::DatabaseAlive(format!("MySQL {address}")))
+    );
+}
+
+
+#[test]
+fn test_database_mysql_wrong_password() {
+    let address = serve_mysql("mysql_native_password", "my-s3cr3t");
+    let history = database_stories(vec![database(
+        DatabaseEngine::Mysql,
+        &address,
+        Some(Secret::Value("wrong".to_string())),
+        vec![],
+    )]);
+    assert_unavailable(&history, &format!("MySQL {address}"), "Access denied");
+}
+
+
+#[test]
+fn test_database_redis_master_replication() {
+    let address = serve_redis(Some("redis-s3cr3t"), REDIS_MASTER_INFO);
+    let history = database_stories(vec![database(
+        DatabaseEngine::Redis,
+        &address,
+        Some(Secret::Value("redis-s3cr3t".to_string())),
+        vec![
+            DatabaseExpectation::ValidReplicationRole("master".to_string()),
+            DatabaseExpectation::ValidReplicationLag(5),
+        ],
+    )]);
+    let label = format!("Redis {address}");
+    assert_eq!(
+        history.iter().map(|story| story.success.clone()).collect::<Vec<_>>(),
+        vec![
+            Some(Expected::DatabaseAlive(label.to_string())),
+            Some(Expected::ReplicationRole(label.to_string(), "master".to_string())),
+            Some(Expected::ReplicationLag(label, 1, 5)),
+        ]
+    );
+}
+
+
+#[test]
+fn test_database_redis_replica_replication() {
+    let address = serve_redis(None, REDIS_REPLICA_INFO);
+    let history = database_stories(vec![database(
+        DatabaseEngine::Redis,
+        &address,
+        None,
+        vec![
+            DatabaseExpectation::ValidReplicationRole("replica".to_string()),
+            DatabaseExpectation::ValidReplicationLag(10),
+        ],
+    )]);
+    let label = format!("Redis {address}");
+    assert_eq!(
+        history.iter().map(|story| story.success.clone()).collect::<Vec<_>>(),
+        vec![
+            Some(Expected::DatabaseAlive(label.to_string())),
+            Some(Expected::ReplicationRole(label.to_string(), "slave".to_string())),
+            Some(Expected::ReplicationLag(label, 3, 10)),
+        ]
+    );
+}
+
+
+#[test]
+fn test_database_redis_authentication_required() {
+    let address = serve_redis(Some("redis-s3cr3t"), REDIS_MASTER_INFO);
+    let history = database_stories(vec![database(
+        DatabaseEngine::Redis,
+        &address,
+        None,
+        vec![DatabaseExpectation::ValidReplicationRole("master".to_string())],
+    )]);
+    assert_unavailable(&history, &format!("Redis {address}"), "NOAUTH");
+}
+
+
+#[test]
+fn test_database_redis_replication_failures() {
+    let replica_address = serve_redis(None, REDIS_REPLICA_INFO);
+    let broken_replica_address = serve_redis(None, REDIS_BROKEN_REPLICA_INFO);
+    let history = database_stories(vec![
+        database(
+            DatabaseEngine::Redis,
+            &replica_address,
+            None,
+            vec![
+                DatabaseExpectation::ValidReplicationRole("master".to_string()),
+                DatabaseExpectation::ValidReplicationLag(2),
+            ],
+        ),
+        database(
+            DatabaseEngine::Redis,
+            &broken_replica_address,
+            None,
+            vec![DatabaseExpectation::ValidReplicationLag(10)],
+        ),
+    ]);
+    assert_eq!(history.len(), 5, "{history:?}");
+    assert_eq!(history.iter().filter(|story| story.success.is_some()).count(), 2);
+    assert!(history.iter().any(|story| {
+        matches!(
+            &story.error,
+            Some(Unexpected::ReplicationRoleMismatch(_, role, expected))
+                if role == "slave" && expected == "master"
+        )
+    }));
+    assert!(history.iter().any(|story| {
+        matches!(story.error, Some(Unexpected::ReplicationLagExceeded(_, 3, 2)))
+    }));
+    assert!(history.iter().any(|story| {
+        matches!(
+            &story.error,
+            Some(Unexpected::ReplicationBroken(_, details)) if details.contains("down")
+        )
+    }));
+}
+
+
+#[test]
+fn test_database_replication_expectations_unsupported() {
+    let address = serve_postgres("pg-s3cr3t");
+    let history = database_stories(vec![database(
+        DatabaseEngine::Postgres,
+        &address,
+        Some(Secret::Value("pg-s3cr3t".to_string())),
+        vec![DatabaseExpectation::ValidReplicationRole("master".to_string())],
+    )]);
+    assert_eq!(history.len(), 2, "{history:?}");
+    assert!(history[0].success.is_some());
+    assert!(matches!(
+        history[1].minor,
+        Some(UnexpectedMinor::InternalProtocolProblem(_, _))
+    ));
+}
+
+
+#[test]
+fn test_database_unavailable() {
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let history =
+        database_stories(vec![database(DatabaseEngine::Postgres, &address, None, vec![])]);
+    assert_unavailable(&history, &format!("PostgreSQL {address}"), "");
+}
+
+
+#[test]
+fn test_database_check_debug_hides_password() {
+    let check = database(
+        DatabaseEngine::Postgres,
+        "127.0.0.1",
+        Some(Secret::Value("pg-s3cr3t".to_string())),
+        vec![],
+    );
+    assert!(!format!("{check:?}").contains("pg-s3cr3t"));
+}
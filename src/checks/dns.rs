@@ -0,0 +1,547 @@
+use crate::*;
+use hickory_proto::{
+    op::{Message, MessageType, OpCode, Query, ResponseCode},
+    rr::{Name, RData, Record, RecordType},
+};
+use openssl::rand::rand_bytes;
+use rayon::prelude::*;
+use std::{
+    io::{Error, ErrorKind, Read, Write},
+    net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// DNS record check structure
+pub struct DnsRecord {
+    /// Record name, ex. "example.com" or "_sip._tcp.example.com"
+    pub name: String,
+
+    /// Record type
+    #[serde(rename = "type")]
+    pub record_type: DnsRecordType,
+
+    /// Expected answer set (in any order), ex. "10 mx1.example.com" for MX record.
+    /// Empty set expects no records at all
+    pub expects: Vec<String>,
+
+    /// Resolvers to query, ex. "1.1.1.1" or "1.1.1.1:53" (system resolvers by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolvers: Option<Vec<String>>,
+
+    /// Query authoritative servers of the zone (found with given resolvers) instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authoritative: Option<bool>,
+
+    /// DNS port of resolvers given without port, and of authoritative servers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
+
+
+/// DNS records type
+pub type DnsRecords = Vec<DnsRecord>;
+
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// DNS record types supported by DNS checks
+#[allow(clippy::upper_case_acronyms)]
+pub enum DnsRecordType {
+    /// IPv4 address
+    A,
+
+    /// IPv6 address
+    AAAA,
+
+    /// Canonical name
+    CNAME,
+
+    /// Mail exchanger: "preference exchange"
+    MX,
+
+    /// Text (character strings are concatenated)
+    TXT,
+
+    /// Name server
+    NS,
+
+    /// Certification Authority Authorization: "flags tag value"
+    CAA,
+
+    /// Service: "priority weight port target"
+    SRV,
+}
+
+
+impl From<DnsRecordType> for RecordType {
+    fn from(record_type: DnsRecordType) -> RecordType {
+        match record_type {
+            DnsRecordType::A => RecordType::A,
+            DnsRecordType::AAAA => RecordType::AAAA,
+            DnsRecordType::CNAME => RecordType::CNAME,
+            DnsRecordType::MX => RecordType::MX,
+            DnsRecordType::TXT => RecordType::TXT,
+            DnsRecordType::NS => RecordType::NS,
+            DnsRecordType::CAA => RecordType::CAA,
+            DnsRecordType::SRV => RecordType::SRV,
+        }
+    }
+}
+
+
+impl DnsRecord {
+    /// Record label used in stories, ex. "example.com MX"
+    pub fn label(&self) -> String {
+        format!("{} {:?}", self.name, self.record_type)
+    }
+
+
+    /// Expected answer set, normalized and sorted
+    pub fn expected_answers(&self) -> Vec<String> {
+        let mut answers: Vec<String> = self
+            .expects
+            .iter()
+            .map(|answer| normalize_answer(self.record_type, answer))
+            .collect();
+        answers.sort();
+        answers.dedup();
+        answers
+    }
+
+
+    /// Servers to query: given (or system) resolvers, or authoritative servers of the zone
+    #[instrument]
+    pub fn servers(&self) -> Result<Vec<SocketAddr>, Error> {
+        let port = self.port.unwrap_or(CHECK_DEFAULT_DNS_PORT);
+        let resolvers = dns_resolvers(self.resolvers.as_deref(), port)?;
+        let servers = if self.authoritative.unwrap_or(false) {
+            authoritative_servers(&self.name, &resolvers, port)?
+        } else {
+            resolvers
+        };
+        if servers.is_empty() {
+            Err(Error::new(ErrorKind::NotFound, "No DNS servers to query"))
+        } else {
+            Ok(servers)
+        }
+    }
+}
+
+
+/// Executes DNS record checks, returns Stories
+#[instrument(skip(checks))]
+pub fn check_dns(checks: &[Check]) -> Stories {
+    checks
+        .into_par_iter()
+        .flat_map(|check| {
+            let notifier = check.notifier.clone();
+            check
+                .dns
+                .par_iter()
+                .flat_map(|records| {
+                    let mut all_records = records.clone();
+                    all_records.sort();
+                    all_records.dedup();
+                    all_records
+                        .par_iter()
+                        .flat_map(|record| check_dns_record(record, notifier.clone()))
+                        .collect::<Stories>()
+                })
+                .collect::<Stories>()
+        })
+        .collect()
+}
+
+
+/// Query each server of given DNS record, and compare answers with expected ones
+#[instrument]
+fn check_dns_record(record: &DnsRecord, notifier: Option<String>) -> Stories {
+    let servers = match record.servers() {
+        Ok(servers) => servers,
+        Err(err) => {
+            return vec![Story::error(
+                Unexpected::DnsQueryFailed(record.label(), err.to_string()),
+                notifier,
+            )]
+        }
+    };
+    let expected = record.expected_answers();
+    let recursion = !record.authoritative.unwrap_or(false);
+    servers
+        .into_par_iter()
+        .map(|server| {
+            let label = record.label();
+            match dns_answers(server, &record.name, record.record_type, recursion) {
+                Ok(answers) if answers == expected => {
+                    Story::success(
+                        Expected::DnsRecordValid(
+                            label,
+                            server.to_string(),
+                            answers.join(", "),
+                        ),
+                        notifier.clone(),
+                    )
+                }
+
+                Ok(answers) => {
+                    Story::error(
+                        Unexpected::DnsRecordMismatch(
+                            label,
+                            server.to_string(),
+                            expected.join(", "),
+                            answers.join(", "),
+                        ),
+                        notifier.clone(),
+                    )
+                }
+
+                Err(err) => {
+                    Story::error(
+                        Unexpected::DnsQueryFailed(
+                            format!("{label} at: {server}"),
+                            err.to_string(),
+                        ),
+                        notifier.clone(),
+                    )
+                }
+            }
+        })
+        .collect()
+}
+
+
+/// Normalize answer: names are lowercase without trailing dot, whitespace is collapsed.
+/// TXT answers are taken as they are (without surrounding quotes)
+fn normalize_answer(record_type: DnsRecordType, answer: &str) -> String {
+    match record_type {
+        DnsRecordType::TXT => {
+            answer
+                .trim()
+                .strip_prefix('"')
+                .and_then(|answer| answer.strip_suffix('"'))
+                .unwrap_or(answer)
+                .to_string()
+        }
+        _ => {
+            answer
+                .split_whitespace()
+                .map(|token| token.trim_matches('"').trim_end_matches('.').to_lowercase())
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    }
+}
+
+
+/// Answer of given record in textual form
+fn record_answer(record: &Record) -> Option<String> {
+    let name = |name: &Name| name.to_ascii();
+    let answer = match record.data()? {
+        RData::A(address) => address.to_string(),
+        RData::AAAA(address) => address.to_string(),
+        RData::CNAME(cname) => name(&cname.0),
+        RData::NS(ns) => name(&ns.0),
+        RData::MX(mx) => format!("{} {}", mx.preference(), name(mx.exchange())),
+        RData::SRV(srv) => {
+            format!(
+                "{} {} {} {}",
+                srv.priority(),
+                srv.weight(),
+                srv.port(),
+                name(srv.target())
+            )
+        }
+        RData::CAA(caa) => caa.to_string(),
+        RData::TXT(txt) => {
+            return Some(
+                txt.txt_data()
+                    .iter()
+                    .map(|data| String::from_utf8_lossy(data).to_string())
+                    .collect(),
+            )
+        }
+        _ => return None,
+    };
+    Some(answer)
+}
+
+
+/// Socket address of DNS server given as "ip" or "ip:port"
+fn server_address(server: &str, default_port: u16) -> Result<SocketAddr, Error> {
+    if let Ok(address) = IpAddr::from_str(server.trim_matches(|c| c == '[' || c == ']')) {
+        return Ok(SocketAddr::new(address, default_port));
+    }
+    SocketAddr::from_str(server).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid DNS server address: {server}: {err}"),
+        )
+    })
+}
+
+
+/// Addresses of given resolvers ("ip" or "ip:port"), or system resolvers by default
+pub fn dns_resolvers(
+    resolvers: Option<&[String]>,
+    port: u16,
+) -> Result<Vec<SocketAddr>, Error> {
+    match resolvers {
+        Some(resolvers) => {
+            resolvers
+                .iter()
+                .map(|resolver| server_address(resolver, port))
+                .collect()
+        }
+        None => system_resolvers(port),
+    }
+}
+
+
+/// Name servers from /etc/resolv.conf
+fn system_resolvers(port: u16) -> Result<Vec<SocketAddr>, Error> {
+    let resolvers: Vec<SocketAddr> = read_text_file(CHECK_RESOLV_CONF)?
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|server| server_address(server.trim(), port).ok())
+        .collect();
+    if resolvers.is_empty() {
+        Err(Error::new(
+            ErrorKind::NotFound,
+            format!("No name servers defined in: {CHECK_RESOLV_CONF}"),
+        ))
+    } else {
+        Ok(resolvers)
+    }
+}
+
+
+/// Authoritative servers of the zone of given name, found by the first resolver able to
+/// find them
+#[instrument]
+fn authoritative_servers(
+    name: &str,
+    resolvers: &[SocketAddr],
+    port: u16,
+) -> Result<Vec<SocketAddr>, Error> {
+    let mut failure =
+        Error::new(ErrorKind::InvalidInput, "No resolvers to find authoritative servers");
+    for resolver in resolvers {
+        match zone_name_servers(name, *resolver, port) {
+            Ok(servers) => return Ok(servers),
+            Err(err) => {
+                debug!("Resolver: {resolver} can't find authoritative servers: {err}");
+                failure = err;
+            }
+        }
+    }
+    Err(failure)
+}
+
+
+/// Authoritative servers of the zone of given name. Zone is found by querying NS records
+/// of the name and its parent domains. Name server addresses are taken from glue records,
+/// or resolved by the system resolver
+fn zone_name_servers(
+    name: &str,
+    resolver: SocketAddr,
+    port: u16,
+) -> Result<Vec<SocketAddr>, Error> {
+    let labels: Vec<&str> = name.trim_end_matches('.').split('.').collect();
+    for skip in 0..labels.len() {
+        let zone = labels[skip..].join(".");
+        let response = dns_query(resolver, &zone, RecordType::NS, true)?;
+        let name_servers: Vec<Name> = response
+            .answers()
+            .iter()
+            .filter_map(|record| {
+                match record.data() {
+                    Some(RData::NS(ns)) => Some(ns.0.clone()),
+                    _ => None,
+                }
+            })
+            .collect();
+        if name_servers.is_empty() {
+            continue;
+        }
+        let mut servers: Vec<SocketAddr> = name_servers
+            .iter()
+            .flat_map(|name_server| {
+                let glue: Vec<SocketAddr> = response
+                    .additionals()
+                    .iter()
+                    .filter(|record| record.name() == name_server)
+                    .filter_map(|record| {
+                        match record.data() {
+                            Some(RData::A(address)) => Some(IpAddr::V4(address.0)),
+                            Some(RData::AAAA(address)) => Some(IpAddr::V6(address.0)),
+                            _ => None,
+                        }
+                    })
+                    .map(|address| SocketAddr::new(address, port))
+                    .collect();
+                if glue.is_empty() {
+                    (name_server.to_ascii().trim_end_matches('.'), port)
+                        .to_socket_addrs()
+                        .map(|addresses| addresses.collect())
+                        .unwrap_or_default()
+                } else {
+                    glue
+                }
+            })
+            .collect();
+        servers.sort();
+        servers.dedup();
+        debug!("Authoritative servers of zone: {zone}: {servers:?}");
+        return if servers.is_empty() {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Name servers of zone: {zone} can't be resolved"),
+            ))
+        } else {
+            Ok(servers)
+        };
+    }
+    Err(Error::new(
+        ErrorKind::NotFound,
+        format!("No authoritative servers found for: {name}"),
+    ))
+}
+
+
+/// Query given DNS server for records of given name and type, returns answer set
+/// (normalized and sorted). Non existent name gives empty answer set
+#[instrument]
+pub fn dns_answers(
+    server: SocketAddr,
+    name: &str,
+    record_type: DnsRecordType,
+    recursion_desired: bool,
+) -> Result<Vec<String>, Error> {
+    let response = dns_query(server, name, record_type.into(), recursion_desired)?;
+    match response.response_code() {
+        ResponseCode::NoError | ResponseCode::NXDomain => {}
+        response_code => {
+            return Err(Error::other(format!(
+                "DNS server: {server} responded with: {response_code}"
+            )))
+        }
+    }
+    let mut answers: Vec<String> = response
+        .answers()
+        .iter()
+        .filter(|record| record.record_type() == record_type.into())
+        .filter_map(record_answer)
+        .map(|answer| normalize_answer(record_type, &answer))
+        .collect();
+    answers.sort();
+    answers.dedup();
+    Ok(answers)
+}
+
+
+/// Query given resolvers in turn, returns answer set of the first one responding
+#[instrument]
+pub fn dns_lookup(
+    resolvers: &[SocketAddr],
+    name: &str,
+    record_type: DnsRecordType,
+) -> Result<Vec<String>, Error> {
+    let mut last_error = Error::new(ErrorKind::InvalidInput, "No resolvers to query");
+    for resolver in resolvers {
+        match dns_answers(*resolver, name, record_type, true) {
+            Ok(answers) => return Ok(answers),
+            Err(err) => {
+                debug!("DNS lookup of: {name} at: {resolver} failed: {err}");
+                last_error = err;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+
+/// Send DNS query over UDP (retried over TCP when response is truncated)
+#[instrument]
+fn dns_query(
+    server: SocketAddr,
+    name: &str,
+    record_type: RecordType,
+    recursion_desired: bool,
+) -> Result<Message, Error> {
+    let invalid_data = |err: hickory_proto::error::ProtoError| {
+        Error::new(ErrorKind::InvalidData, err.to_string())
+    };
+    let name =
+        Name::from_str(&format!("{}.", name.trim_end_matches('.'))).map_err(invalid_data)?;
+    let mut id = [0u8; 2];
+    rand_bytes(&mut id)?;
+    let id = u16::from_be_bytes(id);
+    let mut query = Message::new();
+    query
+        .set_id(id)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(recursion_desired)
+        .add_query(Query::query(name, record_type));
+    let query = query.to_vec().map_err(invalid_data)?;
+    let timeout = Duration::from_secs(CHECK_TIMEOUT);
+
+    let bind_address = match server {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let socket = UdpSocket::bind(bind_address)?;
+    socket.connect(server)?;
+    socket.send(&query)?;
+    let mut buffer = [0u8; 4096];
+    // stray datagrams (undecodable, or answering other queries) are skipped until deadline:
+    let deadline = Instant::now() + timeout;
+    let response = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!("No DNS response from: {server} in {CHECK_TIMEOUT}s"),
+            ));
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        let length = socket.recv(&mut buffer)?;
+        match Message::from_vec(&buffer[..length]) {
+            Ok(response) if response.id() == id => break response,
+            Ok(response) => {
+                debug!("Skipping DNS response with unexpected id: {}", response.id());
+            }
+            Err(err) => debug!("Skipping undecodable DNS response from: {server}: {err}"),
+        }
+    };
+    if !response.truncated() {
+        return Ok(response);
+    }
+
+    debug!("Truncated DNS response from: {server}, retrying over TCP");
+    let mut stream = TcpStream::connect_timeout(&server, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.write_all(&[(query.len() >> 8) as u8, query.len() as u8])?;
+    stream.write_all(&query)?;
+    let mut length = [0u8; 2];
+    stream.read_exact(&mut length)?;
+    let mut response = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut response)?;
+    let response = Message::from_vec(&response).map_err(invalid_data)?;
+    if response.id() == id {
+        Ok(response)
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("DNS response from: {server} has unexpected id: {}", response.id()),
+        ))
+    }
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,356 @@
+use hickory_proto::{
+    op::{Message, MessageType, ResponseCode},
+    rr::{
+        rdata::{A, CAA, CNAME, MX, NS, SRV, TXT},
+        Name, RData, Record, RecordType,
+    },
+};
+use std::{net::UdpSocket, thread};
+
+use crate::{test_fixtures::*, *};
+
+
+/// Long SPF record, sent in multiple character strings (and truncated over UDP)
+fn long_txt() -> String {
+    "v=spf1 ".to_string() + &"include:spf.krecik-dns.test ".repeat(20)
+}
+
+
+/// Spawns stub DNS server with records of "krecik-dns.test" zone. Returns its port
+fn serve_zone() -> u16 {
+    let name = |name: &str| Name::from_ascii(name).unwrap();
+    serve_dns(vec![
+        (
+            ("krecik-dns.test", RecordType::A, true),
+            (vec![RData::A(A::new(10, 0, 0, 1))], vec![]),
+        ),
+        (
+            ("krecik-dns.test", RecordType::A, false),
+            (vec![RData::A(A::new(10, 0, 0, 2))], vec![]),
+        ),
+        (
+            ("krecik-dns.test", RecordType::NS, true),
+            (
+                vec![RData::NS(NS(name("ns.krecik-dns.test.")))],
+                vec![RData::A(A::new(127, 0, 0, 1))],
+            ),
+        ),
+        (
+            ("krecik-dns.test", RecordType::MX, true),
+            (
+                vec![
+                    RData::MX(MX::new(10, name("mx1.krecik-dns.test."))),
+                    RData::MX(MX::new(20, name("mx2.krecik-dns.test."))),
+                ],
+                vec![],
+            ),
+        ),
+        (
+            ("krecik-dns.test", RecordType::TXT, true),
+            (
+                vec![RData::TXT(TXT::new(
+                    long_txt()
+                        .as_bytes()
+                        .chunks(200)
+                        .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+                        .collect(),
+                ))],
+                vec![],
+            ),
+        ),
+        (
+            ("krecik-dns.test", RecordType::CAA, true),
+            (
+                vec![RData::CAA(CAA::new_issue(false, Some(name("letsencrypt.org")), vec![]))],
+                vec![],
+            ),
+        ),
+        (
+            ("www.krecik-dns.test", RecordType::CNAME, true),
+            (vec![RData::CNAME(CNAME(name("krecik-dns.test.")))], vec![]),
+        ),
+        (
+            ("_sip._tcp.krecik-dns.test", RecordType::SRV, true),
+            (vec![RData::SRV(SRV::new(10, 5, 5060, name("sip.krecik-dns.test.")))], vec![]),
+        ),
+    ])
+}
+
+
+/// DNS record check of given name and type, resolved by local server on given port
+fn record(port: u16, name: &str, record_type: DnsRecordType, expects: &[&str]) -> DnsRecord {
+    DnsRecord {
+        name: name.to_string(),
+        record_type,
+        expects: expects.iter().map(|answer| answer.to_string()).collect(),
+        resolvers: Some(vec![format!("127.0.0.1:{port}")]),
+        authoritative: None,
+        port: None,
+    }
+}
+
+
+/// Checks given DNS records
+fn dns_stories(records: DnsRecords) -> Stories {
+    check_dns(&[Check {
+        dns: Some(records),
+        ..Check::default()
+    }])
+}
+
+
+/// Asserts that all stories report valid DNS records
+fn assert_valid(history: &Stories, count: usize) {
+    assert_eq!(history.len(), count, "{history:?}");
+    assert!(
+        history
+            .iter()
+            .all(|story| matches!(story.success, Some(Expected::DnsRecordValid(_, _, _)))),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_dns_records_of_all_types() {
+    let port = serve_zone();
+    let history = dns_stories(vec![
+        record(port, "krecik-dns.test", DnsRecordType::A, &["10.0.0.1"]),
+        record(port, "krecik-dns.test", DnsRecordType::CAA, &["0 issue \"letsencrypt.org\""]),
+        record(port, "krecik-dns.test", DnsRecordType::NS, &["ns.krecik-dns.test"]),
+        record(port, "www.krecik-dns.test", DnsRecordType::CNAME, &["krecik-dns.test."]),
+        record(
+            port,
+            "_sip._tcp.krecik-dns.test",
+            DnsRecordType::SRV,
+            &["10 5 5060 sip.krecik-dns.test"],
+        ),
+    ]);
+    assert_valid(&history, 5);
+}
+
+
+#[test]
+fn test_dns_answers_are_normalized() {
+    let port = serve_zone();
+    let history = dns_stories(vec![record(
+        port,
+        "krecik-dns.test",
+        DnsRecordType::MX,
+        &["20 MX2.krecik-dns.test.", "10 mx1.krecik-dns.test"],
+    )]);
+    assert_valid(&history, 1);
+}
+
+
+#[test]
+fn test_dns_truncated_answer_is_retried_over_tcp() {
+    let port = serve_zone();
+    let history = dns_stories(vec![record(
+        port,
+        "krecik-dns.test",
+        DnsRecordType::TXT,
+        &[&format!("\"{}\"", long_txt())],
+    )]);
+    assert_valid(&history, 1);
+}
+
+
+#[test]
+fn test_dns_missing_record_expects_no_answers() {
+    let port = serve_zone();
+    let history =
+        dns_stories(vec![record(port, "missing.krecik-dns.test", DnsRecordType::AAAA, &[])]);
+    assert_valid(&history, 1);
+}
+
+
+#[test]
+fn test_dns_record_mismatch() {
+    let port = serve_zone();
+    let history = dns_stories(vec![
+        record(port, "krecik-dns.test", DnsRecordType::MX, &["10 mx1.krecik-dns.test"]),
+        record(port, "krecik-dns.test", DnsRecordType::TXT, &["v=spf1 -all"]),
+    ]);
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().all(|story| story.error.is_some()), "{history:?}");
+    assert!(history.iter().any(|story| {
+        matches!(
+            &story.error,
+            Some(Unexpected::DnsRecordMismatch(label, _, expected, actual))
+                if label == "krecik-dns.test MX"
+                    && expected == "10 mx1.krecik-dns.test"
+                    && actual == "10 mx1.krecik-dns.test, 20 mx2.krecik-dns.test"
+        )
+    }));
+}
+
+
+#[test]
+fn test_dns_authoritative_servers() {
+    // glue record of the zone name server points to the same local server,
+    // answering differently without recursion:
+    let port = serve_zone();
+    let mut authoritative = record(port, "krecik-dns.test", DnsRecordType::A, &["10.0.0.2"]);
+    authoritative.authoritative = Some(true);
+    authoritative.port = Some(port);
+    let history = dns_stories(vec![authoritative]);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].success,
+        Some(Expected::DnsRecordValid(_, server, _)) if *server == format!("127.0.0.1:{port}")
+    ));
+}
+
+
+#[test]
+fn test_dns_invalid_resolver_is_error() {
+    let mut invalid = record(0, "krecik-dns.test", DnsRecordType::A, &["10.0.0.1"]);
+    invalid.resolvers = Some(vec!["invalid resolver".to_string()]);
+    let history = dns_stories(vec![invalid]);
+    assert_eq!(history.len(), 1);
+    assert!(
+        matches!(
+            &history[0].error,
+            Some(Unexpected::DnsQueryFailed(label, _)) if label == "krecik-dns.test A"
+        ),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_dns_missing_authoritative_servers_is_error() {
+    let port = serve_zone();
+    let mut authoritative = record(port, "krecik-other.test", DnsRecordType::A, &[]);
+    authoritative.authoritative = Some(true);
+    let history = dns_stories(vec![authoritative]);
+    assert_eq!(history.len(), 1);
+    assert!(
+        matches!(history[0].error, Some(Unexpected::DnsQueryFailed(_, _))),
+        "{history:?}"
+    );
+}
+
+
+/// Spawns resolver answering SERVFAIL to every query. Returns its port
+fn serve_failing() -> u16 {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 512];
+        while let Ok((length, client)) = socket.recv_from(&mut buffer) {
+            let query = Message::from_vec(&buffer[..length]).unwrap();
+            let mut response = Message::new();
+            response
+                .set_id(query.id())
+                .set_message_type(MessageType::Response)
+                .set_response_code(ResponseCode::ServFail);
+            socket.send_to(&response.to_vec().unwrap(), client).unwrap_or_default();
+        }
+    });
+    port
+}
+
+
+#[test]
+fn test_dns_no_resolvers_is_error() {
+    let mut record = record(0, "krecik-dns.test", DnsRecordType::A, &["10.0.0.1"]);
+    record.resolvers = Some(vec![]);
+    let history = dns_stories(vec![record]);
+    assert_eq!(history.len(), 1);
+    assert!(
+        matches!(
+            &history[0].error,
+            Some(Unexpected::DnsQueryFailed(label, _)) if label == "krecik-dns.test A"
+        ),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_dns_unresolvable_authoritative_servers_is_error() {
+    // name server of the zone has no glue record, and its name doesn't resolve:
+    let port = serve_dns(vec![(
+        ("krecik-lame.test", RecordType::NS, true),
+        (vec![RData::NS(NS(Name::from_ascii("ns.krecik-lame.invalid.").unwrap()))], vec![]),
+    )]);
+    let mut authoritative = record(port, "krecik-lame.test", DnsRecordType::A, &[]);
+    authoritative.authoritative = Some(true);
+    let history = dns_stories(vec![authoritative]);
+    assert_eq!(history.len(), 1);
+    assert!(
+        matches!(
+            &history[0].error,
+            Some(Unexpected::DnsQueryFailed(_, details))
+                if details.contains("krecik-lame.test")
+        ),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_dns_authoritative_servers_found_by_next_resolver() {
+    let port = serve_zone();
+    let mut authoritative = record(port, "krecik-dns.test", DnsRecordType::A, &["10.0.0.2"]);
+    authoritative.resolvers =
+        Some(vec![format!("127.0.0.1:{}", serve_failing()), format!("127.0.0.1:{port}")]);
+    authoritative.authoritative = Some(true);
+    authoritative.port = Some(port);
+    let history = dns_stories(vec![authoritative]);
+    assert_valid(&history, 1);
+}
+
+
+#[test]
+fn test_dns_failing_resolver_is_error() {
+    let port = serve_failing();
+    let history = dns_stories(vec![record(port, "krecik-dns.test", DnsRecordType::A, &[])]);
+    assert_eq!(history.len(), 1);
+    assert!(
+        matches!(
+            &history[0].error,
+            Some(Unexpected::DnsQueryFailed(label, details))
+                if label == &format!("krecik-dns.test A at: 127.0.0.1:{port}")
+                    && details.contains("Server Failure")
+        ),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_dns_stray_datagrams_are_skipped() {
+    // server sends garbage and answer to other query before the right answer:
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let server = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 512];
+        if let Ok((length, client)) = socket.recv_from(&mut buffer) {
+            let query = Message::from_vec(&buffer[..length]).unwrap();
+            let question = query.queries()[0].clone();
+            let response = |id: u16| {
+                let mut response = Message::new();
+                response
+                    .set_id(id)
+                    .set_message_type(MessageType::Response)
+                    .add_query(question.clone())
+                    .add_answer(Record::from_rdata(
+                        question.name().clone(),
+                        300,
+                        RData::A(A::new(10, 0, 0, 3)),
+                    ));
+                response.to_vec().unwrap()
+            };
+            socket.send_to(b"\x00garbage", client).unwrap_or_default();
+            socket
+                .send_to(&response(query.id().wrapping_add(1)), client)
+                .unwrap_or_default();
+            socket.send_to(&response(query.id()), client).unwrap_or_default();
+        }
+    });
+    let answers = dns_answers(server, "krecik-dns.test", DnsRecordType::A, true).unwrap();
+    assert_eq!(answers, vec!["10.0.0.3"]);
+}
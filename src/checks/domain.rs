@@ -10,6 +10,89 @@ pub struct Domain {
     /// Domain expectations
     #[serde(default = "default_domain_expectations")]
     pub expects: DomainExpectations,
+
+    /// Check every address the domain resolves to, reporting per-IP stories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_out_dns: Option<bool>,
+
+    /// Path to CA bundle used to verify certificate chain (instead of system CA bundle)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+
+    /// TLS port (default depends on starttls protocol, 443 otherwise)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    /// Plain text protocol upgraded to TLS before the handshake
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starttls: Option<StartTls>,
+
+    /// OCSP responder URL used instead of the one from the certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ocsp_responder: Option<String>,
+
+    /// RDAP server URL used for registration expiry lookups, ex. "https://rdap.org"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rdap_server: Option<String>,
+
+    /// WHOIS server ("host" or "host:port") used when RDAP lookup fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub whois_server: Option<String>,
+}
+
+
+impl Domain {
+    /// Port of TLS service checked
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or_else(|| {
+            self.starttls
+                .map(|protocol| protocol.default_port())
+                .unwrap_or(CHECK_DEFAULT_TLS_PORT)
+        })
+    }
+
+
+    /// Domain name used in stories. Non default port is included
+    pub fn label(&self) -> String {
+        match self.port() {
+            CHECK_DEFAULT_TLS_PORT => self.name.to_string(),
+            port => format!("{}:{port}", self.name),
+        }
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// Protocols upgraded to TLS with STARTTLS-like command
+pub enum StartTls {
+    /// SMTP STARTTLS (ports 25 and 587)
+    Smtp,
+
+    /// IMAP STARTTLS
+    Imap,
+
+    /// POP3 STLS
+    Pop3,
+
+    /// FTP AUTH TLS
+    Ftp,
+
+    /// PostgreSQL SSLRequest
+    Postgres,
+}
+
+
+impl StartTls {
+    /// Default port of the protocol
+    pub fn default_port(&self) -> u16 {
+        match self {
+            StartTls::Smtp => 25,
+            StartTls::Imap => 143,
+            StartTls::Pop3 => 110,
+            StartTls::Ftp => 21,
+            StartTls::Postgres => 5432,
+        }
+    }
 }
 
 
@@ -0,0 +1,564 @@
+use crate::{checks::tls::read_line, *};
+use rayon::prelude::*;
+use std::{
+    fmt::{self, Formatter},
+    io::{Error, ErrorKind, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// Email domain check structure
+pub struct EmailDomain {
+    /// Email domain name
+    pub name: String,
+
+    /// Email domain expectations
+    #[serde(default = "default_email_domain_expectations")]
+    pub expects: EmailDomainExpectations,
+
+    /// Resolvers to query, ex. "1.1.1.1" or "1.1.1.1:53" (system resolvers by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolvers: Option<Vec<String>>,
+
+    /// SMTP port of MX hosts (default: 25)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smtp_port: Option<u16>,
+}
+
+
+/// Email domains type
+pub type EmailDomains = Vec<EmailDomain>;
+
+
+/// Default email domain expectations
+pub fn default_email_domain_expectations() -> EmailDomainExpectations {
+    vec![
+        EmailDomainExpectation::ValidSpf,
+        EmailDomainExpectation::ValidDmarcPolicy(DmarcPolicy::None),
+        EmailDomainExpectation::ValidMxBanner,
+    ]
+}
+
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// DMARC policies, from the weakest one
+pub enum DmarcPolicy {
+    /// Monitoring only: "p=none"
+    None,
+
+    /// Failing mail goes to spam: "p=quarantine"
+    Quarantine,
+
+    /// Failing mail is rejected: "p=reject"
+    Reject,
+}
+
+
+impl fmt::Display for DmarcPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DmarcPolicy::None => write!(f, "none"),
+            DmarcPolicy::Quarantine => write!(f, "quarantine"),
+            DmarcPolicy::Reject => write!(f, "reject"),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Result of SPF record validation
+pub enum SpfStatus {
+    /// SPF record is valid, with given number of DNS lookups (includes are followed)
+    Valid(usize),
+
+    /// SPF record is missing or invalid, with reason
+    Invalid(String),
+
+    /// SPF record needs more DNS lookups than allowed (RFC 7208), with the count so far
+    TooManyLookups(usize),
+}
+
+
+/// SPF term kinds, by DNS lookups required during evaluation
+enum SpfTerm {
+    /// Term without DNS lookup: "all", "ip4", "ip6", "exp" and unknown modifiers
+    Static,
+
+    /// Term with single DNS lookup: "a", "mx", "ptr", "exists"
+    Lookup,
+
+    /// Term with DNS lookup of SPF record of another domain: "include", "redirect"
+    Nested(String),
+}
+
+
+/// Executes email domain checks, returns Stories
+#[instrument(skip(checks))]
+pub fn check_email_domains(checks: &[Check]) -> Stories {
+    checks
+        .into_par_iter()
+        .flat_map(|check| {
+            let notifier = check.notifier.clone();
+            check
+                .email_domains
+                .par_iter()
+                .flat_map(|email_domains| {
+                    let mut all_email_domains = email_domains.clone();
+                    all_email_domains.sort();
+                    all_email_domains.dedup();
+                    all_email_domains
+                        .par_iter()
+                        .flat_map(|email_domain| {
+                            check_email_domain(email_domain, notifier.clone())
+                        })
+                        .collect::<Stories>()
+                })
+                .collect::<Stories>()
+        })
+        .collect()
+}
+
+
+/// Check all expectations of given email domain
+#[instrument]
+fn check_email_domain(email_domain: &EmailDomain, notifier: Option<String>) -> Stories {
+    let resolvers =
+        match dns_resolvers(email_domain.resolvers.as_deref(), CHECK_DEFAULT_DNS_PORT) {
+            Ok(resolvers) => resolvers,
+            Err(err) => {
+                return vec![Story::error(
+                    Unexpected::EmailDomainLookupFailed(
+                        email_domain.name.to_string(),
+                        err.to_string(),
+                    ),
+                    notifier,
+                )]
+            }
+        };
+    email_domain
+        .expects
+        .par_iter()
+        .flat_map(|expectation| {
+            handle_email_domain_expectation(
+                email_domain,
+                &resolvers,
+                expectation,
+                notifier.clone(),
+            )
+        })
+        .collect()
+}
+
+
+/// Build Stories from an EmailDomainExpectation
+#[instrument(skip(resolvers))]
+fn handle_email_domain_expectation(
+    email_domain: &EmailDomain,
+    resolvers: &[SocketAddr],
+    expectation: &EmailDomainExpectation,
+    notifier: Option<String>,
+) -> Stories {
+    let domain = &email_domain.name;
+    let lookup_failed = |label: String, err: Error, notifier: Option<String>| {
+        Story::error(Unexpected::EmailDomainLookupFailed(label, err.to_string()), notifier)
+    };
+    match expectation {
+        EmailDomainExpectation::ValidSpf => {
+            vec![match spf_status(resolvers, domain) {
+                Ok(SpfStatus::Valid(lookups)) => {
+                    Story::success(
+                        Expected::SpfValid(domain.to_string(), lookups),
+                        notifier,
+                    )
+                }
+
+                Ok(SpfStatus::Invalid(reason)) => {
+                    Story::error(
+                        Unexpected::SpfInvalid(domain.to_string(), reason),
+                        notifier,
+                    )
+                }
+
+                Ok(SpfStatus::TooManyLookups(lookups)) => {
+                    Story::error(
+                        Unexpected::SpfTooManyLookups(
+                            domain.to_string(),
+                            lookups,
+                            CHECK_SPF_MAX_DNS_LOOKUPS,
+                        ),
+                        notifier,
+                    )
+                }
+
+                Err(err) => lookup_failed(format!("{domain} SPF"), err, notifier),
+            }]
+        }
+
+        EmailDomainExpectation::ValidDmarcPolicy(required) => {
+            vec![match dmarc_policy(resolvers, domain) {
+                Ok(Some(policy)) if policy >= *required => {
+                    Story::success(
+                        Expected::DmarcPolicyValid(domain.to_string(), policy.to_string()),
+                        notifier,
+                    )
+                }
+
+                Ok(Some(policy)) => {
+                    Story::error(
+                        Unexpected::DmarcPolicyTooWeak(
+                            domain.to_string(),
+                            policy.to_string(),
+                            required.to_string(),
+                        ),
+                        notifier,
+                    )
+                }
+
+                Ok(None) => {
+                    Story::error(
+                        Unexpected::DmarcPolicyMissing(domain.to_string()),
+                        notifier,
+                    )
+                }
+
+                Err(err) => lookup_failed(format!("{domain} DMARC"), err, notifier),
+            }]
+        }
+
+        EmailDomainExpectation::ValidDkimSelectors(selectors) => {
+            selectors
+                .iter()
+                .map(|selector| {
+                    match dkim_key_published(resolvers, domain, selector) {
+                        Ok(true) => {
+                            Story::success(
+                                Expected::DkimKeyPublished(
+                                    domain.to_string(),
+                                    selector.to_string(),
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+
+                        Ok(false) => {
+                            Story::error(
+                                Unexpected::DkimKeyMissing(
+                                    domain.to_string(),
+                                    selector.to_string(),
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+
+                        Err(err) => {
+                            lookup_failed(
+                                format!("{domain} DKIM {selector}"),
+                                err,
+                                notifier.clone(),
+                            )
+                        }
+                    }
+                })
+                .collect()
+        }
+
+        EmailDomainExpectation::ValidMxBanner => {
+            let port = email_domain.smtp_port.unwrap_or(CHECK_DEFAULT_SMTP_PORT);
+            match mx_hosts(resolvers, domain) {
+                Ok(hosts) if hosts.is_empty() => {
+                    vec![Story::error(
+                        Unexpected::MxHostsMissing(domain.to_string()),
+                        notifier,
+                    )]
+                }
+
+                Ok(hosts) => {
+                    hosts
+                        .par_iter()
+                        .map(|host| {
+                            match smtp_banner(host, port) {
+                                Ok(banner) => {
+                                    debug!("MX host: {host} greets with: {banner}");
+                                    Story::success(
+                                        Expected::MxBannerValid(
+                                            domain.to_string(),
+                                            host.to_string(),
+                                        ),
+                                        notifier.clone(),
+                                    )
+                                }
+
+                                Err(err) => {
+                                    Story::error(
+                                        Unexpected::MxBannerMissing(
+                                            domain.to_string(),
+                                            host.to_string(),
+                                            err.to_string(),
+                                        ),
+                                        notifier.clone(),
+                                    )
+                                }
+                            }
+                        })
+                        .collect()
+                }
+
+                Err(err) => vec![lookup_failed(format!("{domain} MX"), err, notifier)],
+            }
+        }
+    }
+}
+
+
+/// Validate SPF record of given domain: syntax, and number of DNS lookups
+#[instrument]
+pub fn spf_status(resolvers: &[SocketAddr], domain: &str) -> Result<SpfStatus, Error> {
+    spf_status_at_depth(resolvers, domain, 0)
+}
+
+
+/// Validate SPF record of given domain, nested with include or redirect
+fn spf_status_at_depth(
+    resolvers: &[SocketAddr],
+    domain: &str,
+    depth: usize,
+) -> Result<SpfStatus, Error> {
+    if depth > CHECK_SPF_MAX_DNS_LOOKUPS {
+        return Ok(SpfStatus::TooManyLookups(depth));
+    }
+    let records: Vec<String> = dns_lookup(resolvers, domain, DnsRecordType::TXT)?
+        .into_iter()
+        .filter(|record| {
+            record.eq_ignore_ascii_case("v=spf1")
+                || record.to_lowercase().starts_with("v=spf1 ")
+        })
+        .collect();
+    let record = match records.as_slice() {
+        [] => return Ok(SpfStatus::Invalid(format!("No SPF record at: {domain}"))),
+        [record] => record,
+        _ => return Ok(SpfStatus::Invalid(format!("Multiple SPF records at: {domain}"))),
+    };
+
+    let mut lookups = 0;
+    for term in record.split_whitespace().skip(1) {
+        match spf_term(term) {
+            Ok(SpfTerm::Static) => {}
+
+            Ok(SpfTerm::Lookup) => lookups += 1,
+
+            Ok(SpfTerm::Nested(target)) if target.contains('%') => {
+                // macros are expanded during evaluation, so target can't be followed here:
+                lookups += 1
+            }
+
+            Ok(SpfTerm::Nested(target)) => {
+                lookups += 1;
+                match spf_status_at_depth(resolvers, &target, depth + 1)? {
+                    SpfStatus::Valid(nested_lookups) => lookups += nested_lookups,
+                    SpfStatus::TooManyLookups(nested_lookups) => {
+                        return Ok(SpfStatus::TooManyLookups(lookups + nested_lookups))
+                    }
+                    invalid => return Ok(invalid),
+                }
+            }
+
+            Err(reason) => {
+                return Ok(SpfStatus::Invalid(format!(
+                    "{reason}: \"{term}\" in SPF record of: {domain}"
+                )))
+            }
+        }
+        if lookups > CHECK_SPF_MAX_DNS_LOOKUPS {
+            return Ok(SpfStatus::TooManyLookups(lookups));
+        }
+    }
+    Ok(SpfStatus::Valid(lookups))
+}
+
+
+/// Parse single SPF term (mechanism or modifier)
+fn spf_term(term: &str) -> Result<SpfTerm, String> {
+    let is_name = |name: &str| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    };
+    if let Some((name, value)) = term.split_once('=') {
+        if is_name(name) {
+            return match name.to_lowercase().as_str() {
+                "redirect" if !value.is_empty() => Ok(SpfTerm::Nested(value.to_string())),
+                "redirect" | "exp" if value.is_empty() => Err("Missing domain".to_string()),
+                _ => Ok(SpfTerm::Static),
+            };
+        }
+    }
+
+    let mechanism = term.trim_start_matches(['+', '-', '~', '?']);
+    let (name, argument) = match mechanism.find([':', '/']) {
+        Some(index) => mechanism.split_at(index),
+        None => (mechanism, ""),
+    };
+    let domain = argument.strip_prefix(':');
+    match name.to_lowercase().as_str() {
+        "all" if argument.is_empty() => Ok(SpfTerm::Static),
+
+        "include" => {
+            match domain {
+                Some(domain) if !domain.is_empty() => Ok(SpfTerm::Nested(domain.to_string())),
+                _ => Err("Missing domain".to_string()),
+            }
+        }
+
+        "exists" => {
+            match domain {
+                Some(domain) if !domain.is_empty() => Ok(SpfTerm::Lookup),
+                _ => Err("Missing domain".to_string()),
+            }
+        }
+
+        "a" | "mx" | "ptr" => {
+            match domain {
+                Some("") => Err("Missing domain".to_string()),
+                _ => Ok(SpfTerm::Lookup),
+            }
+        }
+
+        "ip4" => {
+            match domain {
+                Some(network) if valid_network::<Ipv4Addr>(network, 32) => Ok(SpfTerm::Static),
+                _ => Err("Invalid IPv4 network".to_string()),
+            }
+        }
+
+        "ip6" => {
+            match domain {
+                Some(network) if valid_network::<Ipv6Addr>(network, 128) => {
+                    Ok(SpfTerm::Static)
+                }
+                _ => Err("Invalid IPv6 network".to_string()),
+            }
+        }
+
+        _ => Err("Unknown mechanism".to_string()),
+    }
+}
+
+
+/// Check if network is an address, with optional prefix length, ex. "10.0.0.0/8"
+fn valid_network<Address: FromStr>(network: &str, max_prefix: u8) -> bool {
+    let (address, prefix) = network.split_once('/').unwrap_or((network, ""));
+    address.parse::<Address>().is_ok()
+        && (prefix.is_empty() || prefix.parse::<u8>().is_ok_and(|prefix| prefix <= max_prefix))
+}
+
+
+/// DMARC policy published by given domain. None if there's no (single, valid) DMARC record
+#[instrument]
+pub fn dmarc_policy(
+    resolvers: &[SocketAddr],
+    domain: &str,
+) -> Result<Option<DmarcPolicy>, Error> {
+    let records: Vec<String> =
+        dns_lookup(resolvers, &format!("_dmarc.{domain}"), DnsRecordType::TXT)?
+            .into_iter()
+            .filter(|record| record_tag(record, "v").as_deref() == Some("DMARC1"))
+            .collect();
+    match records.as_slice() {
+        [record] => {
+            Ok(
+                match record_tag(record, "p").map(|policy| policy.to_lowercase()).as_deref() {
+                    Some("none") => Some(DmarcPolicy::None),
+                    Some("quarantine") => Some(DmarcPolicy::Quarantine),
+                    Some("reject") => Some(DmarcPolicy::Reject),
+                    _ => None,
+                },
+            )
+        }
+        _ => Ok(None),
+    }
+}
+
+
+/// Check if DKIM selector of given domain publishes a public key (not revoked with empty "p=")
+#[instrument]
+pub fn dkim_key_published(
+    resolvers: &[SocketAddr],
+    domain: &str,
+    selector: &str,
+) -> Result<bool, Error> {
+    let records = dns_lookup(
+        resolvers,
+        &format!("{selector}._domainkey.{domain}"),
+        DnsRecordType::TXT,
+    )?;
+    Ok(records.iter().any(|record| {
+        record_tag(record, "v").is_none_or(|version| version == "DKIM1")
+            && record_tag(record, "p").is_some_and(|key| !key.is_empty())
+    }))
+}
+
+
+/// Value of tag from "tag=value; …" record (DMARC, DKIM), with whitespace removed
+fn record_tag(record: &str, tag: &str) -> Option<String> {
+    record.split(';').find_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        if name.trim() == tag {
+            Some(value.split_whitespace().collect())
+        } else {
+            None
+        }
+    })
+}
+
+
+/// MX hosts of given domain, ordered by preference. Domain itself is used as implicit MX
+/// when there are no MX records. Null MX ("0 .") gives no hosts
+#[instrument]
+pub fn mx_hosts(resolvers: &[SocketAddr], domain: &str) -> Result<Vec<String>, Error> {
+    let mut records: Vec<(u16, String)> = dns_lookup(resolvers, domain, DnsRecordType::MX)?
+        .iter()
+        .filter_map(|record| {
+            let (preference, host) = record.split_once(' ').unwrap_or((record, ""));
+            Some((preference.parse().ok()?, host.to_string()))
+        })
+        .collect();
+    if records.is_empty() {
+        return Ok(vec![domain.to_string()]);
+    }
+    records.sort();
+    Ok(records
+        .into_iter()
+        .map(|(_, host)| host)
+        .filter(|host| !host.is_empty())
+        .collect())
+}
+
+
+/// Connect to SMTP server, and read its greeting. Returns the (last) banner line
+#[instrument]
+pub fn smtp_banner(host: &str, port: u16) -> Result<String, Error> {
+    let mut stream = tcp_connect(host, None, port)?;
+    let banner = loop {
+        let line = read_line(&mut stream)?;
+        if line.get(3..4) != Some("-") {
+            break line;
+        }
+    };
+    stream.write_all(b"QUIT\r\n").unwrap_or_default();
+    if banner.starts_with("220") {
+        Ok(banner)
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Expected SMTP greeting: \"220\", got: \"{banner}\""),
+        ))
+    }
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
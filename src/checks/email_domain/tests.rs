@@ -0,0 +1,385 @@
+use hickory_proto::rr::{
+    rdata::{MX, TXT},
+    Name, RData, RecordType,
+};
+use std::{
+    io::Write,
+    net::{TcpListener, UdpSocket},
+    thread,
+};
+
+use crate::{test_fixtures::*, *};
+
+
+/// TXT records, each with single character string
+fn txt(records: &[&str]) -> Vec<RData> {
+    records
+        .iter()
+        .map(|record| RData::TXT(TXT::new(vec![record.to_string()])))
+        .collect()
+}
+
+
+/// MX record of given preference and host
+fn mx(preference: u16, host: &str) -> Vec<RData> {
+    vec![RData::MX(MX::new(preference, Name::from_ascii(host).unwrap()))]
+}
+
+
+/// Spawns stub DNS server with records of test mail domains. Returns its port
+fn serve_mail_zone() -> u16 {
+    let many_lookups = "v=spf1 a:a1.krecik-bad.test a:a2.krecik-bad.test mx ptr \
+                        a:a3.krecik-bad.test a:a4.krecik-bad.test \
+                        exists:a5.krecik-bad.test \
+                        include:_spf.krecik-mail.test ~all";
+    let ten_lookups = "v=spf1 a mx ptr a:1.krecik-limit.test a:2.krecik-limit.test \
+                       a:3.krecik-limit.test a:4.krecik-limit.test \
+                       exists:5.krecik-limit.test mx:6.krecik-limit.test \
+                       a:7.krecik-limit.test ip4:10.0.0.1 -all";
+    serve_dns(vec![
+        (
+            ("krecik-mail.test", RecordType::TXT, true),
+            (
+                txt(&[
+                    "v=spf1 mx include:_spf.krecik-mail.test ip4:10.0.0.0/8 ~all",
+                    "krecik-verification=abc",
+                ]),
+                vec![],
+            ),
+        ),
+        (
+            ("_spf.krecik-mail.test", RecordType::TXT, true),
+            (txt(&["v=spf1 a mx:mx.krecik-mail.test ip6:2001:db8::/32 a/24 -all"]), vec![]),
+        ),
+        (
+            ("_dmarc.krecik-mail.test", RecordType::TXT, true),
+            (txt(&["v=DMARC1; p=quarantine; rua=mailto:dmarc@krecik-mail.test"]), vec![]),
+        ),
+        (
+            ("mail2024._domainkey.krecik-mail.test", RecordType::TXT, true),
+            (txt(&["v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8A"]), vec![]),
+        ),
+        (
+            ("old._domainkey.krecik-mail.test", RecordType::TXT, true),
+            (txt(&["v=DKIM1; p="]), vec![]),
+        ),
+        (("krecik-mail.test", RecordType::MX, true), (mx(10, "localhost."), vec![])),
+        (("krecik-bad.test", RecordType::TXT, true), (txt(&[many_lookups]), vec![])),
+        (("krecik-limit.test", RecordType::TXT, true), (txt(&[ten_lookups]), vec![])),
+        (
+            ("krecik-loop.test", RecordType::TXT, true),
+            (txt(&["v=spf1 include:krecik-loop.test -all"]), vec![]),
+        ),
+        (
+            ("krecik-invalid.test", RecordType::TXT, true),
+            (txt(&["v=spf1 ip4:300.0.0.1 -all"]), vec![]),
+        ),
+        (
+            ("krecik-multiple.test", RecordType::TXT, true),
+            (txt(&["v=spf1 -all", "v=spf1 mx -all"]), vec![]),
+        ),
+        (
+            ("_dmarc.krecik-strict.test", RecordType::TXT, true),
+            (txt(&["v=DMARC1; p=reject"]), vec![]),
+        ),
+        (("krecik-null-mx.test", RecordType::MX, true), (mx(0, "."), vec![])),
+    ])
+}
+
+
+/// Spawns local SMTP server greeting with multiline banner. Returns its port
+fn serve_smtp() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            stream
+                .write_all(b"220-krecik.test ESMTP\r\n220 Ready\r\n")
+                .unwrap_or_default();
+            read_client_line(&mut stream);
+        }
+    });
+    port
+}
+
+
+/// Port nobody listens on (TCP and UDP)
+fn closed_port() -> u16 {
+    UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+
+/// Checks given expectations of email domain, resolved by DNS server on given port
+fn email_domain_stories(
+    name: &str,
+    expects: EmailDomainExpectations,
+    dns_port: u16,
+    smtp_port: u16,
+) -> Stories {
+    check_email_domains(&[Check {
+        email_domains: Some(vec![EmailDomain {
+            name: name.to_string(),
+            expects,
+            resolvers: Some(vec![format!("127.0.0.1:{dns_port}")]),
+            smtp_port: Some(smtp_port),
+        }]),
+        notifier: Some("mail-team".to_string()),
+        ..Check::default()
+    }])
+}
+
+
+/// Checks SPF record of given domain
+fn spf_story(name: &str) -> Story {
+    let expects = vec![EmailDomainExpectation::ValidSpf];
+    let history = email_domain_stories(name, expects, serve_mail_zone(), 0);
+    assert_eq!(history.len(), 1, "{history:?}");
+    history[0].clone()
+}
+
+
+#[test]
+fn test_valid_mail_domain() {
+    let history = email_domain_stories(
+        "krecik-mail.test",
+        vec![
+            EmailDomainExpectation::ValidSpf,
+            EmailDomainExpectation::ValidDmarcPolicy(DmarcPolicy::Quarantine),
+            EmailDomainExpectation::ValidDkimSelectors(vec!["mail2024".to_string()]),
+            EmailDomainExpectation::ValidMxBanner,
+        ],
+        serve_mail_zone(),
+        serve_smtp(),
+    );
+    assert_eq!(history.len(), 4);
+    assert!(history.iter().all(|story| story.success.is_some()), "{history:?}");
+    assert!(history.iter().all(|story| story.notifier.as_deref() == Some("mail-team")));
+}
+
+
+#[test]
+fn test_spf_lookups_include_nested_records() {
+    // mx + include + (a + mx:… + a/24) of included record:
+    assert_eq!(
+        spf_story("krecik-mail.test").success,
+        Some(Expected::SpfValid("krecik-mail.test".to_string(), 5))
+    );
+}
+
+
+#[test]
+fn test_spf_lookup_limit_reached() {
+    assert_eq!(
+        spf_story("krecik-limit.test").success,
+        Some(Expected::SpfValid("krecik-limit.test".to_string(), CHECK_SPF_MAX_DNS_LOOKUPS))
+    );
+}
+
+
+#[test]
+fn test_spf_lookup_limit_exceeded() {
+    // 8 lookups, and 3 more of included record:
+    assert_eq!(
+        spf_story("krecik-bad.test").error,
+        Some(Unexpected::SpfTooManyLookups(
+            "krecik-bad.test".to_string(),
+            11,
+            CHECK_SPF_MAX_DNS_LOOKUPS
+        ))
+    );
+}
+
+
+#[test]
+fn test_spf_include_depth_is_limited() {
+    assert!(matches!(
+        spf_story("krecik-loop.test").error,
+        Some(Unexpected::SpfTooManyLookups(_, lookups, CHECK_SPF_MAX_DNS_LOOKUPS))
+            if lookups > CHECK_SPF_MAX_DNS_LOOKUPS
+    ));
+}
+
+
+#[test]
+fn test_spf_invalid_records() {
+    for name in ["krecik-invalid.test", "krecik-multiple.test", "krecik-missing.test"] {
+        let story = spf_story(name);
+        assert!(matches!(story.error, Some(Unexpected::SpfInvalid(_, _))), "{story:?}");
+    }
+}
+
+
+#[test]
+fn test_dmarc_policy_ordering() {
+    assert!(DmarcPolicy::None < DmarcPolicy::Quarantine);
+    assert!(DmarcPolicy::Quarantine < DmarcPolicy::Reject);
+
+    let history = email_domain_stories(
+        "krecik-strict.test",
+        vec![
+            EmailDomainExpectation::ValidDmarcPolicy(DmarcPolicy::None),
+            EmailDomainExpectation::ValidDmarcPolicy(DmarcPolicy::Quarantine),
+            EmailDomainExpectation::ValidDmarcPolicy(DmarcPolicy::Reject),
+        ],
+        serve_mail_zone(),
+        0,
+    );
+    assert_eq!(history.len(), 3);
+    assert!(history.iter().all(|story| story.success.is_some()), "{history:?}");
+}
+
+
+#[test]
+fn test_dmarc_policy_too_weak() {
+    let history = email_domain_stories(
+        "krecik-mail.test",
+        vec![EmailDomainExpectation::ValidDmarcPolicy(DmarcPolicy::Reject)],
+        serve_mail_zone(),
+        0,
+    );
+    assert_eq!(
+        history[0].error,
+        Some(Unexpected::DmarcPolicyTooWeak(
+            "krecik-mail.test".to_string(),
+            "quarantine".to_string(),
+            "reject".to_string(),
+        ))
+    );
+}
+
+
+#[test]
+fn test_dmarc_policy_missing() {
+    let history = email_domain_stories(
+        "krecik-bad.test",
+        vec![EmailDomainExpectation::ValidDmarcPolicy(DmarcPolicy::None)],
+        serve_mail_zone(),
+        0,
+    );
+    assert_eq!(
+        history[0].error,
+        Some(Unexpected::DmarcPolicyMissing("krecik-bad.test".to_string()))
+    );
+}
+
+
+#[test]
+fn test_dkim_keys_revoked_or_missing() {
+    let history = email_domain_stories(
+        "krecik-mail.test",
+        vec![EmailDomainExpectation::ValidDkimSelectors(vec![
+            "old".to_string(),
+            "missing".to_string(),
+        ])],
+        serve_mail_zone(),
+        0,
+    );
+    assert_eq!(history.len(), 2);
+    assert!(
+        history
+            .iter()
+            .all(|story| matches!(story.error, Some(Unexpected::DkimKeyMissing(_, _)))),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_mx_banner_missing() {
+    let history = email_domain_stories(
+        "krecik-mail.test",
+        vec![EmailDomainExpectation::ValidMxBanner],
+        serve_mail_zone(),
+        closed_port(),
+    );
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].error,
+        Some(Unexpected::MxBannerMissing(_, host, _)) if host == "localhost"
+    ));
+}
+
+
+#[test]
+fn test_implicit_mx_is_the_domain_itself() {
+    let resolvers = [format!("127.0.0.1:{}", serve_mail_zone()).parse().unwrap()];
+    assert_eq!(mx_hosts(&resolvers, "krecik-bad.test").unwrap(), vec!["krecik-bad.test"]);
+}
+
+
+#[test]
+fn test_null_mx_without_hosts_is_error() {
+    let resolvers = [format!("127.0.0.1:{}", serve_mail_zone()).parse().unwrap()];
+    assert!(mx_hosts(&resolvers, "krecik-null-mx.test").unwrap().is_empty());
+
+    let history = email_domain_stories(
+        "krecik-null-mx.test",
+        vec![EmailDomainExpectation::ValidMxBanner],
+        serve_mail_zone(),
+        0,
+    );
+    assert_eq!(
+        history.into_iter().map(|story| story.error).collect::<Vec<_>>(),
+        vec![Some(Unexpected::MxHostsMissing("krecik-null-mx.test".to_string()))]
+    );
+}
+
+
+#[test]
+fn test_unreachable_resolver_is_error() {
+    let history = email_domain_stories(
+        "krecik-mail.test",
+        default_email_domain_expectations(),
+        closed_port(),
+        0,
+    );
+    assert_eq!(history.len(), 3);
+    for (story, lookup) in history.iter().zip(["SPF", "DMARC", "MX"]) {
+        assert!(
+            matches!(
+                &story.error,
+                Some(Unexpected::EmailDomainLookupFailed(label, _))
+                    if *label == format!("krecik-mail.test {lookup}")
+            ),
+            "{story:?}"
+        );
+        assert_eq!(story.notifier.as_deref(), Some("mail-team"));
+    }
+}
+
+
+#[test]
+fn test_invalid_resolver_is_error() {
+    let history = check_email_domains(&[Check {
+        email_domains: Some(vec![EmailDomain {
+            name: "krecik-mail.test".to_string(),
+            expects: default_email_domain_expectations(),
+            resolvers: Some(vec!["invalid resolver".to_string()]),
+            smtp_port: None,
+        }]),
+        ..Check::default()
+    }]);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].error,
+        Some(Unexpected::EmailDomainLookupFailed(name, _)) if name == "krecik-mail.test"
+    ));
+}
+
+
+#[test]
+fn test_smtp_banner_line_is_limited() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            stream
+                .write_all(&vec![b'2'; CHECK_MAX_LINE_LENGTH * 2])
+                .unwrap_or_default();
+        }
+    });
+    assert_eq!(
+        smtp_banner("127.0.0.1", port).unwrap_err().kind(),
+        std::io::ErrorKind::InvalidData
+    );
+}
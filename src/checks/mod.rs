@@ -4,6 +4,30 @@ pub mod domain;
 /// Page checks:
 pub mod page;
 
+/// DNS record checks:
+pub mod dns;
+
+/// Email domain checks (SPF, DMARC, DKIM, MX):
+pub mod email_domain;
+
+/// Raw TCP service checks:
+pub mod tcp;
+
+/// Database liveness checks (PostgreSQL, MySQL, Redis):
+pub mod database;
+
+/// SSH server banner and host key checks:
+pub mod ssh;
+
+/// TLS connections and certificate inspection for domain checks:
+pub mod tls;
+
+/// Domain registration expiry lookups (RDAP, WHOIS):
+pub mod registration;
+
+/// Page check authentication schemes:
+pub mod auth;
+
 /// Mapper for default remote Checks lilst JSON resource: Pongo
 pub mod pongo;
 
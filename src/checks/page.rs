@@ -5,6 +5,9 @@ use curl::{multi::Easy2Handle, MultiError};
 /// Type alias for long type name:
 pub type CurlHandler = Result<Easy2Handle<Collector>, MultiError>;
 
+/// Page request with resolved secrets: request body, authentication, TLS client key passphrase
+pub type PageRequest = (Vec<u8>, Option<ResolvedAuth>, Option<String>);
+
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 /// Page check structure
@@ -19,6 +22,10 @@ pub struct Page {
     /// Curl options
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<PageOptions>,
+
+    /// Check every address the host name resolves to, reporting per-IP stories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_out_dns: Option<bool>,
 }
 
 
@@ -48,6 +55,10 @@ pub struct PageOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub post_data: Option<String>,
 
+    /// Path to a file with HTTP request body (used instead of post_data)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_file: Option<String>,
+
     /// HTTP cookies
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cookies: Option<Vec<String>>,
@@ -83,15 +94,64 @@ pub struct PageOptions {
     /// TLS host verification
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ssl_verify_host: Option<bool>,
+
+    /// Path to TLS client certificate (PEM)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<String>,
+
+    /// Path to TLS client private key (PEM)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+
+    /// TLS client private key passphrase
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_key_passphrase: Option<Secret>,
+
+    /// Path to CA bundle used to verify the peer (instead of system CA bundle)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+
+    /// Pinned public key of the peer, ex. "sha256//base64-encoded-sha256-of-public-key"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned_public_key: Option<String>,
+
+    /// Proxy URL, ex. "http://proxy:3128" or "socks5h://proxy:1080"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// Local interface name or source IP used for outgoing connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+
+    /// IP version used to resolve host names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_version: Option<IpVersion>,
+
+    /// Host name to IP overrides, ex. "example.com:443:10.0.0.1" (like curl --resolve)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolve: Option<Vec<String>>,
+
+    /// Connection target overrides, ex. "example.com:443:backend:8443" (curl --connect-to)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_to: Option<Vec<String>>,
+
+    /// HTTP protocol version used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_version: Option<HttpVersion>,
+
+    /// HTTP authentication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<PageAuth>,
 }
 
 
 /// Implement JSON serialization on .to_string():
-impl ToString for PageOptions {
-    fn to_string(&self) -> String {
-        serde_json::to_string(&self).unwrap_or_else(|_| {
+impl fmt::Display for PageOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(&self).unwrap_or_else(|_| {
             String::from("{\"status\": \"PageOptions serialization failure\"}")
-        })
+        });
+        write!(f, "{json}")
     }
 }
 
@@ -110,13 +170,63 @@ impl Default for PageOptions {
             cookies: None,
             headers: None,
             post_data: None,
+            body_file: None,
             verbose: None,
+            client_cert: None,
+            client_key: None,
+            client_key_passphrase: None,
+            ca_bundle: None,
+            pinned_public_key: None,
+            proxy: None,
+            interface: None,
+            ip_version: None,
+            resolve: None,
+            connect_to: None,
+            http_version: None,
+            auth: None,
         }
     }
 }
 
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// HTTP protocol versions. By default HTTP/2 is negotiated for HTTPS, HTTP/1.1 for HTTP
+pub enum HttpVersion {
+    /// HTTP/1.0
+    #[serde(rename = "1.0")]
+    Http10,
+
+    /// HTTP/1.1
+    #[serde(rename = "1.1")]
+    Http11,
+
+    /// HTTP/2 (with fallback to HTTP/1.1)
+    #[serde(rename = "2")]
+    Http2,
+
+    /// HTTP/2 without HTTP/1.1 Upgrade (no fallback)
+    #[serde(rename = "2-prior-knowledge")]
+    Http2PriorKnowledge,
+}
+
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// IP versions used to resolve host names
+#[derive(Default)]
+pub enum IpVersion {
+    /// Any IP version (system default)
+    #[default]
+    Any,
+
+    /// IPv4 only
+    V4,
+
+    /// IPv6 only
+    V6,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 /// HTTP methods allowed
 #[derive(Default)]
 pub enum Method {
@@ -135,4 +245,30 @@ pub enum Method {
 
     /// HTTP DELETE
     Delete,
+
+    /// HTTP PATCH
+    Patch,
+
+    /// HTTP OPTIONS
+    Options,
+
+    /// Any other HTTP method, sent as it is, ex. "PURGE"
+    Custom(String),
+}
+
+
+impl Method {
+    /// HTTP method name
+    pub fn name(&self) -> String {
+        match self {
+            Method::Head => String::from("HEAD"),
+            Method::Put => String::from("PUT"),
+            Method::Get => String::from("GET"),
+            Method::Post => String::from("POST"),
+            Method::Delete => String::from("DELETE"),
+            Method::Patch => String::from("PATCH"),
+            Method::Options => String::from("OPTIONS"),
+            Method::Custom(verb) => verb.to_string(),
+        }
+    }
 }
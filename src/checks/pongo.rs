@@ -28,6 +28,13 @@ pub fn collect_pongo_domains(check: &PongoCheck) -> Vec<Domain> {
                     Some(Domain {
                         name: vhost.to_string(),
                         expects: default_domain_expectations(),
+                        fan_out_dns: None,
+                        ca_bundle: None,
+                        port: None,
+                        starttls: None,
+                        ocsp_responder: None,
+                        rdap_server: None,
+                        whois_server: None,
                     })
                 })
                 .collect::<Option<Domains>>()
@@ -75,6 +82,7 @@ pub fn collect_pongo_hosts(check: &PongoCheck, mapper: &PongoRemoteMapper) -> Ve
                                 url: format!("{CHECK_DEFAULT_PROTOCOL}{vhost}/{ams}/"),
                                 expects: pongo_page_expectations(),
                                 options: options.to_owned(),
+                                fan_out_dns: None,
                             })
                         } else {
                             debug!("Skipping not active client: {client}");
@@ -96,6 +104,7 @@ pub fn collect_pongo_hosts(check: &PongoCheck, mapper: &PongoRemoteMapper) -> Ve
                                 url: vhost.to_string(),
                                 expects: showroom_page_expectations(),
                                 options: options.to_owned(),
+                                fan_out_dns: None,
                             })
                         } else {
                             debug!("Skipping not active client: {client}");
@@ -123,6 +132,7 @@ pub fn collect_pongo_hosts(check: &PongoCheck, mapper: &PongoRemoteMapper) -> Ve
                                 url: format!("{CHECK_DEFAULT_PROTOCOL}{vhost}/graphql?health"),
                                 expects: pongo_api_expectations(),
                                 options: api_options.to_owned(),
+                                fan_out_dns: None,
                             })
                         } else {
                             debug!("Skipping not active client: {}", &client);
@@ -152,7 +162,7 @@ pub fn read_pongo_mapper(pongo_mapper: &str) -> PongoRemoteMapper {
 /// Read checks from Pongo remote
 #[instrument]
 pub fn get_pongo_checks(url: &str) -> PongoChecks {
-    let mut easy = Easy2::new(Collector(Vec::new()));
+    let mut easy = Easy2::new(Collector::default());
     easy.get(true).unwrap_or_default();
     easy.url(url).unwrap_or_default();
     easy.perform().unwrap_or_default();
@@ -287,10 +297,11 @@ pub struct PongoRemoteMapper {
 
 
 /// Implement JSON serialization on .to_string():
-impl ToString for PongoRemoteMapper {
-    fn to_string(&self) -> String {
-        serde_json::to_string(&self).unwrap_or_else(|_| {
+impl fmt::Display for PongoRemoteMapper {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(&self).unwrap_or_else(|_| {
             String::from("{\"status\": \"PongoRemoteMapper serialization failure\"}")
-        })
+        });
+        write!(f, "{json}")
     }
 }
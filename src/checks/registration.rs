@@ -0,0 +1,236 @@
+use crate::*;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use curl::easy::{Easy2, List};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+
+/// Registration expiry cache: (domain, RDAP server, WHOIS server) => (expiry, fetch time).
+/// Lookup failures are kept as (error kind, details)
+type Registrations = HashMap<
+    (String, String, String),
+    (Result<DateTime<Utc>, (ErrorKind, String)>, Instant),
+>;
+
+
+lazy_static! {
+    /// Cache of domain registration expiry dates
+    static ref REGISTRATIONS: Mutex<Registrations> = Mutex::new(HashMap::new());
+
+    /// WHOIS lines with registration expiry date
+    static ref WHOIS_EXPIRY: Regex = Regex::new(concat!(
+        r"(?im)^\s*(registry expiry date|registrar registration expiration date|",
+        r"expiration date|expiry date|expire date|expires|paid-till|renewal date)",
+        r"\s*:\s*(\S.*?)\s*$"
+    ))
+    .unwrap();
+
+    /// WHOIS referral to another WHOIS server (IANA)
+    static ref WHOIS_REFERRAL: Regex =
+        Regex::new(r"(?im)^\s*(refer|whois)\s*:\s*(\S+)\s*$").unwrap();
+}
+
+
+/// Registration expiry date of given domain, read from RDAP (with fallback to WHOIS).
+/// Results, and lookup failures, are cached for CHECK_REGISTRATION_CACHE_TTL
+#[instrument]
+pub fn registration_expiry(
+    domain: &str,
+    rdap_server: Option<&str>,
+    whois_server: Option<&str>,
+) -> Result<DateTime<Utc>, Error> {
+    let rdap_server = rdap_server.unwrap_or(CHECK_DEFAULT_RDAP_SERVER);
+    let whois_server = whois_server.unwrap_or(CHECK_DEFAULT_WHOIS_SERVER);
+    let cache_key = (
+        domain.to_lowercase(),
+        rdap_server.to_string(),
+        whois_server.to_string(),
+    );
+    let lookup_failure = |(kind, details): (ErrorKind, String)| Error::new(kind, details);
+    if let Some((registration, fetched)) = REGISTRATIONS
+        .lock()
+        .map_err(|err| Error::other(err.to_string()))?
+        .get(&cache_key)
+    {
+        if fetched.elapsed() < Duration::from_secs(CHECK_REGISTRATION_CACHE_TTL) {
+            trace!("Using cached registration expiry of domain: {domain}");
+            return registration.clone().map_err(lookup_failure);
+        }
+    }
+
+    let registration = rdap_expiry(domain, rdap_server)
+        .or_else(|err| {
+            debug!("RDAP query of domain: {domain} failed: {err}. Falling back to WHOIS");
+            whois_expiry(domain, whois_server)
+        })
+        .map_err(|err| (err.kind(), err.to_string()));
+    REGISTRATIONS
+        .lock()
+        .map_err(|err| Error::other(err.to_string()))?
+        .insert(cache_key, (registration.clone(), Instant::now()));
+    registration.map_err(lookup_failure)
+}
+
+
+/// Registrable domain candidates, from the most specific one: "www.example.com", "example.com"
+fn domain_candidates(domain: &str) -> Vec<String> {
+    let labels: Vec<&str> = domain
+        .trim_end_matches('.')
+        .split('.')
+        .filter(|label| !label.is_empty())
+        .collect();
+    (0..labels.len().saturating_sub(1))
+        .map(|skip| labels[skip..].join("."))
+        .collect()
+}
+
+
+/// Query RDAP server for registration expiry of given domain (or its parent domains)
+#[instrument]
+pub fn rdap_expiry(domain: &str, rdap_server: &str) -> Result<DateTime<Utc>, Error> {
+    let mut last_error = Error::new(
+        ErrorKind::InvalidInput,
+        format!("Invalid domain name: {domain}"),
+    );
+    for candidate in domain_candidates(domain) {
+        let url = format!("{}/domain/{candidate}", rdap_server.trim_end_matches('/'));
+        let curl_error = |err: curl::Error| Error::other(format!("{url}: {err}"));
+        let mut headers = List::new();
+        headers
+            .append("Accept: application/rdap+json")
+            .map_err(curl_error)?;
+        let mut curl = Easy2::new(Collector::default());
+        curl.url(&url).map_err(curl_error)?;
+        curl.timeout(Duration::from_secs(CHECK_TIMEOUT))
+            .map_err(curl_error)?;
+        curl.follow_location(true).map_err(curl_error)?;
+        curl.http_headers(headers).map_err(curl_error)?;
+        curl.perform().map_err(curl_error)?;
+
+        let response_code = curl.response_code().map_err(curl_error)?;
+        if response_code != CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE {
+            last_error = Error::new(
+                ErrorKind::NotFound,
+                format!("RDAP server: {url} returned error: {response_code}"),
+            );
+            continue;
+        }
+        let response: serde_json::Value = serde_json::from_slice(&curl.get_ref().0)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        return response["events"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|event| event["eventAction"] == "expiration")
+            .and_then(|event| event["eventDate"].as_str())
+            .and_then(parse_expiry_date)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("RDAP response from: {url} has no expiration event"),
+                )
+            });
+    }
+    Err(last_error)
+}
+
+
+/// Query WHOIS server (port 43 by default) for registration expiry of given domain
+/// (or its parent domains). Single referral to another WHOIS server is followed
+#[instrument]
+pub fn whois_expiry(domain: &str, whois_server: &str) -> Result<DateTime<Utc>, Error> {
+    let mut last_error = Error::new(
+        ErrorKind::InvalidInput,
+        format!("Invalid domain name: {domain}"),
+    );
+    for candidate in domain_candidates(domain) {
+        let mut response = whois_query(&candidate, whois_server)?;
+        if WHOIS_EXPIRY.captures(&response).is_none() {
+            if let Some(referral) = WHOIS_REFERRAL.captures(&response) {
+                debug!("WHOIS server: {whois_server} refers to: {}", &referral[2]);
+                response = whois_query(&candidate, &referral[2])?;
+            }
+        }
+        match WHOIS_EXPIRY.captures(&response) {
+            Some(expiry) => {
+                return parse_expiry_date(&expiry[2]).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Unknown WHOIS expiry date format: {}", &expiry[2]),
+                    )
+                })
+            }
+            None => {
+                last_error = Error::new(
+                    ErrorKind::NotFound,
+                    format!("WHOIS response for: {candidate} has no expiry date"),
+                )
+            }
+        }
+    }
+    Err(last_error)
+}
+
+
+/// Send WHOIS query to given server ("host" or "host:port"), returns the response
+#[instrument]
+fn whois_query(domain: &str, whois_server: &str) -> Result<String, Error> {
+    let server = match whois_server.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => whois_server.to_string(),
+        _ => format!("{whois_server}:{CHECK_DEFAULT_WHOIS_PORT}"),
+    };
+    let address = server.to_socket_addrs()?.next().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("Couldn't resolve WHOIS server: {whois_server}"),
+        )
+    })?;
+    let timeout = Duration::from_secs(CHECK_TIMEOUT);
+    let mut stream = TcpStream::connect_timeout(&address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.write_all(format!("{domain}\r\n").as_bytes())?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(String::from_utf8_lossy(&response).to_string())
+}
+
+
+/// Parse expiry date in one of formats used by RDAP and WHOIS servers
+pub fn parse_expiry_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let value = value.trim_end_matches(" UTC").trim_end_matches('Z');
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y.%m.%d %H:%M:%S",
+        "%Y/%m/%d %H:%M:%S",
+    ] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Utc.from_utc_datetime(&date));
+        }
+    }
+    for format in ["%Y-%m-%d", "%Y.%m.%d", "%Y/%m/%d", "%d-%b-%Y", "%d.%m.%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return date
+                .and_hms_opt(0, 0, 0)
+                .map(|date| Utc.from_utc_datetime(&date));
+        }
+    }
+    None
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,172 @@
+use std::{net::TcpListener, sync::Arc, thread};
+
+use super::*;
+use crate::{
+    actors::{generic_checker::GenericChecker, multi_checker::MultiChecker},
+    checks::domain::Domain,
+    test_fixtures::*,
+};
+
+
+/// Spawns local RDAP server knowing given domain, expiring in given amount of days.
+/// Request lines are collected in returned queries
+fn serve_rdap(domain: &'static str, days: i64) -> (String, Arc<Mutex<Vec<String>>>) {
+    let queries = Arc::new(Mutex::new(Vec::new()));
+    let collected = queries.clone();
+    let expiry = (Utc::now() + chrono::Duration::days(days)).to_rfc3339();
+    let server = serve_http(move |request| {
+        let request_line = request.lines().next().unwrap_or_default().to_string();
+        collected.lock().unwrap().push(request_line.clone());
+        if request_line.starts_with(&format!("GET /domain/{domain} ")) {
+            let response = serde_json::json!({
+                "ldhName": domain,
+                "events": [
+                    {"eventAction": "registration", "eventDate": "2001-01-01T00:00:00Z"},
+                    {"eventAction": "expiration", "eventDate": expiry},
+                ]
+            });
+            http_response(200, &[], response.to_string().as_bytes())
+        } else {
+            http_response(404, &[], b"")
+        }
+    });
+    (server, queries)
+}
+
+
+/// Spawns local WHOIS server answering every query with given response
+fn serve_whois(response: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            read_client_line(&mut stream);
+            stream.write_all(response.as_bytes()).unwrap_or_default();
+        }
+    });
+    address.to_string()
+}
+
+
+/// Checks registration of given domain, with given registration servers
+fn registration_stories(
+    name: &str,
+    expected_days: &[i32],
+    rdap_server: &str,
+    whois_server: &str,
+) -> Stories {
+    MultiChecker::check_domains(&[Check {
+        domains: Some(vec![Domain {
+            name: name.to_string(),
+            expects: expected_days
+                .iter()
+                .map(|days| DomainExpectation::ValidRegistrationPeriod(*days))
+                .collect(),
+            fan_out_dns: None,
+            ca_bundle: None,
+            port: None,
+            starttls: None,
+            ocsp_responder: None,
+            rdap_server: Some(rdap_server.to_string()),
+            whois_server: Some(whois_server.to_string()),
+        }]),
+        ..Check::default()
+    }])
+}
+
+
+#[test]
+fn test_rdap_registration_of_parent_domain() {
+    let (rdap_server, _) = serve_rdap("krecik-rdap.test", 400);
+    let history = registration_stories(
+        "www.krecik-rdap.test",
+        &[30, 500],
+        &rdap_server,
+        "127.0.0.1:1",
+    );
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().any(|story| {
+        matches!(
+            story.success,
+            Some(Expected::DomainRegistrationFresh(_, days, 30))
+                if (398..=400).contains(&days)
+        )
+    }));
+    assert!(history.iter().any(|story| {
+        matches!(story.error, Some(Unexpected::DomainRegistrationExpiring(_, _)))
+    }));
+}
+
+
+#[test]
+fn test_rdap_registration_is_cached() {
+    let (rdap_server, queries) = serve_rdap("krecik-cache.test", 400);
+    for _ in 0..2 {
+        let history =
+            registration_stories("krecik-cache.test", &[30], &rdap_server, "127.0.0.1:1");
+        assert!(history[0].success.is_some(), "{history:?}");
+    }
+    assert_eq!(*queries.lock().unwrap(), vec!["GET /domain/krecik-cache.test HTTP/1.1"]);
+}
+
+
+#[test]
+fn test_registration_lookup_failure_is_cached_error() {
+    let (rdap_server, queries) = serve_rdap("krecik-other.test", 400);
+    for _ in 0..2 {
+        let history =
+            registration_stories("krecik-dead.test", &[30], &rdap_server, "127.0.0.1:1");
+        assert_eq!(history.len(), 1);
+        assert!(
+            matches!(
+                &history[0].error,
+                Some(Unexpected::DomainRegistrationLookupFailed(name, _))
+                    if name == "krecik-dead.test"
+            ),
+            "{history:?}"
+        );
+    }
+    assert_eq!(*queries.lock().unwrap(), vec!["GET /domain/krecik-dead.test HTTP/1.1"]);
+}
+
+
+#[test]
+fn test_whois_fallback_with_referral() {
+    let (rdap_server, _) = serve_rdap("krecik-other.test", 400);
+    let expiry = (Utc::now() + chrono::Duration::days(10)).format("%Y-%m-%dT%H:%M:%SZ");
+    let registry_whois = serve_whois(format!(
+        "Domain Name: KRECIK-WHOIS.TEST\r\nRegistry Expiry Date: {expiry}\r\n"
+    ));
+    let iana_whois =
+        serve_whois(format!("% IANA WHOIS server\r\nrefer: {registry_whois}\r\n"));
+
+    let history =
+        registration_stories("krecik-whois.test", &[30], &rdap_server, &iana_whois);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        history[0].error,
+        Some(Unexpected::DomainRegistrationExpiring(_, days)) if (9..=10).contains(&days)
+    ));
+}
+
+
+#[test]
+fn test_parse_expiry_date_formats() {
+    for date in [
+        "2030-01-02T03:04:05Z",
+        "2030-01-02T03:04:05.000Z",
+        "2030-01-02 03:04:05",
+        "2030.01.02 03:04:05",
+        "2030-01-02",
+        "02-jan-2030",
+    ] {
+        let parsed = parse_expiry_date(date).unwrap();
+        assert_eq!(parsed.format("%Y-%m-%d").to_string(), "2030-01-02", "{date}");
+    }
+}
+
+
+#[test]
+fn test_parse_expiry_date_unknown_format() {
+    assert!(parse_expiry_date("soon").is_none());
+}
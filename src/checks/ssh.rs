@@ -0,0 +1,600 @@
+use crate::{checks::tls::read_line, *};
+use openssl::{
+    base64,
+    bn::{BigNum, BigNumContext},
+    derive::Deriver,
+    ec::{EcGroup, EcKey, EcPoint},
+    ecdsa::EcdsaSig,
+    hash::{hash, MessageDigest},
+    nid::Nid,
+    pkey::{Id, PKey},
+    rand::rand_bytes,
+    rsa::Rsa,
+    sha::sha256,
+    sign::Verifier,
+};
+use rayon::prelude::*;
+use regex::Regex;
+use std::{
+    io::{Error, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+
+/// SSH message numbers used during key exchange (RFC 4253, RFC 5656)
+const SSH_MSG_DISCONNECT: u8 = 1;
+const SSH_MSG_IGNORE: u8 = 2;
+const SSH_MSG_DEBUG: u8 = 4;
+const SSH_MSG_KEXINIT: u8 = 20;
+const SSH_MSG_KEX_ECDH_INIT: u8 = 30;
+const SSH_MSG_KEX_ECDH_REPLY: u8 = 31;
+
+/// Key exchange methods supported by the check (RFC 8731)
+const SSH_KEX_ALGORITHMS: &[&str] = &["curve25519-sha256", "curve25519-sha256@libssh.org"];
+
+/// Host key algorithms offered by default, from the preferred one
+const SSH_HOST_KEY_ALGORITHMS: &[&str] = &[
+    "ssh-ed25519",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "rsa-sha2-512",
+    "rsa-sha2-256",
+    "ssh-rsa",
+];
+
+/// Ciphers and MACs offered only to complete KEXINIT (never used by the check)
+const SSH_CIPHERS: &str = "chacha20-poly1305@openssh.com,aes128-ctr,aes256-ctr,\
+                           aes128-gcm@openssh.com,aes256-gcm@openssh.com";
+const SSH_MACS: &str = "hmac-sha2-256-etm@openssh.com,hmac-sha2-256,hmac-sha2-512,hmac-sha1";
+
+/// Maximum size of SSH packet accepted during key exchange
+const SSH_MAX_PACKET_SIZE: usize = 262144;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// SSH server check structure
+pub struct SshCheck {
+    /// SSH server address: "host" or "host:port" (default port: 22)
+    pub address: String,
+
+    /// SSH server expectations (host key is fetched and verified by default)
+    #[serde(default)]
+    pub expects: SshExpectations,
+
+    /// Host key algorithms to negotiate, ex. ["ssh-ed25519"] (all supported by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_key_algorithms: Option<Vec<String>>,
+}
+
+
+/// SSH checks type
+pub type SshChecks = Vec<SshCheck>;
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// SSH server identity, read during key exchange
+pub struct SshHostKey {
+    /// Protocol banner, ex. "SSH-2.0-OpenSSH_9.6"
+    pub banner: String,
+
+    /// Negotiated host key algorithm, ex. "ssh-ed25519"
+    pub algorithm: String,
+
+    /// Host key fingerprint in OpenSSH format: "SHA256:…"
+    pub fingerprint: String,
+}
+
+
+/// Executes SSH server checks, returns Stories
+#[instrument(skip(checks))]
+pub fn check_ssh(checks: &[Check]) -> Stories {
+    checks
+        .into_par_iter()
+        .flat_map(|check| {
+            let notifier = check.notifier.clone();
+            check
+                .ssh
+                .par_iter()
+                .flat_map(|ssh_checks| {
+                    let mut all_ssh_checks = ssh_checks.clone();
+                    all_ssh_checks.sort();
+                    all_ssh_checks.dedup();
+                    all_ssh_checks
+                        .par_iter()
+                        .flat_map(|ssh_check| check_ssh_server(ssh_check, notifier.clone()))
+                        .collect::<Stories>()
+                })
+                .collect::<Stories>()
+        })
+        .collect()
+}
+
+
+/// Fetch SSH server banner and host key, and check all expectations on them
+#[instrument]
+fn check_ssh_server(ssh_check: &SshCheck, notifier: Option<String>) -> Stories {
+    let address = &ssh_check.address;
+    let host_key = match ssh_host_key(address, ssh_check.host_key_algorithms.as_deref()) {
+        Ok(host_key) => host_key,
+        Err(err) => {
+            return vec![Story::error(
+                Unexpected::SshHandshakeFailed(address.to_string(), err.to_string()),
+                notifier,
+            )]
+        }
+    };
+    debug!("SSH server: {address} host key: {host_key:?}");
+    if ssh_check.expects.is_empty() {
+        return vec![Story::success(
+            Expected::SshHostKey(
+                address.to_string(),
+                host_key.algorithm,
+                host_key.fingerprint,
+            ),
+            notifier,
+        )];
+    }
+    ssh_check
+        .expects
+        .iter()
+        .map(|expectation| {
+            match expectation {
+                SshExpectation::ValidBanner(pattern) => {
+                    match Regex::new(pattern) {
+                        Ok(regex) if regex.is_match(&host_key.banner) => {
+                            Story::success(
+                                Expected::SshBanner(
+                                    address.to_string(),
+                                    pattern.to_string(),
+                                    host_key.banner.to_string(),
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+
+                        Ok(_) => {
+                            Story::error(
+                                Unexpected::SshBannerMismatch(
+                                    address.to_string(),
+                                    pattern.to_string(),
+                                    host_key.banner.to_string(),
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+
+                        Err(err) => {
+                            Story::error(
+                                Unexpected::RegexMalformed(
+                                    address.to_string(),
+                                    pattern.to_string(),
+                                    err.to_string(),
+                                ),
+                                notifier.clone(),
+                            )
+                        }
+                    }
+                }
+
+                SshExpectation::ValidHostKey(fingerprint)
+                    if *fingerprint == host_key.fingerprint =>
+                {
+                    Story::success(
+                        Expected::SshHostKey(
+                            address.to_string(),
+                            host_key.algorithm.to_string(),
+                            host_key.fingerprint.to_string(),
+                        ),
+                        notifier.clone(),
+                    )
+                }
+
+                SshExpectation::ValidHostKey(fingerprint) => {
+                    Story::error(
+                        Unexpected::SshHostKeyMismatch(
+                            address.to_string(),
+                            host_key.algorithm.to_string(),
+                            host_key.fingerprint.to_string(),
+                            fingerprint.to_string(),
+                        ),
+                        notifier.clone(),
+                    )
+                }
+            }
+        })
+        .collect()
+}
+
+
+/// Host key fingerprint in OpenSSH format (SHA256, base64 without padding)
+pub fn ssh_fingerprint(host_key: &[u8]) -> String {
+    format!(
+        "SHA256:{}",
+        base64::encode_block(&sha256(host_key)).trim_end_matches('=')
+    )
+}
+
+
+/// Connect to SSH server, read its banner, and perform key exchange far enough to obtain
+/// the host key. Server signature of the exchange hash is verified with the host key
+#[instrument]
+pub fn ssh_host_key(
+    address: &str,
+    host_key_algorithms: Option<&[String]>,
+) -> Result<SshHostKey, Error> {
+    let address = match address.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => address.to_string(),
+        _ => format!("{address}:{CHECK_DEFAULT_SSH_PORT}"),
+    };
+    let socket_address = address.to_socket_addrs()?.next().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("Couldn't resolve any address from: {address}"),
+        )
+    })?;
+    let mut stream = TcpStream::connect_timeout(
+        &socket_address,
+        Duration::from_secs(CHECK_CONNECTION_TIMEOUT),
+    )?;
+    let timeout = Duration::from_secs(CHECK_TIMEOUT);
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let client_banner = format!("SSH-2.0-Krecik_{}", env!("CARGO_PKG_VERSION"));
+    stream.write_all(format!("{client_banner}\r\n").as_bytes())?;
+    let server_banner = read_ssh_banner(&mut stream)?;
+
+    let host_key_algorithms: Vec<&str> = match host_key_algorithms {
+        Some(algorithms) => algorithms.iter().map(String::as_str).collect(),
+        None => SSH_HOST_KEY_ALGORITHMS.to_vec(),
+    };
+    let client_kex_init = ssh_kex_init(SSH_KEX_ALGORITHMS, &host_key_algorithms)?;
+    write_ssh_packet(&mut stream, &client_kex_init)?;
+    let server_kex_init = read_ssh_message(&mut stream, SSH_MSG_KEXINIT)?;
+    let mut reader = SshReader(&server_kex_init);
+    reader.take(17)?; // message number, cookie
+    let server_kex_algorithms = reader.name_list()?;
+    let server_host_key_algorithms = reader.name_list()?;
+    negotiate("key exchange", SSH_KEX_ALGORITHMS, &server_kex_algorithms)?;
+    let algorithm = negotiate(
+        "host key",
+        &host_key_algorithms,
+        &server_host_key_algorithms,
+    )?;
+
+    let client_key = PKey::generate_x25519().map_err(Error::other)?;
+    let client_public_key = client_key.raw_public_key().map_err(Error::other)?;
+    let mut kex_ecdh_init = vec![SSH_MSG_KEX_ECDH_INIT];
+    put_ssh_string(&mut kex_ecdh_init, &client_public_key);
+    write_ssh_packet(&mut stream, &kex_ecdh_init)?;
+    let kex_ecdh_reply = read_ssh_message(&mut stream, SSH_MSG_KEX_ECDH_REPLY)?;
+    let mut reader = SshReader(&kex_ecdh_reply[1..]);
+    let host_key = reader.string()?;
+    let server_public_key = reader.string()?;
+    let signature = reader.string()?;
+
+    let server_key = PKey::public_key_from_raw_bytes(server_public_key, Id::X25519)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let mut deriver = Deriver::new(&client_key).map_err(Error::other)?;
+    deriver.set_peer(&server_key).map_err(Error::other)?;
+    let shared_secret = deriver.derive_to_vec().map_err(Error::other)?;
+    let exchange_hash = ssh_exchange_hash(
+        &[
+            client_banner.as_bytes(),
+            server_banner.as_bytes(),
+            &client_kex_init,
+            &server_kex_init,
+            host_key,
+            &client_public_key,
+            server_public_key,
+        ],
+        &shared_secret,
+    );
+    verify_ssh_signature(algorithm, host_key, signature, &exchange_hash)?;
+
+    let mut disconnect = vec![SSH_MSG_DISCONNECT];
+    disconnect.extend_from_slice(&11u32.to_be_bytes()); // SSH_DISCONNECT_BY_APPLICATION
+    put_ssh_string(&mut disconnect, b"");
+    put_ssh_string(&mut disconnect, b"");
+    write_ssh_packet(&mut stream, &disconnect).unwrap_or_default();
+    Ok(SshHostKey {
+        banner: server_banner,
+        algorithm: algorithm.to_string(),
+        fingerprint: ssh_fingerprint(host_key),
+    })
+}
+
+
+/// First client algorithm supported by the server
+fn negotiate<'a>(kind: &str, client: &[&'a str], server: &[String]) -> Result<&'a str, Error> {
+    client
+        .iter()
+        .find(|algorithm| server.iter().any(|supported| supported == *algorithm))
+        .copied()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unsupported,
+                format!("No common {kind} algorithm. Server supports: {}", server.join(",")),
+            )
+        })
+}
+
+
+/// Read SSH protocol banner. Lines sent by the server before the banner are skipped
+fn read_ssh_banner(stream: &mut TcpStream) -> Result<String, Error> {
+    for _ in 0..CHECK_SSH_MAX_PREAMBLE_LINES {
+        let line = read_line(stream)?;
+        if line.starts_with("SSH-2.0-") || line.starts_with("SSH-1.99-") {
+            return Ok(line);
+        }
+        if line.starts_with("SSH-") {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Unsupported SSH protocol version: {line}"),
+            ));
+        }
+    }
+    Err(Error::new(ErrorKind::InvalidData, "No SSH protocol banner"))
+}
+
+
+/// KEXINIT message payload with given algorithms
+pub(crate) fn ssh_kex_init(
+    kex_algorithms: &[&str],
+    host_key_algorithms: &[&str],
+) -> Result<Vec<u8>, Error> {
+    let mut payload = vec![SSH_MSG_KEXINIT];
+    let mut cookie = [0u8; 16];
+    rand_bytes(&mut cookie).map_err(Error::other)?;
+    payload.extend_from_slice(&cookie);
+    for name_list in [
+        kex_algorithms.join(","),
+        host_key_algorithms.join(","),
+        SSH_CIPHERS.to_string(),
+        SSH_CIPHERS.to_string(),
+        SSH_MACS.to_string(),
+        SSH_MACS.to_string(),
+        "none".to_string(),
+        "none".to_string(),
+        String::new(),
+        String::new(),
+    ] {
+        put_ssh_string(&mut payload, name_list.as_bytes());
+    }
+    payload.push(0); // first_kex_packet_follows
+    payload.extend_from_slice(&0u32.to_be_bytes());
+    Ok(payload)
+}
+
+
+/// Exchange hash of curve25519-sha256 key exchange: strings given in protocol order
+/// (V_C, V_S, I_C, I_S, K_S, Q_C, Q_S), followed by shared secret K
+pub(crate) fn ssh_exchange_hash(strings: &[&[u8]], shared_secret: &[u8]) -> [u8; 32] {
+    let mut data = Vec::new();
+    for string in strings {
+        put_ssh_string(&mut data, string);
+    }
+    put_ssh_mpint(&mut data, shared_secret);
+    sha256(&data)
+}
+
+
+/// Verify server signature of exchange hash, made with given host key and algorithm
+fn verify_ssh_signature(
+    algorithm: &str,
+    host_key: &[u8],
+    signature: &[u8],
+    exchange_hash: &[u8],
+) -> Result<(), Error> {
+    let invalid = |details: String| Error::new(ErrorKind::InvalidData, details);
+    let openssl_error = |err: openssl::error::ErrorStack| invalid(err.to_string());
+    let mut key = SshReader(host_key);
+    let key_type = String::from_utf8_lossy(key.string()?).to_string();
+    let mut signature = SshReader(signature);
+    let signature_type = String::from_utf8_lossy(signature.string()?).to_string();
+    let signature = signature.string()?;
+    if signature_type != algorithm {
+        return Err(invalid(format!(
+            "Signature algorithm: {signature_type} differs from negotiated one: {algorithm}"
+        )));
+    }
+
+    let verified = match (key_type.as_str(), algorithm) {
+        ("ssh-ed25519", "ssh-ed25519") => {
+            let public_key = PKey::public_key_from_raw_bytes(key.string()?, Id::ED25519)
+                .map_err(openssl_error)?;
+            Verifier::new_without_digest(&public_key)
+                .and_then(|mut verifier| verifier.verify_oneshot(signature, exchange_hash))
+                .map_err(openssl_error)?
+        }
+
+        ("ssh-rsa", "rsa-sha2-512" | "rsa-sha2-256" | "ssh-rsa") => {
+            let exponent = BigNum::from_slice(key.string()?).map_err(openssl_error)?;
+            let modulus = BigNum::from_slice(key.string()?).map_err(openssl_error)?;
+            let public_key = Rsa::from_public_components(modulus, exponent)
+                .and_then(PKey::from_rsa)
+                .map_err(openssl_error)?;
+            let digest = match algorithm {
+                "rsa-sha2-512" => MessageDigest::sha512(),
+                "rsa-sha2-256" => MessageDigest::sha256(),
+                _ => MessageDigest::sha1(),
+            };
+            Verifier::new(digest, &public_key)
+                .and_then(|mut verifier| {
+                    verifier.update(exchange_hash)?;
+                    verifier.verify(signature)
+                })
+                .map_err(openssl_error)?
+        }
+
+        (key_type, _) if key_type == algorithm && key_type.starts_with("ecdsa-sha2-") => {
+            let (curve, digest) = match key_type {
+                "ecdsa-sha2-nistp256" => (Nid::X9_62_PRIME256V1, MessageDigest::sha256()),
+                "ecdsa-sha2-nistp384" => (Nid::SECP384R1, MessageDigest::sha384()),
+                "ecdsa-sha2-nistp521" => (Nid::SECP521R1, MessageDigest::sha512()),
+                _ => return Err(invalid(format!("Unsupported host key type: {key_type}"))),
+            };
+            let group = EcGroup::from_curve_name(curve).map_err(openssl_error)?;
+            key.string()?; // curve identifier
+            let mut context = BigNumContext::new().map_err(openssl_error)?;
+            let point = EcPoint::from_bytes(&group, key.string()?, &mut context)
+                .map_err(openssl_error)?;
+            let public_key = EcKey::from_public_key(&group, &point).map_err(openssl_error)?;
+            let mut components = SshReader(signature);
+            let r = BigNum::from_slice(components.string()?).map_err(openssl_error)?;
+            let s = BigNum::from_slice(components.string()?).map_err(openssl_error)?;
+            let digest = hash(digest, exchange_hash).map_err(openssl_error)?;
+            EcdsaSig::from_private_components(r, s)
+                .and_then(|signature| signature.verify(&digest, &public_key))
+                .map_err(openssl_error)?
+        }
+
+        (key_type, _) => {
+            return Err(invalid(format!(
+                "Host key type: {key_type} doesn't match negotiated algorithm: {algorithm}"
+            )))
+        }
+    };
+    if verified {
+        Ok(())
+    } else {
+        Err(invalid(format!("Invalid {algorithm} host key signature")))
+    }
+}
+
+
+/// Read SSH message of expected type. Ignore and debug messages are skipped
+fn read_ssh_message(stream: &mut TcpStream, expected: u8) -> Result<Vec<u8>, Error> {
+    loop {
+        let payload = read_ssh_packet(stream)?;
+        match payload.first().copied() {
+            Some(message) if message == expected => return Ok(payload),
+
+            Some(SSH_MSG_IGNORE | SSH_MSG_DEBUG) => continue,
+
+            Some(SSH_MSG_DISCONNECT) => {
+                let mut reader = SshReader(&payload[1..]);
+                reader.u32()?;
+                let description = String::from_utf8_lossy(reader.string()?).to_string();
+                return Err(Error::new(
+                    ErrorKind::ConnectionAborted,
+                    format!("SSH server disconnected: {description}"),
+                ));
+            }
+
+            message => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Expected SSH message: {expected}, got: {message:?}"),
+                ))
+            }
+        }
+    }
+}
+
+
+/// Read unencrypted SSH binary packet, returns its payload
+pub(crate) fn read_ssh_packet(stream: &mut TcpStream) -> Result<Vec<u8>, Error> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if !(5..=SSH_MAX_PACKET_SIZE).contains(&length) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid SSH packet length: {length}"),
+        ));
+    }
+    let mut packet = vec![0u8; length];
+    stream.read_exact(&mut packet)?;
+    let padding = packet[0] as usize;
+    if padding + 1 >= length {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid SSH packet padding: {padding}"),
+        ));
+    }
+    Ok(packet[1..length - padding].to_vec())
+}
+
+
+/// Write unencrypted SSH binary packet with given payload
+pub(crate) fn write_ssh_packet(stream: &mut TcpStream, payload: &[u8]) -> Result<(), Error> {
+    let mut padding = 8 - (5 + payload.len()) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+    let mut packet = Vec::with_capacity(5 + payload.len() + padding);
+    packet.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+    packet.push(padding as u8);
+    packet.extend_from_slice(payload);
+    packet.resize(packet.len() + padding, 0);
+    stream.write_all(&packet)
+}
+
+
+/// Append SSH string (length prefixed bytes)
+pub(crate) fn put_ssh_string(buffer: &mut Vec<u8>, string: &[u8]) {
+    buffer.extend_from_slice(&(string.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(string);
+}
+
+
+/// Append SSH mpint from unsigned big-endian bytes
+pub(crate) fn put_ssh_mpint(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    let bytes = &bytes[bytes.iter().take_while(|byte| **byte == 0).count()..];
+    if bytes.first().is_some_and(|byte| byte & 0x80 != 0) {
+        buffer.extend_from_slice(&(bytes.len() as u32 + 1).to_be_bytes());
+        buffer.push(0);
+        buffer.extend_from_slice(bytes);
+    } else {
+        put_ssh_string(buffer, bytes);
+    }
+}
+
+
+/// Reader of SSH data types
+pub(crate) struct SshReader<'a>(pub &'a [u8]);
+
+
+impl<'a> SshReader<'a> {
+    /// Read uint32
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+
+    /// Read string (length prefixed bytes)
+    pub fn string(&mut self) -> Result<&'a [u8], Error> {
+        let length = self.u32()? as usize;
+        self.take(length)
+    }
+
+
+    /// Read name-list (comma separated names)
+    pub fn name_list(&mut self) -> Result<Vec<String>, Error> {
+        Ok(String::from_utf8_lossy(self.string()?)
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+
+    /// Take given number of bytes
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < length {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Truncated SSH message"));
+        }
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(bytes)
+    }
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
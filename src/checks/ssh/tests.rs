@@ -0,0 +1,306 @@
+use openssl::{
+    base64,
+    bn::BigNumContext,
+    derive::Deriver,
+    ec::{EcGroup, EcKey, PointConversionForm},
+    ecdsa::EcdsaSig,
+    hash::{hash, MessageDigest},
+    nid::Nid,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    sign::Signer,
+};
+use std::{
+    io::{ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use super::*;
+use crate::test_fixtures::*;
+
+
+const BANNER: &str = r"^SSH-2\.0-KrecikFake_";
+
+
+/// Spawns minimal fake SSH server presenting given host key (SSH public key blob).
+/// Exchange hash is signed with sign(), which returns raw signature for given algorithm
+fn serve_ssh<F>(algorithm: &'static str, host_key: Vec<u8>, sign: F) -> String
+where
+    F: Fn(&[u8]) -> Vec<u8> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let server_banner = "SSH-2.0-KrecikFake_1.0";
+            stream
+                .write_all(format!("Welcome!\r\n{server_banner}\r\n").as_bytes())
+                .unwrap_or_default();
+            let client_banner = read_client_line(&mut stream);
+            let Ok(client_kex_init) = read_ssh_packet(&mut stream) else {
+                continue;
+            };
+            let server_kex_init =
+                ssh_kex_init(&["curve25519-sha256"], &[algorithm]).unwrap();
+            write_ssh_packet(&mut stream, &server_kex_init).unwrap_or_default();
+            let Ok(kex_ecdh_init) = read_ssh_packet(&mut stream) else {
+                continue;
+            };
+            let client_public_key =
+                SshReader(&kex_ecdh_init[1..]).string().unwrap().to_vec();
+
+            let server_key = PKey::generate_x25519().unwrap();
+            let server_public_key = server_key.raw_public_key().unwrap();
+            let client_key = PKey::public_key_from_raw_bytes(
+                &client_public_key,
+                Id::X25519,
+            )
+            .unwrap();
+            let mut deriver = Deriver::new(&server_key).unwrap();
+            deriver.set_peer(&client_key).unwrap();
+            let exchange_hash = ssh_exchange_hash(
+                &[
+                    client_banner.as_bytes(),
+                    server_banner.as_bytes(),
+                    &client_kex_init,
+                    &server_kex_init,
+                    &host_key,
+                    &client_public_key,
+                    &server_public_key,
+                ],
+                &deriver.derive_to_vec().unwrap(),
+            );
+            let mut signature = Vec::new();
+            put_ssh_string(&mut signature, algorithm.as_bytes());
+            put_ssh_string(&mut signature, &sign(&exchange_hash));
+            let mut kex_ecdh_reply = vec![31];
+            put_ssh_string(&mut kex_ecdh_reply, &host_key);
+            put_ssh_string(&mut kex_ecdh_reply, &server_public_key);
+            put_ssh_string(&mut kex_ecdh_reply, &signature);
+            write_ssh_packet(&mut stream, &kex_ecdh_reply).unwrap_or_default();
+            read_ssh_packet(&mut stream).unwrap_or_default(); // disconnect
+        }
+    });
+    address
+}
+
+
+/// SSH public key blob with given fields
+fn ssh_blob(fields: &[&[u8]]) -> Vec<u8> {
+    let mut blob = Vec::new();
+    for field in fields {
+        put_ssh_string(&mut blob, field);
+    }
+    blob
+}
+
+
+/// Ed25519 host key blob of given key
+fn ed25519_blob(key: &PKey<Private>) -> Vec<u8> {
+    ssh_blob(&[b"ssh-ed25519", &key.raw_public_key().unwrap()])
+}
+
+
+/// Spawns fake SSH server presenting given ed25519 host key blob, signing with given key.
+/// Returns its address
+fn serve_ed25519(host_key: Vec<u8>, signing_key: PKey<Private>) -> String {
+    serve_ssh("ssh-ed25519", host_key, move |data| {
+        Signer::new_without_digest(&signing_key)
+            .unwrap()
+            .sign_oneshot_to_vec(data)
+            .unwrap()
+    })
+}
+
+
+/// Spawns fake SSH server with ECDSA (nistp256) host key. Returns its address and host key
+fn serve_ecdsa() -> (String, Vec<u8>) {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let ecdsa_key = EcKey::generate(&group).unwrap();
+    let point = ecdsa_key
+        .public_key()
+        .to_bytes(
+            &group,
+            PointConversionForm::UNCOMPRESSED,
+            &mut BigNumContext::new().unwrap(),
+        )
+        .unwrap();
+    let blob = ssh_blob(&[b"ecdsa-sha2-nistp256", b"nistp256", &point]);
+    let address = serve_ssh("ecdsa-sha2-nistp256", blob.clone(), move |data| {
+        let digest = hash(MessageDigest::sha256(), data).unwrap();
+        let signature = EcdsaSig::sign(&digest, &ecdsa_key).unwrap();
+        let mut components = Vec::new();
+        put_ssh_mpint(&mut components, &signature.r().to_vec());
+        put_ssh_mpint(&mut components, &signature.s().to_vec());
+        components
+    });
+    (address, blob)
+}
+
+
+/// Spawns fake SSH server with RSA host key, signing with SHA-256.
+/// Returns its address and host key
+fn serve_rsa() -> (String, Vec<u8>) {
+    let rsa_key = Rsa::generate(2048).unwrap();
+    let mut blob = ssh_blob(&[b"ssh-rsa"]);
+    put_ssh_mpint(&mut blob, &rsa_key.e().to_vec());
+    put_ssh_mpint(&mut blob, &rsa_key.n().to_vec());
+    let rsa_key = PKey::from_rsa(rsa_key).unwrap();
+    let address = serve_ssh("rsa-sha2-256", blob.clone(), move |data| {
+        let mut signer = Signer::new(MessageDigest::sha256(), &rsa_key).unwrap();
+        signer.update(data).unwrap();
+        signer.sign_to_vec().unwrap()
+    });
+    (address, blob)
+}
+
+
+/// Checks SSH server at given address
+fn ssh_stories(
+    address: &str,
+    expects: SshExpectations,
+    host_key_algorithms: Option<Vec<String>>,
+) -> Stories {
+    check_ssh(&[Check {
+        ssh: Some(vec![SshCheck {
+            address: address.to_string(),
+            expects,
+            host_key_algorithms,
+        }]),
+        ..Check::default()
+    }])
+}
+
+
+#[test]
+fn test_ssh_fingerprint_matches_ssh_keygen() {
+    let blob = ed25519_blob(&PKey::generate_ed25519().unwrap());
+    let public_key_file = fixture_file(
+        "ssh.pub",
+        format!("ssh-ed25519 {} krecik\n", base64::encode_block(&blob)).as_bytes(),
+    );
+    let keygen = std::process::Command::new("ssh-keygen")
+        .args(["-l", "-E", "sha256", "-f", &public_key_file])
+        .output()
+        .unwrap();
+    let fingerprint = String::from_utf8_lossy(&keygen.stdout)
+        .split_whitespace()
+        .nth(1)
+        .unwrap()
+        .to_string();
+    assert_eq!(fingerprint, ssh_fingerprint(&blob));
+}
+
+
+#[test]
+fn test_ed25519_host_key_and_banner() {
+    let key = PKey::generate_ed25519().unwrap();
+    let blob = ed25519_blob(&key);
+    let history = ssh_stories(
+        &serve_ed25519(blob.clone(), key),
+        vec![
+            SshExpectation::ValidBanner(BANNER.to_string()),
+            SshExpectation::ValidHostKey(ssh_fingerprint(&blob)),
+        ],
+        None,
+    );
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().all(|story| story.success.is_some()), "{history:?}");
+}
+
+
+#[test]
+fn test_ecdsa_host_key_without_expectations() {
+    let (address, blob) = serve_ecdsa();
+    let history = ssh_stories(&address, vec![], None);
+    assert_eq!(
+        history.into_iter().map(|story| story.success).collect::<Vec<_>>(),
+        vec![Some(Expected::SshHostKey(
+            address,
+            "ecdsa-sha2-nistp256".to_string(),
+            ssh_fingerprint(&blob),
+        ))]
+    );
+}
+
+
+#[test]
+fn test_rsa_host_key() {
+    let (address, blob) = serve_rsa();
+    let expects = vec![SshExpectation::ValidHostKey(ssh_fingerprint(&blob))];
+    let history = ssh_stories(&address, expects, None);
+    assert_eq!(history.len(), 1);
+    assert!(history[0].success.is_some(), "{history:?}");
+}
+
+
+#[test]
+fn test_ssh_banner_mismatch() {
+    let key = PKey::generate_ed25519().unwrap();
+    let address = serve_ed25519(ed25519_blob(&key), key);
+    let expects = vec![SshExpectation::ValidBanner(r"^SSH-2\.0-OpenSSH".to_string())];
+    let history = ssh_stories(&address, expects, None);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(history[0].error, Some(Unexpected::SshBannerMismatch(_, _, _))));
+}
+
+
+#[test]
+fn test_ssh_host_key_mismatch() {
+    let key = PKey::generate_ed25519().unwrap();
+    let address = serve_ed25519(ed25519_blob(&key), key);
+    let other_blob = ed25519_blob(&PKey::generate_ed25519().unwrap());
+    let expects = vec![SshExpectation::ValidHostKey(ssh_fingerprint(&other_blob))];
+    let history = ssh_stories(&address, expects, None);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].error,
+        Some(Unexpected::SshHostKeyMismatch(_, algorithm, _, _)) if algorithm == "ssh-ed25519"
+    ));
+}
+
+
+#[test]
+fn test_ssh_forged_host_key() {
+    // server presents ed25519 host key it doesn't own:
+    let blob = ed25519_blob(&PKey::generate_ed25519().unwrap());
+    let forged_address = serve_ed25519(blob.clone(), PKey::generate_ed25519().unwrap());
+    let expects = vec![SshExpectation::ValidHostKey(ssh_fingerprint(&blob))];
+    let history = ssh_stories(&forged_address, expects, None);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].error,
+        Some(Unexpected::SshHandshakeFailed(address, details))
+            if *address == forged_address && details.contains("signature")
+    ));
+}
+
+
+#[test]
+fn test_ssh_no_common_host_key_algorithm() {
+    let (rsa_address, _) = serve_rsa();
+    let history = ssh_stories(&rsa_address, vec![], Some(vec!["ssh-ed25519".to_string()]));
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].error,
+        Some(Unexpected::SshHandshakeFailed(address, details))
+            if *address == rsa_address && details.contains("No common host key")
+    ));
+}
+
+
+#[test]
+fn test_ssh_banner_line_is_limited() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            stream
+                .write_all(&vec![b'S'; CHECK_MAX_LINE_LENGTH * 2])
+                .unwrap_or_default();
+        }
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+    assert_eq!(read_ssh_banner(&mut stream).unwrap_err().kind(), ErrorKind::InvalidData);
+}
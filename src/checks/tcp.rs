@@ -0,0 +1,225 @@
+use crate::*;
+use rayon::prelude::*;
+use regex::Regex;
+use std::{
+    io::{Error, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// Raw TCP service check structure
+pub struct TcpCheck {
+    /// Service address: "host:port"
+    pub address: String,
+
+    /// TCP service expectations (connection itself is checked by default)
+    #[serde(default)]
+    pub expects: TcpExpectations,
+
+    /// Payload sent after connecting, ex. "PING\r\n"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
+}
+
+
+/// TCP checks type
+pub type TcpChecks = Vec<TcpCheck>;
+
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Result of exchange with TCP service
+pub struct TcpResponse {
+    /// TCP connect time in milliseconds
+    pub connect_time: u64,
+
+    /// Bytes received from the service (banner, response to payload)
+    pub response: Vec<u8>,
+}
+
+
+/// Executes raw TCP service checks, returns Stories
+#[instrument(skip(checks))]
+pub fn check_tcp(checks: &[Check]) -> Stories {
+    checks
+        .into_par_iter()
+        .flat_map(|check| {
+            let notifier = check.notifier.clone();
+            check
+                .tcp
+                .par_iter()
+                .flat_map(|tcp_checks| {
+                    let mut all_tcp_checks = tcp_checks.clone();
+                    all_tcp_checks.sort();
+                    all_tcp_checks.dedup();
+                    all_tcp_checks
+                        .par_iter()
+                        .flat_map(|tcp_check| check_tcp_service(tcp_check, notifier.clone()))
+                        .collect::<Stories>()
+                })
+                .collect::<Stories>()
+        })
+        .collect()
+}
+
+
+/// Connect to TCP service, and check all expectations on the connection and response
+#[instrument]
+fn check_tcp_service(tcp_check: &TcpCheck, notifier: Option<String>) -> Stories {
+    let address = &tcp_check.address;
+    let mut stories = Stories::new();
+    let mut patterns = Vec::new();
+    for expectation in &tcp_check.expects {
+        if let TcpExpectation::ValidBanner(pattern) = expectation {
+            match Regex::new(pattern) {
+                Ok(regex) => patterns.push(regex),
+                Err(err) => {
+                    stories.push(Story::error(
+                        Unexpected::RegexMalformed(
+                            address.to_string(),
+                            pattern.to_string(),
+                            err.to_string(),
+                        ),
+                        notifier.clone(),
+                    ))
+                }
+            }
+        }
+    }
+    let payload = tcp_check.payload.as_ref().map(|payload| payload.as_bytes());
+    let response = match tcp_exchange(address, payload, &patterns) {
+        Ok(response) => response,
+        Err(err) => {
+            stories.push(Story::error(
+                Unexpected::TcpConnectionFailed(address.to_string(), err.to_string()),
+                notifier,
+            ));
+            return stories;
+        }
+    };
+    let text = String::from_utf8_lossy(&response.response);
+    trace!("TCP service: {address} response: {text:?}");
+
+    if tcp_check.expects.is_empty() {
+        stories.push(Story::success(
+            Expected::TcpConnected(address.to_string(), response.connect_time),
+            notifier.clone(),
+        ));
+    }
+    for expectation in &tcp_check.expects {
+        match expectation {
+            TcpExpectation::ValidBanner(pattern) => {
+                let Some(regex) = patterns.iter().find(|regex| regex.as_str() == pattern)
+                else {
+                    continue; // malformed regex is reported already
+                };
+                stories.push(match regex.find(&text) {
+                    Some(found) => {
+                        Story::success(
+                            Expected::TcpBanner(
+                                address.to_string(),
+                                pattern.to_string(),
+                                found.as_str().to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+
+                    None => {
+                        Story::error(
+                            Unexpected::TcpBannerMismatch(
+                                address.to_string(),
+                                pattern.to_string(),
+                            ),
+                            notifier.clone(),
+                        )
+                    }
+                })
+            }
+
+            TcpExpectation::ValidConnectTime(limit) if response.connect_time > *limit => {
+                stories.push(Story::error(
+                    Unexpected::TcpConnectTimeExceeded(
+                        address.to_string(),
+                        response.connect_time,
+                        *limit,
+                    ),
+                    notifier.clone(),
+                ))
+            }
+
+            TcpExpectation::ValidConnectTime(limit) => {
+                stories.push(Story::success(
+                    Expected::TcpConnectTime(
+                        address.to_string(),
+                        response.connect_time,
+                        *limit,
+                    ),
+                    notifier.clone(),
+                ))
+            }
+        }
+    }
+    stories
+}
+
+
+/// Connect to TCP service, send the payload, and read the response until it matches all
+/// given patterns. Reading stops on EOF, timeout, or CHECK_TCP_MAX_RESPONSE_SIZE too.
+/// Nothing is read without patterns
+#[instrument(skip(patterns))]
+pub fn tcp_exchange(
+    address: &str,
+    payload: Option<&[u8]>,
+    patterns: &[Regex],
+) -> Result<TcpResponse, Error> {
+    let socket_address = address.to_socket_addrs()?.next().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("Couldn't resolve any address from: {address}"),
+        )
+    })?;
+    let start = Instant::now();
+    let mut stream = TcpStream::connect_timeout(
+        &socket_address,
+        Duration::from_secs(CHECK_CONNECTION_TIMEOUT),
+    )?;
+    let connect_time = start.elapsed().as_millis() as u64;
+    let timeout = Duration::from_secs(CHECK_TIMEOUT);
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    if let Some(payload) = payload {
+        stream.write_all(payload)?;
+    }
+
+    let mut response = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while !patterns.is_empty() && response.len() < CHECK_TCP_MAX_RESPONSE_SIZE {
+        let length = match stream.read(&mut buffer) {
+            Ok(length) => length,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                debug!("TCP service: {address} stopped responding: {err}");
+                break;
+            }
+            Err(err) => return Err(err),
+        };
+        if length == 0 {
+            break;
+        }
+        response.extend_from_slice(&buffer[..length]);
+        let text = String::from_utf8_lossy(&response);
+        if patterns.iter().all(|pattern| pattern.is_match(&text)) {
+            break;
+        }
+    }
+    Ok(TcpResponse {
+        connect_time,
+        response,
+    })
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
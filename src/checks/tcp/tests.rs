@@ -0,0 +1,172 @@
+use std::{net::TcpListener, thread};
+
+use super::*;
+use crate::test_fixtures::*;
+
+
+/// Replies of TCP service to client commands: (command, reply)
+type Replies = &'static [(&'static str, &'static [u8])];
+
+
+/// Spawns local TCP service sending given banner, and replies to client commands.
+/// Without replies connection is closed after the banner. Returns its address
+fn serve_tcp(banner: &'static [u8], replies: Replies) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            stream.write_all(banner).unwrap_or_default();
+            if replies.is_empty() {
+                continue;
+            }
+            let command = read_client_line(&mut stream);
+            if let Some((_, reply)) = replies.iter().find(|(cmd, _)| *cmd == command) {
+                stream.write_all(reply).unwrap_or_default();
+            }
+        }
+    });
+    address
+}
+
+
+/// Spawns local service greeting with SSH banner. Returns its address
+fn serve_ssh_banner() -> String {
+    serve_tcp(b"SSH-2.0-OpenSSH_9.6 Krecik\r\n", &[])
+}
+
+
+/// Checks TCP service at given address
+fn tcp_stories(address: &str, payload: Option<&str>, expects: TcpExpectations) -> Stories {
+    check_tcp(&[Check {
+        tcp: Some(vec![TcpCheck {
+            address: address.to_string(),
+            expects,
+            payload: payload.map(|payload| payload.to_string()),
+        }]),
+        ..Check::default()
+    }])
+}
+
+
+#[test]
+fn test_tcp_banner_reports_first_match() {
+    let history = tcp_stories(
+        &serve_ssh_banner(),
+        None,
+        vec![TcpExpectation::ValidBanner(r"^SSH-2\.0-OpenSSH_\d+".to_string())],
+    );
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].success,
+        Some(Expected::TcpBanner(_, _, found)) if found == "SSH-2.0-OpenSSH_9"
+    ));
+}
+
+
+#[test]
+fn test_tcp_payload_reply() {
+    let address = serve_tcp(b"", &[("PING", b"+PONG\r\n")]);
+    let history = tcp_stories(
+        &address,
+        Some("PING\r\n"),
+        vec![TcpExpectation::ValidBanner(r"^\+PONG\r\n$".to_string())],
+    );
+    assert_eq!(history.len(), 1);
+    assert!(history[0].success.is_some(), "{history:?}");
+}
+
+
+#[test]
+fn test_tcp_multiline_reply_matches_all_patterns() {
+    let address = serve_tcp(
+        b"",
+        &[(
+            "stats",
+            b"STAT pid 42\r\nSTAT uptime 7\r\nSTAT curr_connections 3\r\nEND\r\n",
+        )],
+    );
+    let history = tcp_stories(
+        &address,
+        Some("stats\r\n"),
+        vec![
+            TcpExpectation::ValidBanner(r"STAT uptime \d+".to_string()),
+            TcpExpectation::ValidBanner(r"(?m)^END\r$".to_string()),
+        ],
+    );
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().all(|story| story.success.is_some()), "{history:?}");
+}
+
+
+#[test]
+fn test_tcp_connected_without_expectations() {
+    let address = serve_tcp(b"", &[("PING", b"+PONG\r\n")]);
+    let history = tcp_stories(&address, None, vec![]);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].success,
+        Some(Expected::TcpConnected(connected, _)) if *connected == address
+    ));
+}
+
+
+#[test]
+fn test_tcp_connect_time() {
+    let expects = vec![TcpExpectation::ValidConnectTime(5000)];
+    let history = tcp_stories(&serve_ssh_banner(), None, expects);
+    assert_eq!(history.len(), 1);
+    assert!(
+        matches!(history[0].success, Some(Expected::TcpConnectTime(_, _, 5000))),
+        "{history:?}"
+    );
+}
+
+
+#[test]
+fn test_tcp_banner_mismatch() {
+    let history = tcp_stories(
+        &serve_ssh_banner(),
+        None,
+        vec![TcpExpectation::ValidBanner("^220 ".to_string())],
+    );
+    assert_eq!(history.len(), 1);
+    assert!(matches!(history[0].error, Some(Unexpected::TcpBannerMismatch(_, _))));
+}
+
+
+#[test]
+fn test_tcp_malformed_banner_regex() {
+    let history = tcp_stories(
+        &serve_ssh_banner(),
+        None,
+        vec![
+            TcpExpectation::ValidBanner("(unclosed".to_string()),
+            TcpExpectation::ValidBanner("^SSH-".to_string()),
+        ],
+    );
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().any(|story| {
+        matches!(story.error, Some(Unexpected::RegexMalformed(_, _, _)))
+    }));
+    assert!(history.iter().any(|story| story.success.is_some()), "{history:?}");
+}
+
+
+#[test]
+fn test_tcp_connection_refused() {
+    let closed_address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let history = tcp_stories(
+        &closed_address,
+        Some("PING\r\n"),
+        vec![TcpExpectation::ValidConnectTime(5000)],
+    );
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        &history[0].error,
+        Some(Unexpected::TcpConnectionFailed(address, _)) if *address == closed_address
+    ));
+}
@@ -0,0 +1,675 @@
+use crate::{checks::domain::StartTls, *};
+use curl::easy::{Easy2, List};
+use openssl::{
+    asn1::Asn1Time,
+    hash::MessageDigest,
+    nid::Nid,
+    ocsp::{
+        OcspCertId, OcspCertStatus, OcspFlag, OcspRequest, OcspResponse, OcspResponseStatus,
+    },
+    pkey::Id,
+    ssl::{
+        self, ErrorCode, HandshakeError, SslConnector, SslConnectorBuilder, SslMethod,
+        SslStream, SslVerifyMode, SslVersion, StatusType,
+    },
+    stack::Stack,
+    x509::{store::X509StoreBuilder, verify::X509VerifyFlags, X509VerifyResult, X509},
+};
+use std::{
+    ffi::c_int,
+    io::{Error, ErrorKind, Read, Write},
+    net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs},
+    str,
+    time::Duration,
+};
+
+
+/// OpenSSL reasons of handshake failures caused by the client itself, unable to offer
+/// requested protocol versions (SSL_R_NO_PROTOCOLS_AVAILABLE) or cipher suites
+/// (SSL_R_NO_CIPHERS_AVAILABLE)
+const TLS_CLIENT_OFFER_FAILURES: [c_int; 2] = [191, 181];
+
+/// OpenSSL reason of handshake failure, when server negotiates not offered TLS version
+/// (SSL_R_UNSUPPORTED_PROTOCOL)
+const TLS_UNSUPPORTED_PROTOCOL: c_int = 258;
+
+/// Offset of OpenSSL reasons of TLS alerts received from the server (SSL_AD_REASON_OFFSET)
+const TLS_ALERT_REASON_OFFSET: c_int = 1000;
+
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+/// TLS protocol versions
+pub enum TlsVersion {
+    /// TLS 1.0
+    #[serde(rename = "1.0")]
+    Tls10,
+
+    /// TLS 1.1
+    #[serde(rename = "1.1")]
+    Tls11,
+
+    /// TLS 1.2
+    #[serde(rename = "1.2")]
+    Tls12,
+
+    /// TLS 1.3
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let version = match self {
+            TlsVersion::Tls10 => "1.0",
+            TlsVersion::Tls11 => "1.1",
+            TlsVersion::Tls12 => "1.2",
+            TlsVersion::Tls13 => "1.3",
+        };
+        write!(f, "{version}")
+    }
+}
+
+
+impl From<TlsVersion> for SslVersion {
+    fn from(version: TlsVersion) -> SslVersion {
+        match version {
+            TlsVersion::Tls10 => SslVersion::TLS1,
+            TlsVersion::Tls11 => SslVersion::TLS1_1,
+            TlsVersion::Tls12 => SslVersion::TLS1_2,
+            TlsVersion::Tls13 => SslVersion::TLS1_3,
+        }
+    }
+}
+
+
+/// TLS peer details gathered during the handshake
+#[derive(Debug)]
+pub struct TlsPeer {
+    /// Peer (leaf) certificate
+    pub certificate: X509,
+
+    /// Certificate chain sent by the peer (including the leaf certificate)
+    pub chain: Vec<X509>,
+
+    /// Result of the chain verification against trusted CA certificates
+    pub verify_result: X509VerifyResult,
+
+    /// OCSP response stapled by the peer (DER)
+    pub stapled_ocsp: Option<Vec<u8>>,
+}
+
+
+/// Revocation status of the certificate, read from OCSP response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevocationStatus {
+    /// Certificate isn't revoked
+    Good,
+
+    /// Certificate is revoked (revocation time)
+    Revoked(String),
+
+    /// Certificate is unknown to the responder
+    Unknown,
+}
+
+
+impl TlsPeer {
+    /// Days left until certificate expiry (negative when already expired)
+    pub fn days_to_expiry(&self) -> Result<i32, Error> {
+        let now = Asn1Time::days_from_now(0).map_err(Error::other)?;
+        let diff = now
+            .diff(self.certificate.not_after())
+            .map_err(Error::other)?;
+        Ok(if diff.days == 0 && diff.secs < 0 {
+            -1
+        } else {
+            diff.days
+        })
+    }
+
+
+    /// Issuer of the certificate, ex. "C=US, O=Let's Encrypt, CN=R3"
+    pub fn issuer(&self) -> String {
+        self.certificate
+            .issuer_name()
+            .entries()
+            .map(|entry| {
+                format!(
+                    "{}={}",
+                    entry.object().nid().short_name().unwrap_or_default(),
+                    str::from_utf8(entry.data().as_slice()).unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+
+    /// DNS names and IP addresses from Subject Alternative Name extension
+    pub fn subject_alt_names(&self) -> Vec<String> {
+        self.certificate
+            .subject_alt_names()
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| {
+                        name.dnsname().map(|dns_name| dns_name.to_lowercase()).or_else(|| {
+                            name.ipaddress().and_then(|ip| {
+                                match ip.len() {
+                                    4 => Some(IpAddr::from(<[u8; 4]>::try_from(ip).ok()?)),
+                                    16 => Some(IpAddr::from(<[u8; 16]>::try_from(ip).ok()?)),
+                                    _ => None,
+                                }
+                                .map(|ip| ip.to_string())
+                            })
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+
+    /// Check if Subject Alternative Names cover given name (wildcards cover a single label)
+    pub fn covers_name(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('.').to_lowercase();
+        self.subject_alt_names().iter().any(|alt_name| {
+            match alt_name.strip_prefix("*.") {
+                Some(wildcard_domain) => {
+                    name.split_once('.').is_some_and(|(label, domain)| {
+                        !label.is_empty() && domain == wildcard_domain
+                    })
+                }
+                None => alt_name == &name,
+            }
+        })
+    }
+
+
+    /// Public key algorithm and key size in bits, ex. ("RSA", 2048)
+    pub fn public_key(&self) -> Result<(String, u32), Error> {
+        let key = self.certificate.public_key().map_err(Error::other)?;
+        let algorithm = match key.id() {
+            Id::RSA => "RSA",
+            Id::EC => "EC",
+            Id::ED25519 => "ED25519",
+            Id::ED448 => "ED448",
+            Id::DSA => "DSA",
+            _ => "UNKNOWN",
+        };
+        Ok((algorithm.to_string(), key.bits()))
+    }
+
+
+    /// Certificate signature algorithm, ex. "sha256WithRSAEncryption"
+    pub fn signature_algorithm(&self) -> String {
+        let nid: Nid = self.certificate.signature_algorithm().object().nid();
+        nid.long_name().unwrap_or_default().to_string()
+    }
+
+
+    /// Check if the certificate chain sent by the peer is complete and trusted
+    pub fn chain_verified(&self) -> bool {
+        self.verify_result == X509VerifyResult::OK
+    }
+
+
+    /// OCSP responder URL from Authority Information Access extension
+    pub fn ocsp_responder(&self) -> Option<String> {
+        self.certificate
+            .ocsp_responders()
+            .ok()?
+            .iter()
+            .next()
+            .map(|responder| responder.to_string())
+    }
+
+
+    /// Certificate of the issuer, looked up in the chain sent by the peer and in the CA bundle
+    pub fn issuer_certificate(&self, ca_bundle: Option<&str>) -> Result<X509, Error> {
+        let bundle = match ca_bundle {
+            Some(ca_bundle) => {
+                X509::stack_from_pem(read_text_file(ca_bundle)?.as_bytes())
+                    .map_err(Error::other)?
+            }
+            None => vec![],
+        };
+        self.chain
+            .iter()
+            .chain(bundle.iter())
+            .find(|candidate| candidate.issued(&self.certificate) == X509VerifyResult::OK)
+            .cloned()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("No issuer certificate of: {} found", self.issuer()),
+                )
+            })
+    }
+
+
+    /// Read revocation status of the certificate from OCSP response (DER).
+    /// Response has to be signed by the issuer (or its delegated responder), and fresh
+    pub fn revocation_status(
+        &self,
+        response: &[u8],
+        issuer: &X509,
+    ) -> Result<RevocationStatus, Error> {
+        let response = OcspResponse::from_der(response).map_err(Error::other)?;
+        if response.status() != OcspResponseStatus::SUCCESSFUL {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("OCSP response status: {:?}", response.status()),
+            ));
+        }
+        let basic = response.basic().map_err(Error::other)?;
+
+        let mut issuers = Stack::new().map_err(Error::other)?;
+        issuers.push(issuer.clone()).map_err(Error::other)?;
+        let mut store = X509StoreBuilder::new().map_err(Error::other)?;
+        store.add_cert(issuer.clone()).map_err(Error::other)?;
+        store
+            .set_flags(X509VerifyFlags::PARTIAL_CHAIN)
+            .map_err(Error::other)?;
+        basic
+            .verify(&issuers, &store.build(), OcspFlag::TRUST_OTHER)
+            .map_err(|err| {
+                Error::other(format!("OCSP response signature is invalid: {err}"))
+            })?;
+
+        let certificate_id =
+            OcspCertId::from_cert(MessageDigest::sha1(), &self.certificate, issuer)
+                .map_err(Error::other)?;
+        let status = basic.find_status(&certificate_id).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                "OCSP response doesn't contain status of the certificate",
+            )
+        })?;
+        status
+            .check_validity(CHECK_OCSP_VALIDITY_LEEWAY, Some(CHECK_OCSP_MAX_AGE))
+            .map_err(|err| Error::other(format!("OCSP response is stale: {err}")))?;
+        Ok(match status.status {
+            OcspCertStatus::GOOD => RevocationStatus::Good,
+            OcspCertStatus::REVOKED => {
+                RevocationStatus::Revoked(
+                    status
+                        .revocation_time
+                        .map(|time| time.to_string())
+                        .unwrap_or_default(),
+                )
+            }
+            _ => RevocationStatus::Unknown,
+        })
+    }
+}
+
+
+/// Connect to TLS server of given domain and read peer certificates.
+/// With address given, connects to that address instead of resolving the domain.
+/// Certificates aren't verified during the handshake - verification result is stored instead
+#[instrument]
+pub fn tls_peer(
+    domain: &str,
+    address: Option<IpAddr>,
+    port: u16,
+    starttls: Option<StartTls>,
+    ca_bundle: Option<&str>,
+) -> Result<TlsPeer, Error> {
+    let stream = tls_connect(domain, address, port, starttls, ca_bundle)?;
+    let ssl = stream.ssl();
+    let certificate = ssl.peer_certificate().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("No certificate sent by: {domain}:{port}"),
+        )
+    })?;
+    let chain = ssl
+        .peer_cert_chain()
+        .map(|chain| chain.iter().map(|cert| cert.to_owned()).collect())
+        .unwrap_or_default();
+    Ok(TlsPeer {
+        certificate,
+        chain,
+        verify_result: ssl.verify_result(),
+        stapled_ocsp: ssl.ocsp_status().map(|response| response.to_vec()),
+    })
+}
+
+
+/// Query OCSP responder for the status of certificate issued by given issuer.
+/// Returns OCSP response (DER)
+#[instrument(skip(certificate, issuer))]
+pub fn ocsp_query(
+    responder: &str,
+    certificate: &X509,
+    issuer: &X509,
+) -> Result<Vec<u8>, Error> {
+    let certificate_id = OcspCertId::from_cert(MessageDigest::sha1(), certificate, issuer)
+        .map_err(Error::other)?;
+    let mut request = OcspRequest::new().map_err(Error::other)?;
+    request.add_id(certificate_id).map_err(Error::other)?;
+    let request = request.to_der().map_err(Error::other)?;
+
+    debug!("Querying OCSP responder: {responder}");
+    let curl_error = |err: curl::Error| Error::other(format!("{responder}: {err}"));
+    let mut headers = List::new();
+    headers
+        .append("Content-Type: application/ocsp-request")
+        .map_err(curl_error)?;
+    let mut curl = Easy2::new(Collector::default());
+    curl.url(responder).map_err(curl_error)?;
+    curl.timeout(Duration::from_secs(CHECK_TIMEOUT))
+        .map_err(curl_error)?;
+    curl.http_headers(headers).map_err(curl_error)?;
+    curl.post(true).map_err(curl_error)?;
+    curl.post_fields_copy(&request).map_err(curl_error)?;
+    curl.perform().map_err(curl_error)?;
+
+    let response_code = curl.response_code().map_err(curl_error)?;
+    if response_code != CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE {
+        return Err(Error::other(format!(
+            "OCSP responder: {responder} returned error: {response_code}"
+        )));
+    }
+    Ok(curl.get_ref().0.to_vec())
+}
+
+
+/// Open TCP connection to given domain (or address) and port
+#[instrument]
+pub fn tcp_connect(
+    domain: &str,
+    address: Option<IpAddr>,
+    port: u16,
+) -> Result<TcpStream, Error> {
+    let socket_address = match address {
+        Some(address) => SocketAddr::new(address, port),
+        None => {
+            (domain, port).to_socket_addrs()?.next().ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Couldn't resolve any address from domain: {domain}"),
+                )
+            })?
+        }
+    };
+    let timeout = Duration::from_secs(CHECK_TIMEOUT);
+    let stream = TcpStream::connect_timeout(&socket_address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+
+/// Perform TLS handshake with given domain (or address) and port.
+/// With starttls protocol given, plain text connection is upgraded to TLS first
+#[instrument]
+pub fn tls_connect(
+    domain: &str,
+    address: Option<IpAddr>,
+    port: u16,
+    starttls: Option<StartTls>,
+    ca_bundle: Option<&str>,
+) -> Result<SslStream<TcpStream>, Error> {
+    let mut stream = tcp_connect(domain, address, port)?;
+    if let Some(protocol) = starttls {
+        starttls_upgrade(&mut stream, protocol).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("{protocol:?} STARTTLS with: {domain}:{port} failed: {err}"),
+            )
+        })?;
+    }
+    let mut connector = SslConnector::builder(SslMethod::tls()).map_err(Error::other)?;
+    connector.set_verify(SslVerifyMode::NONE);
+    if let Some(ca_bundle) = ca_bundle {
+        connector.set_ca_file(ca_bundle).map_err(Error::other)?;
+    }
+    let mut configuration = connector.build().configure().map_err(Error::other)?;
+    configuration.set_verify_hostname(false); // name coverage is checked separately
+    configuration
+        .set_status_type(StatusType::OCSP)
+        .map_err(Error::other)?;
+    configuration
+        .connect(domain, stream)
+        .map_err(|err| {
+            Error::other(format!("TLS handshake with: {domain}:{port} failed: {err}"))
+        })
+}
+
+
+/// Check if server accepts given TLS version
+#[instrument]
+pub fn tls_version_accepted(
+    domain: &str,
+    address: Option<IpAddr>,
+    port: u16,
+    starttls: Option<StartTls>,
+    version: TlsVersion,
+) -> Result<bool, Error> {
+    let accepted = tls_probe(domain, address, port, starttls, |connector| {
+        // legacy versions are disabled by default security level
+        connector.set_cipher_list("ALL:@SECLEVEL=0")?;
+        connector.set_min_proto_version(Some(version.into()))?;
+        connector.set_max_proto_version(Some(version.into()))
+    })?;
+    Ok(accepted.is_some())
+}
+
+
+/// Names of weak cipher suites accepted by the server. Each accepted cipher suite
+/// is excluded from the offer in the next probe, until the server refuses the handshake
+#[instrument]
+pub fn weak_ciphers_accepted(
+    domain: &str,
+    address: Option<IpAddr>,
+    port: u16,
+    starttls: Option<StartTls>,
+) -> Result<Vec<String>, Error> {
+    let mut accepted: Vec<String> = vec![];
+    loop {
+        let cipher_list = accepted
+            .iter()
+            .fold(CHECK_WEAK_TLS_CIPHERS.to_string(), |list, cipher| {
+                format!("{list}:!{cipher}")
+            });
+        let probe = tls_probe(domain, address, port, starttls, |connector| {
+            connector.set_cipher_list(&cipher_list)?;
+            connector.set_min_proto_version(Some(SslVersion::TLS1))?;
+            // TLS 1.3 cipher suites are all strong
+            connector.set_max_proto_version(Some(SslVersion::TLS1_2))
+        });
+        match probe {
+            Ok(Some(stream)) => {
+                match stream.ssl().current_cipher() {
+                    Some(cipher) if !accepted.contains(&cipher.name().to_string()) => {
+                        accepted.push(cipher.name().to_string())
+                    }
+                    _ => return Ok(accepted),
+                }
+            }
+            Ok(None) => return Ok(accepted),
+            // no weak cipher suite left to offer
+            Err(err) if err.kind() == ErrorKind::InvalidInput && !accepted.is_empty() => {
+                return Ok(accepted)
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+
+/// Perform TLS handshake with connector configured by configure().
+/// Returns None when the server refuses the handshake. Connection failures, failures of
+/// the client to offer configured protocol versions or cipher suites are errors
+fn tls_probe<F>(
+    domain: &str,
+    address: Option<IpAddr>,
+    port: u16,
+    starttls: Option<StartTls>,
+    configure: F,
+) -> Result<Option<SslStream<TcpStream>>, Error>
+where
+    F: FnOnce(&mut SslConnectorBuilder) -> Result<(), openssl::error::ErrorStack>,
+{
+    let mut connector = SslConnector::builder(SslMethod::tls()).map_err(Error::other)?;
+    connector.set_verify(SslVerifyMode::NONE);
+    configure(&mut connector).map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+    let mut configuration = connector.build().configure().map_err(Error::other)?;
+    configuration.set_verify_hostname(false);
+    let mut stream = tcp_connect(domain, address, port)?;
+    if let Some(protocol) = starttls {
+        starttls_upgrade(&mut stream, protocol)?;
+    }
+    match configuration.connect(domain, stream) {
+        Ok(stream) => Ok(Some(stream)),
+        Err(HandshakeError::Failure(handshake)) if handshake_refused(handshake.error()) => {
+            Ok(None)
+        }
+        Err(HandshakeError::Failure(handshake)) if client_offer_failed(handshake.error()) => {
+            Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "TLS client can't offer configured protocol versions or cipher suites to: \
+                     {domain}:{port}. Details: {}",
+                    handshake.error()
+                ),
+            ))
+        }
+        Err(err) => {
+            Err(Error::other(format!("TLS handshake with: {domain}:{port} failed: {err}")))
+        }
+    }
+}
+
+
+/// Check if TLS handshake failed, because the server refused it with TLS alert,
+/// or negotiated TLS version which wasn't offered
+fn handshake_refused(error: &ssl::Error) -> bool {
+    ssl_error_reasons(error).any(|reason| {
+        reason >= TLS_ALERT_REASON_OFFSET || reason == TLS_UNSUPPORTED_PROTOCOL
+    })
+}
+
+
+/// Check if TLS handshake failed, because the client had nothing to offer
+fn client_offer_failed(error: &ssl::Error) -> bool {
+    ssl_error_reasons(error).any(|reason| TLS_CLIENT_OFFER_FAILURES.contains(&reason))
+}
+
+
+/// OpenSSL reasons of TLS protocol error. I/O errors have none
+fn ssl_error_reasons(error: &ssl::Error) -> impl Iterator<Item = c_int> + '_ {
+    error
+        .ssl_error()
+        .filter(|_| error.code() == ErrorCode::SSL)
+        .into_iter()
+        .flat_map(|stack| stack.errors().iter().map(|err| err.reason_code()))
+}
+
+
+/// Negotiate TLS upgrade of plain text connection using given protocol
+#[instrument(skip(stream))]
+pub fn starttls_upgrade(stream: &mut TcpStream, protocol: StartTls) -> Result<(), Error> {
+    match protocol {
+        StartTls::Smtp => {
+            expect_reply(stream, "220")?;
+            stream.write_all(b"EHLO krecik\r\n")?;
+            expect_reply(stream, "250")?;
+            stream.write_all(b"STARTTLS\r\n")?;
+            expect_reply(stream, "220")
+        }
+
+        StartTls::Imap => {
+            expect_line(stream, "* OK")?;
+            stream.write_all(b"K1 STARTTLS\r\n")?;
+            loop {
+                // skip untagged responses
+                let line = read_line(stream)?;
+                if line.starts_with("K1 ") {
+                    return expect_prefix(&line, "K1 OK");
+                }
+            }
+        }
+
+        StartTls::Pop3 => {
+            expect_line(stream, "+OK")?;
+            stream.write_all(b"STLS\r\n")?;
+            expect_line(stream, "+OK")
+        }
+
+        StartTls::Ftp => {
+            expect_reply(stream, "220")?;
+            stream.write_all(b"AUTH TLS\r\n")?;
+            expect_reply(stream, "234")
+        }
+
+        StartTls::Postgres => {
+            // SSLRequest: message length (8) and SSL request code (80877103)
+            stream.write_all(&[0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f])?;
+            let mut answer = [0u8; 1];
+            stream.read_exact(&mut answer)?;
+            match answer {
+                [b'S'] => Ok(()),
+                _ => Err(Error::new(ErrorKind::Unsupported, "Server refused SSLRequest")),
+            }
+        }
+    }
+}
+
+
+/// Read single line of plain text protocol. Bytes are read one by one,
+/// to leave the rest of the stream untouched for the TLS handshake.
+/// Lines longer than CHECK_MAX_LINE_LENGTH are rejected as invalid data
+pub(crate) fn read_line(stream: &mut TcpStream) -> Result<String, Error> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while byte[0] != b'\n' {
+        if line.len() >= CHECK_MAX_LINE_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Line is longer than {CHECK_MAX_LINE_LENGTH} bytes"),
+            ));
+        }
+        stream.read_exact(&mut byte)?;
+        line.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+
+/// Check if line starts with expected prefix
+fn expect_prefix(line: &str, prefix: &str) -> Result<(), Error> {
+    if line.starts_with(prefix) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Expected: \"{prefix}\", got: \"{line}\""),
+        ))
+    }
+}
+
+
+/// Read single line starting with expected prefix
+fn expect_line(stream: &mut TcpStream, prefix: &str) -> Result<(), Error> {
+    expect_prefix(&read_line(stream)?, prefix)
+}
+
+
+/// Read SMTP/FTP reply (possibly multiline, ex. "250-…", "250 …") with expected code
+fn expect_reply(stream: &mut TcpStream, code: &str) -> Result<(), Error> {
+    loop {
+        let line = read_line(stream)?;
+        if line.get(3..4) != Some("-") {
+            return expect_prefix(&line, code);
+        }
+    }
+}
+
+
+/// Tests:
+#[cfg(test)]
+mod tests;
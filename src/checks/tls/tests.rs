@@ -0,0 +1,613 @@
+use openssl::{
+    hash::MessageDigest,
+    ocsp::{OcspCertId, OcspRequest},
+    ssl::{SslAcceptor, SslMethod, SslOptions, SslVersion},
+    x509::X509,
+};
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    thread,
+};
+
+use super::tls_probe;
+use crate::{
+    actors::{generic_checker::GenericChecker, multi_checker::MultiChecker},
+    checks::domain::{Domain, StartTls},
+    test_fixtures::*,
+    *,
+};
+
+
+const LOCALHOST: &str = "127.0.0.1";
+
+
+/// Domain "localhost" served on given port, with given expectations
+fn domain(port: u16, expects: DomainExpectations) -> Domain {
+    Domain {
+        name: "localhost".to_string(),
+        expects,
+        fan_out_dns: None,
+        ca_bundle: None,
+        port: Some(port),
+        starttls: None,
+        ocsp_responder: None,
+        rdap_server: None,
+        whois_server: None,
+    }
+}
+
+
+/// Spawns local TLS server with certificate for "localhost", issued by test CA.
+/// Returns port of the server and path of the CA bundle (named after given name)
+fn serve_localhost(name: &str) -> (u16, FixtureFile) {
+    let (ca, ca_key) = issue_certificate("Krecik Test CA", None);
+    let (server_cert, server_key) = issue_certificate("localhost", Some((&ca, &ca_key)));
+    let port = serve_starttls(&server_cert, &server_key, |_| true);
+    (port, fixture_file(name, &ca.to_pem().unwrap()))
+}
+
+
+/// Connects to local TLS server, trusting its CA when ca_bundle is given
+fn peer(port: u16, ca_bundle: Option<&str>) -> TlsPeer {
+    tls_peer("localhost", Some(LOCALHOST.parse().unwrap()), port, None, ca_bundle).unwrap()
+}
+
+
+/// Checks single expectation of given domain name against the peer
+fn certificate_story(peer: &TlsPeer, name: &str, expectation: DomainExpectation) -> Story {
+    let address: Option<IpAddr> = Some(LOCALHOST.parse().unwrap());
+    let mut domain = domain(CHECK_DEFAULT_TLS_PORT, vec![]);
+    domain.name = name.to_string();
+    MultiChecker::handle_domain_expectation(name, &domain, address, peer, &expectation, None)
+}
+
+
+#[test]
+fn test_tls_peer_details() {
+    let (port, ca_bundle) = serve_localhost("peer-details-ca.pem");
+    let peer = peer(port, Some(&ca_bundle));
+
+    assert_eq!(peer.issuer(), "CN=Krecik Test CA");
+    assert_eq!(peer.subject_alt_names(), vec!["localhost", "127.0.0.1"]);
+    assert_eq!(peer.public_key().unwrap(), ("RSA".to_string(), 2048));
+    assert_eq!(peer.signature_algorithm(), "sha256WithRSAEncryption");
+}
+
+
+#[test]
+fn test_certificate_expectations_met() {
+    let (port, ca_bundle) = serve_localhost("expectations-met-ca.pem");
+    let peer = peer(port, Some(&ca_bundle));
+
+    for expectation in [
+        DomainExpectation::ValidExpiryPeriod(30),
+        DomainExpectation::ValidIssuer("krecik test ca".to_string()),
+        DomainExpectation::ValidSubjectAltName,
+        DomainExpectation::ValidChain,
+        DomainExpectation::ValidKeyAlgorithm("rsa".to_string()),
+        DomainExpectation::ValidMinimumKeySize(2048),
+        DomainExpectation::ValidSignatureAlgorithm("sha256".to_string()),
+    ] {
+        let story = certificate_story(&peer, "localhost", expectation);
+        assert!(story.success.is_some(), "{story:?}");
+    }
+}
+
+
+#[test]
+fn test_certificate_expiring_too_soon() {
+    let (port, _) = serve_localhost("expiring-ca.pem");
+    let story = certificate_story(
+        &peer(port, None),
+        "localhost",
+        DomainExpectation::ValidExpiryPeriod(100),
+    );
+    assert!(matches!(story.error, Some(Unexpected::TLSDomainExpired(_, _))));
+}
+
+
+#[test]
+fn test_certificate_issuer_mismatch() {
+    let (port, _) = serve_localhost("issuer-ca.pem");
+    let story = certificate_story(
+        &peer(port, None),
+        "localhost",
+        DomainExpectation::ValidIssuer("Let's Encrypt".to_string()),
+    );
+    assert!(matches!(story.error, Some(Unexpected::TLSIssuerMismatch(_, _, _))));
+}
+
+
+#[test]
+fn test_certificate_not_covering_name() {
+    let (port, _) = serve_localhost("name-ca.pem");
+    let story = certificate_story(
+        &peer(port, None),
+        "example.com",
+        DomainExpectation::ValidSubjectAltName,
+    );
+    assert!(matches!(story.error, Some(Unexpected::TLSNameNotCovered(_, _))));
+}
+
+
+#[test]
+fn test_certificate_chain_untrusted() {
+    let (port, _) = serve_localhost("chain-ca.pem");
+    let story =
+        certificate_story(&peer(port, None), "localhost", DomainExpectation::ValidChain);
+    assert!(matches!(story.error, Some(Unexpected::TLSChainIncomplete(_, _))));
+}
+
+
+#[test]
+fn test_certificate_key_mismatch() {
+    let (port, _) = serve_localhost("key-ca.pem");
+    let peer = peer(port, None);
+
+    let story = certificate_story(
+        &peer,
+        "localhost",
+        DomainExpectation::ValidKeyAlgorithm("ECDSA".to_string()),
+    );
+    assert!(matches!(story.error, Some(Unexpected::TLSKeyAlgorithmMismatch(_, _, _))));
+
+    let story =
+        certificate_story(&peer, "localhost", DomainExpectation::ValidMinimumKeySize(4096));
+    assert!(matches!(story.error, Some(Unexpected::TLSKeyTooSmall(_, 2048, 4096))));
+}
+
+
+#[test]
+fn test_certificate_signature_algorithm_mismatch() {
+    let (port, _) = serve_localhost("signature-ca.pem");
+    let story = certificate_story(
+        &peer(port, None),
+        "localhost",
+        DomainExpectation::ValidSignatureAlgorithm("sha384".to_string()),
+    );
+    assert!(matches!(story.error, Some(Unexpected::TLSSignatureAlgorithmMismatch(_, _, _))));
+}
+
+
+#[test]
+fn test_unreachable_domain_is_minor_problem() {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let history = MultiChecker::check_domains(&[Check {
+        domains: Some(vec![domain(
+            port,
+            vec![DomainExpectation::ValidChain, DomainExpectation::ValidExpiryPeriod(30)],
+        )]),
+        ..Check::default()
+    }]);
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().all(|story| story.minor.is_some()), "{history:?}");
+}
+
+
+#[test]
+fn test_read_line_rejects_overlong_line() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            stream.write_all(b"220 ready\r\n").unwrap_or_default();
+            stream
+                .write_all(&vec![b'X'; CHECK_MAX_LINE_LENGTH + 1])
+                .unwrap_or_default();
+        }
+    });
+    let mut stream = TcpStream::connect(address).unwrap();
+    assert_eq!(read_line(&mut stream).unwrap(), "220 ready");
+    assert_eq!(read_line(&mut stream).unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+
+/// Checks certificate expiry and chain of "localhost", served by local server talking
+/// given STARTTLS protocol with negotiate()
+fn starttls_stories<F>(name: &str, starttls: Option<StartTls>, negotiate: F) -> Stories
+where
+    F: Fn(&mut TcpStream) -> bool + Send + 'static,
+{
+    let (ca, ca_key) = issue_certificate("Krecik Test CA", None);
+    let (server_cert, server_key) = issue_certificate("localhost", Some((&ca, &ca_key)));
+    let port = serve_starttls(&server_cert, &server_key, negotiate);
+    let ca_bundle = fixture_file(name, &ca.to_pem().unwrap());
+    let mut domain = domain(
+        port,
+        vec![DomainExpectation::ValidExpiryPeriod(30), DomainExpectation::ValidChain],
+    );
+    domain.ca_bundle = Some(ca_bundle.to_string());
+    domain.starttls = starttls;
+    MultiChecker::check_domains(&[Check {
+        domains: Some(vec![domain]),
+        ..Check::default()
+    }])
+}
+
+
+/// Asserts that both stories succeeded and are labeled with the non default port
+fn assert_port_stories(history: &Stories) {
+    assert_eq!(history.len(), 2, "{history:?}");
+    for story in history {
+        assert!(story.success.is_some(), "{story:?}");
+        assert!(story.to_string().contains("localhost:"), "{story}");
+    }
+}
+
+
+#[test]
+fn test_domain_on_custom_port() {
+    assert_port_stories(&starttls_stories("custom-port-ca.pem", None, |_| true));
+}
+
+
+#[test]
+fn test_domain_with_smtp_starttls() {
+    let history = starttls_stories("smtp-ca.pem", Some(StartTls::Smtp), |stream| {
+        stream.write_all(b"220-mail.localhost ESMTP\r\n220 Krecik\r\n").unwrap();
+        assert!(read_client_line(stream).starts_with("EHLO "));
+        stream
+            .write_all(b"250-mail.localhost\r\n250-PIPELINING\r\n250 STARTTLS\r\n")
+            .unwrap();
+        assert_eq!(read_client_line(stream), "STARTTLS");
+        stream.write_all(b"220 Ready to start TLS\r\n").unwrap();
+        true
+    });
+    assert_port_stories(&history);
+}
+
+
+#[test]
+fn test_domain_with_imap_starttls() {
+    let history = starttls_stories("imap-ca.pem", Some(StartTls::Imap), |stream| {
+        stream.write_all(b"* OK IMAP4rev1 ready\r\n").unwrap();
+        let command = read_client_line(stream);
+        let tag = command.split_whitespace().next().unwrap_or_default().to_string();
+        assert!(command.ends_with(" STARTTLS"));
+        let response = format!("* CAPABILITY IMAP4rev1\r\n{tag} OK Begin TLS\r\n");
+        stream.write_all(response.as_bytes()).unwrap();
+        true
+    });
+    assert_port_stories(&history);
+}
+
+
+#[test]
+fn test_domain_with_pop3_starttls() {
+    let history = starttls_stories("pop3-ca.pem", Some(StartTls::Pop3), |stream| {
+        stream.write_all(b"+OK POP3 ready\r\n").unwrap();
+        assert_eq!(read_client_line(stream), "STLS");
+        stream.write_all(b"+OK Begin TLS\r\n").unwrap();
+        true
+    });
+    assert_port_stories(&history);
+}
+
+
+#[test]
+fn test_domain_with_ftp_starttls() {
+    let history = starttls_stories("ftp-ca.pem", Some(StartTls::Ftp), |stream| {
+        stream.write_all(b"220 FTP ready\r\n").unwrap();
+        assert_eq!(read_client_line(stream), "AUTH TLS");
+        stream.write_all(b"234 AUTH TLS OK\r\n").unwrap();
+        true
+    });
+    assert_port_stories(&history);
+}
+
+
+#[test]
+fn test_domain_with_postgres_starttls() {
+    let history = starttls_stories("postgres-ca.pem", Some(StartTls::Postgres), |stream| {
+        let mut ssl_request = [0u8; 8];
+        stream.read_exact(&mut ssl_request).unwrap();
+        assert_eq!(ssl_request, [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f]);
+        stream.write_all(b"S").unwrap();
+        true
+    });
+    assert_port_stories(&history);
+}
+
+
+#[test]
+fn test_domain_refusing_postgres_starttls() {
+    let history =
+        starttls_stories("refusing-postgres-ca.pem", Some(StartTls::Postgres), |stream| {
+            let mut ssl_request = [0u8; 8];
+            stream.read_exact(&mut ssl_request).unwrap();
+            stream.write_all(b"N").unwrap();
+            false
+        });
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().all(|story| story.minor.is_some()), "{history:?}");
+}
+
+
+#[test]
+fn test_domain_default_ports() {
+    assert_eq!(StartTls::Smtp.default_port(), 25);
+    assert_eq!(domain(CHECK_DEFAULT_TLS_PORT, vec![]).label(), "localhost");
+    assert_eq!(domain(8443, vec![]).label(), "localhost:8443");
+}
+
+
+/// Checks TLS protocol expectations of "localhost" served on given port
+fn protocol_stories(port: u16) -> Stories {
+    MultiChecker::check_domains(&[Check {
+        domains: Some(vec![domain(
+            port,
+            vec![
+                DomainExpectation::ForbidTlsVersion(TlsVersion::Tls10),
+                DomainExpectation::ForbidTlsVersion(TlsVersion::Tls11),
+                DomainExpectation::RequireTlsVersion(TlsVersion::Tls13),
+                DomainExpectation::ForbidWeakCiphers,
+            ],
+        )]),
+        ..Check::default()
+    }])
+}
+
+
+/// Spawns legacy server: TLS 1.0 - 1.2 with anonymous and NULL cipher suites.
+/// Returns errors of the protocol expectations
+fn legacy_protocol_errors() -> Vec<Unexpected> {
+    let (server_cert, server_key) = issue_certificate("localhost", None);
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_certificate(&server_cert).unwrap();
+    acceptor.set_private_key(&server_key).unwrap();
+    acceptor.set_cipher_list("ALL:aNULL:eNULL:@SECLEVEL=0").unwrap();
+    acceptor.clear_options(SslOptions::NO_TLSV1 | SslOptions::NO_TLSV1_1);
+    acceptor.set_min_proto_version(Some(SslVersion::TLS1)).unwrap();
+    acceptor.set_max_proto_version(Some(SslVersion::TLS1_2)).unwrap();
+    let port = serve_tls_acceptor(acceptor.build(), |_| true);
+    let history = protocol_stories(port);
+    assert_eq!(history.len(), 4);
+    history.into_iter().filter_map(|story| story.error).collect()
+}
+
+
+#[test]
+fn test_strict_server_protocol_expectations_met() {
+    let (server_cert, server_key) = issue_certificate("localhost", None);
+    let history = protocol_stories(serve_starttls(&server_cert, &server_key, |_| true));
+    assert_eq!(history.len(), 4);
+    for story in history {
+        assert!(story.success.is_some(), "{story:?}");
+    }
+}
+
+
+#[test]
+fn test_legacy_server_protocol_expectations_failed() {
+    let errors = legacy_protocol_errors();
+    assert_eq!(errors.len(), 4, "{errors:?}");
+}
+
+
+#[test]
+fn test_legacy_tls_version_forbidden() {
+    assert!(legacy_protocol_errors().iter().any(|error| {
+        matches!(error, Unexpected::TLSVersionForbidden(_, version) if version == "1.0")
+    }));
+}
+
+
+#[test]
+fn test_modern_tls_version_missing() {
+    assert!(legacy_protocol_errors().iter().any(|error| {
+        matches!(error, Unexpected::TLSVersionMissing(_, version) if version == "1.3")
+    }));
+}
+
+
+#[test]
+fn test_weak_ciphers_accepted() {
+    assert!(legacy_protocol_errors().iter().any(|error| {
+        matches!(
+            error,
+            Unexpected::TLSWeakCiphersAccepted(_, ciphers) if ciphers.contains("NULL")
+        )
+    }));
+}
+
+
+/// Spawns server closing every connection without TLS handshake. Returns its port
+fn serve_closing() -> u16 {
+    let listener = TcpListener::bind((LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            drop(stream);
+        }
+    });
+    port
+}
+
+
+#[test]
+fn test_closed_connection_isnt_refusal() {
+    let port = serve_closing();
+    let address = Some(LOCALHOST.parse().unwrap());
+    let legacy_version =
+        tls_version_accepted("localhost", address, port, None, TlsVersion::Tls10);
+    assert!(legacy_version.is_err());
+    assert!(weak_ciphers_accepted("localhost", address, port, None).is_err());
+}
+
+
+#[test]
+fn test_client_offer_failure_isnt_refusal() {
+    let (server_cert, server_key) = issue_certificate("localhost", None);
+    let port = serve_starttls(&server_cert, &server_key, |_| true);
+    // NULL cipher suites aren't offered at default security level:
+    let address = Some(LOCALHOST.parse().unwrap());
+    let probe = tls_probe("localhost", address, port, None, |connector| {
+        connector.set_cipher_list("eNULL")?;
+        connector.set_max_proto_version(Some(SslVersion::TLS1_2))
+    });
+    assert_eq!(probe.err().map(|err| err.kind()), Some(ErrorKind::Unsupported));
+}
+
+
+#[test]
+fn test_tls_version_deserialization() {
+    let expectation: DomainExpectation =
+        serde_json::from_str(r#"{"ForbidTlsVersion": "1.0"}"#).unwrap();
+    assert_eq!(expectation, DomainExpectation::ForbidTlsVersion(TlsVersion::Tls10));
+}
+
+
+/// Signs OCSP response for given OCSP request (DER) with CA, using "openssl ocsp"
+/// and OpenSSL CA index file (with "V"alid and "R"evoked certificate entries)
+fn ocsp_response(
+    request: &[u8],
+    index_file: &str,
+    ca_file: &str,
+    ca_key_file: &str,
+) -> Vec<u8> {
+    let mut responder = std::process::Command::new("openssl")
+        .args([
+            "ocsp", "-index", index_file, "-CA", ca_file, "-rsigner", ca_file, "-rkey",
+            ca_key_file, "-ndays", "1", "-reqin", "/dev/stdin", "-respout", "/dev/stdout",
+        ])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    responder.stdin.take().unwrap().write_all(request).unwrap();
+    responder.wait_with_output().unwrap().stdout
+}
+
+
+/// Spawns local stand-in OCSP responder. Returns its URL
+fn serve_ocsp(index_file: &str, ca_file: &str, ca_key_file: &str) -> String {
+    let (index_file, ca_file, ca_key_file) =
+        (index_file.to_string(), ca_file.to_string(), ca_key_file.to_string());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let request = read_raw_request(&mut stream);
+            let body = match request.windows(4).position(|window| window == b"\r\n\r\n") {
+                Some(head_length) => &request[head_length + 4..],
+                None => &[],
+            };
+            let response = http_response(
+                200,
+                &["Content-Type: application/ocsp-response"],
+                &ocsp_response(body, &index_file, &ca_file, &ca_key_file),
+            );
+            stream.write_all(&response).unwrap_or_default();
+        }
+    });
+    format!("http://{address}/")
+}
+
+
+/// Checks revocation and OCSP stapling of "localhost" certificate, valid or revoked,
+/// served with or without stapled OCSP response. Files are named after given name
+fn ocsp_stories(name: &str, revoked: bool, stapling: bool) -> Stories {
+    let (ca, ca_key) = issue_certificate("Krecik Test CA", None);
+    let (valid_cert, valid_key) = issue_certificate("localhost", Some((&ca, &ca_key)));
+    let (revoked_cert, revoked_key) = issue_certificate("localhost", Some((&ca, &ca_key)));
+    let serial = |certificate: &X509| {
+        certificate
+            .serial_number()
+            .to_bn()
+            .unwrap()
+            .to_hex_str()
+            .unwrap()
+            .to_string()
+    };
+    let ca_file = fixture_file(&format!("{name}-ca.pem"), &ca.to_pem().unwrap());
+    let ca_key_file = fixture_file(
+        &format!("{name}-ca.key"),
+        &ca_key.private_key_to_pem_pkcs8().unwrap(),
+    );
+    let index_file = fixture_file(
+        &format!("{name}-index.txt"),
+        format!(
+            "V\t301231235959Z\t\t{}\tunknown\t/CN=localhost\n\
+             R\t301231235959Z\t250101000000Z\t{}\tunknown\t/CN=localhost\n",
+            serial(&valid_cert),
+            serial(&revoked_cert)
+        )
+        .as_bytes(),
+    );
+    let responder = serve_ocsp(&index_file, &ca_file, &ca_key_file);
+
+    let (certificate, key) = match revoked {
+        true => (revoked_cert, revoked_key),
+        false => (valid_cert, valid_key),
+    };
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_certificate(&certificate).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    if stapling {
+        let certificate_id =
+            OcspCertId::from_cert(MessageDigest::sha1(), &certificate, &ca).unwrap();
+        let mut request = OcspRequest::new().unwrap();
+        request.add_id(certificate_id).unwrap();
+        let stapled =
+            ocsp_response(&request.to_der().unwrap(), &index_file, &ca_file, &ca_key_file);
+        acceptor
+            .set_status_callback(move |ssl| {
+                ssl.set_ocsp_status(&stapled)?;
+                Ok(true)
+            })
+            .unwrap();
+    }
+    let port = serve_tls_acceptor(acceptor.build(), |_| true);
+
+    let mut domain = domain(
+        port,
+        vec![DomainExpectation::NotRevoked, DomainExpectation::RequireOcspStapling],
+    );
+    domain.ca_bundle = Some(ca_file.to_string());
+    domain.ocsp_responder = Some(responder);
+    let history = MultiChecker::check_domains(&[Check {
+        domains: Some(vec![domain]),
+        ..Check::default()
+    }]);
+    assert_eq!(history.len(), 2);
+    history
+}
+
+
+#[test]
+fn test_valid_certificate_not_revoked() {
+    let history = ocsp_stories("ocsp-valid", false, false);
+    assert!(history.iter().any(|story| {
+        matches!(story.success, Some(Expected::TLSCertificateNotRevoked(_, _)))
+    }));
+}
+
+
+#[test]
+fn test_ocsp_stapling_missing() {
+    let history = ocsp_stories("ocsp-no-stapling", false, false);
+    assert!(history.iter().any(|story| {
+        matches!(story.error, Some(Unexpected::TLSOcspStaplingMissing(_, _)))
+    }));
+}
+
+
+#[test]
+fn test_ocsp_stapling_fresh() {
+    for story in ocsp_stories("ocsp-stapling", false, true) {
+        assert!(story.success.is_some(), "{story:?}");
+    }
+}
+
+
+#[test]
+fn test_revoked_certificate() {
+    for story in ocsp_stories("ocsp-revoked", true, true) {
+        assert!(
+            matches!(story.error, Some(Unexpected::TLSCertificateRevoked(_, _))),
+            "{story:?}"
+        );
+    }
+}
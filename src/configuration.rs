@@ -45,6 +45,85 @@ pub const CHECK_HTTP_MINIMUM_LENGHT: usize = 128;
 /// Default interval (in seconds) between checks to not exceed 3 checks per minute (considered a flood)
 pub const CHECK_MINIMUM_INTERVAL: usize = 20;
 
+/// Amount of bytes from the beginning of the page searched for <meta charset>
+pub const CHECK_CHARSET_SNIFF_LENGTH: usize = 1024;
+
+/// Default OAuth2 token lifetime (in seconds), used when token endpoint doesn't return "expires_in"
+pub const CHECK_OAUTH2_DEFAULT_TOKEN_TTL: u64 = 300;
+
+/// Amount of seconds before OAuth2 token expiry, when cached token gets refreshed
+pub const CHECK_OAUTH2_TOKEN_REFRESH_MARGIN: u64 = 30;
+
+/// Default TLS port used by domain checks
+pub const CHECK_DEFAULT_TLS_PORT: u16 = 443;
+
+/// OpenSSL cipher list of weak cipher suites, offered by ForbidWeakCiphers probes
+pub const CHECK_WEAK_TLS_CIPHERS: &str =
+    "aNULL:eNULL:EXPORT:LOW:RC4:DES:3DES:MD5:!PSK:@SECLEVEL=0";
+
+/// Allowed clock skew (in seconds) when checking OCSP response validity period
+pub const CHECK_OCSP_VALIDITY_LEEWAY: u32 = 300;
+
+/// Maximum age (in seconds) of OCSP response (its "this update" time)
+pub const CHECK_OCSP_MAX_AGE: u32 = 604800;
+
+/// Default RDAP server (bootstrap service redirecting to RDAP server of the registry)
+pub const CHECK_DEFAULT_RDAP_SERVER: &str = "https://rdap.org";
+
+/// Default WHOIS server (IANA, referring to WHOIS server of the registry)
+pub const CHECK_DEFAULT_WHOIS_SERVER: &str = "whois.iana.org";
+
+/// Default WHOIS port
+pub const CHECK_DEFAULT_WHOIS_PORT: u16 = 43;
+
+/// Amount of seconds domain registration expiry is cached for
+pub const CHECK_REGISTRATION_CACHE_TTL: u64 = 86400;
+
+/// Default DNS port
+pub const CHECK_DEFAULT_DNS_PORT: u16 = 53;
+
+/// Maximum number of DNS lookups needed to evaluate SPF record (RFC 7208)
+pub const CHECK_SPF_MAX_DNS_LOOKUPS: usize = 10;
+
+/// Maximum length of single line read from plain text protocols (SMTP, FTP, SSH banners)
+pub const CHECK_MAX_LINE_LENGTH: usize = 4096;
+
+/// Default SMTP port of MX hosts
+pub const CHECK_DEFAULT_SMTP_PORT: u16 = 25;
+
+/// Maximum size of response read from TCP service (bytes)
+pub const CHECK_TCP_MAX_RESPONSE_SIZE: usize = 65536;
+
+/// Default SSH port
+pub const CHECK_DEFAULT_SSH_PORT: u16 = 22;
+
+/// Maximum number of lines SSH server may send before its protocol banner
+pub const CHECK_SSH_MAX_PREAMBLE_LINES: usize = 32;
+
+/// Default PostgreSQL port
+pub const CHECK_DEFAULT_POSTGRES_PORT: u16 = 5432;
+
+/// Default PostgreSQL user
+pub const CHECK_DEFAULT_POSTGRES_USER: &str = "postgres";
+
+/// Default MySQL port
+pub const CHECK_DEFAULT_MYSQL_PORT: u16 = 3306;
+
+/// Default MySQL user
+pub const CHECK_DEFAULT_MYSQL_USER: &str = "root";
+
+/// Default Redis port
+pub const CHECK_DEFAULT_REDIS_PORT: u16 = 6379;
+
+/// Maximum size of database protocol message accepted by database checks (bytes)
+pub const CHECK_DATABASE_MAX_MESSAGE_SIZE: usize = 16777215;
+
+/// System resolver configuration, source of default DNS resolvers
+pub const CHECK_RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Curl error code of public key pinning failure (not exposed by Curl crate)
+pub const CURLE_SSL_PINNEDPUBKEYNOTMATCH: u32 = 90;
+
 /// Default page content expectation:
 pub const CHECK_DEFAULT_CONTENT_EXPECTATION: &str = "body";
 
@@ -63,8 +142,11 @@ pub const TESTS_DIR: &str = "tests";
 /// Default Web proto:
 pub const CHECK_DEFAULT_PROTOCOL: &str = "https://";
 
+/// History directory, where check stories and page digests are stored
+pub const HISTORY_DIR: &str = "/tmp";
+
 /// Stories required for the validation
 pub const STORIES_TO_VALIDATE_COUNT: usize = 4;
 
-/// Amount of stories to keep in /tmp/
+/// Amount of stories to keep in HISTORY_DIR
 pub const STORIES_TO_KEEP_COUNT: usize = 60 * 12; // keep 720 stories
@@ -19,20 +19,14 @@
     overflowing_literals,
     path_statements,
     patterns_in_fns_without_body,
-    private_in_public,
     unconditional_recursion,
     unused,
     unused_allocation,
     unused_comparisons,
     unused_parens,
     while_true,
-    missing_debug_implementations,
     missing_docs,
-    trivial_casts,
-    trivial_numeric_casts,
-    unused_extern_crates,
-    unused_import_braces,
-    unused_qualifications
+    unused_extern_crates
 )]
 #![warn(dead_code, unused_imports, unused_variables)]
 
@@ -49,8 +43,16 @@ static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
 pub use crate::actors::notificator::*;
 pub use crate::api::*;
+pub use crate::checks::auth::*;
 pub use crate::checks::check::*;
+pub use crate::checks::database::*;
+pub use crate::checks::dns::*;
+pub use crate::checks::email_domain::*;
 pub use crate::checks::page::*;
+pub use crate::checks::registration::*;
+pub use crate::checks::ssh::*;
+pub use crate::checks::tcp::*;
+pub use crate::checks::tls::*;
 pub use crate::checks::pongo::*;
 pub use crate::config::*;
 pub use crate::configuration::*;
@@ -67,8 +69,10 @@ pub use thiserror::Error;
 pub use tracing::{debug, error, event, info, instrument, span, trace, warn, Level};
 
 
-/// Collects async content from Curl:
-pub struct Collector(Vec<u8>);
+/// Collects async content (body, status line and headers of the final response) from Curl.
+/// Status lines and Location headers of all responses are kept to trace redirects:
+#[derive(Default)]
+pub struct Collector(Vec<u8>, Vec<String>, Vec<(String, Option<String>)>);
 
 
 impl Handler for Collector {
@@ -76,23 +80,44 @@ impl Handler for Collector {
         self.0.extend_from_slice(data);
         Ok(data.len())
     }
+
+
+    fn header(&mut self, data: &[u8]) -> bool {
+        let line = String::from_utf8_lossy(data).trim_end().to_string();
+        if line.starts_with("HTTP/") {
+            // new response (after redirect or 100-continue), drop previous headers
+            self.1.clear();
+            self.1.push(line.to_string());
+            self.2.push((line, None));
+        } else if !line.is_empty() {
+            if let Some((name, location)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("location") {
+                    if let Some(response) = self.2.last_mut() {
+                        response.1 = Some(location.trim().to_string());
+                    }
+                }
+            }
+            self.1.push(line);
+        }
+        true
+    }
 }
 
 
 impl Debug for Collector {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct(&format!(
-            "Collector buffer (first 50 in hex): {}…",
-            self.to_string()
+            "Collector buffer (first 50 in hex): {self}…"
         ))
         .finish()
     }
 }
 
 
-impl ToString for Collector {
-    fn to_string(&self) -> String {
-        self.0.iter().take(50).map(|c| format!("{:x}", c)).collect()
+impl fmt::Display for Collector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let hex: String = self.0.iter().take(50).map(|c| format!("{:x}", c)).collect();
+        write!(f, "{hex}")
     }
 }
 
@@ -129,3 +154,7 @@ pub mod actors;
 /// Tests:
 #[cfg(test)]
 mod tests;
+
+/// Local servers and helpers shared by tests:
+#[cfg(test)]
+mod test_fixtures;
@@ -1,4 +1,9 @@
 use crate::*;
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
 
 
 #[derive(
@@ -10,18 +15,58 @@ pub enum PageExpectation {
     #[error("ValidCode: {0}.")]
     ValidCode(u32),
 
+    /// Valid error code range (inclusive)
+    #[error("ValidCodeRange: {0}-{1}.")]
+    ValidCodeRange(u32, u32),
+
+    /// Valid error code from given set
+    #[error("ValidCodeIn: {0:?}.")]
+    ValidCodeIn(Vec<u32>),
+
+    /// Valid error code class, ex. "2xx" or "3xx"
+    #[error("ValidCodeClass: {0}.")]
+    ValidCodeClass(String),
+
     /// Valid no-content check
     #[error("ValidNoContent.")]
     ValidNoContent,
 
-    /// Valid content regex match
+    /// Valid content literal match
     #[error("ValidContent: {0}.")]
     ValidContent(String),
 
+    /// Valid content regex match
+    #[error("ValidContentRegex: {0}.")]
+    ValidContentRegex(String),
+
+    /// Valid content case-insensitive literal match
+    #[error("ValidContentCaseInsensitive: {0}.")]
+    ValidContentCaseInsensitive(String),
+
+    /// Valid content literal match, occurring at least given amount of times
+    #[error("ValidContentOccurrences: {0}, at least {1} times.")]
+    ValidContentOccurrences(String, usize),
+
+    /// Invalid content literal - check fails if it's found in content
+    #[error("InvalidContent: {0}.")]
+    InvalidContent(String),
+
     /// Valid content length
     #[error("ValidLength: {0} bytes.")]
     ValidLength(usize),
 
+    /// Valid maximum content length
+    #[error("ValidMaxLength: {0} bytes.")]
+    ValidMaxLength(usize),
+
+    /// Valid content length range (inclusive)
+    #[error("ValidLengthRange: {0}-{1} bytes.")]
+    ValidLengthRange(usize, usize),
+
+    /// Valid exact content length
+    #[error("ValidExactLength: {0} bytes.")]
+    ValidExactLength(usize),
+
     /// Valid no-content-length check
     #[error("ValidNoLength.")]
     ValidNoLength,
@@ -33,6 +78,151 @@ pub enum PageExpectation {
     /// Valid no-address check
     #[error("ValidNoAddress.")]
     ValidNoAddress,
+
+    /// Valid SHA256 checksum of the response body
+    #[error("ValidChecksum: {0}.")]
+    ValidChecksum(String),
+
+    /// Detect content changes since last check. Matches of given regexes
+    /// (timestamps, nonces…) are stripped from the content before digest is computed
+    #[error("DetectChange: {0:?}.")]
+    DetectChange(Vec<String>),
+
+    /// Valid response header (name, value regex)
+    #[error("ValidHeader: {0}: {1}.")]
+    ValidHeader(String, String),
+
+    /// Invalid response header - check fails if header is present in response
+    #[error("InvalidHeader: {0}.")]
+    InvalidHeader(String),
+
+    /// Valid JSON body value selected by JSONPath or JSON-pointer
+    #[error("ValidJson: {0}.")]
+    ValidJson(JsonExpectation),
+
+    /// Valid maximum amount of redirects
+    #[error("ValidRedirectCount: at most {0}.")]
+    ValidRedirectCount(usize),
+
+    /// Valid redirect chain: every requested URL with HTTP code it responded with
+    #[error("ValidRedirectChain: {0:?}.")]
+    ValidRedirectChain(Vec<RedirectHop>),
+
+    /// Plain HTTP URL has to be permanently (301) redirected to HTTPS,
+    /// never getting back to plain HTTP
+    #[error("RequireHttpsRedirect.")]
+    RequireHttpsRedirect,
+
+    /// Valid negotiated HTTP version, ex. "1.1" or "2"
+    #[error("ValidHttpVersion: {0}.")]
+    ValidHttpVersion(String),
+
+    /// Valid response times (limits in milliseconds)
+    #[error("ValidResponseTime: {0}.")]
+    ValidResponseTime(ResponseTimeLimits),
+}
+
+
+#[derive(
+    Debug, Copy, Clone, Serialize, Deserialize, Default, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+/// Response time limits in milliseconds. Each limit is optional
+pub struct ResponseTimeLimits {
+    /// DNS lookup time limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns: Option<u64>,
+
+    /// TCP connect time limit (after DNS lookup)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect: Option<u64>,
+
+    /// TLS handshake time limit (after TCP connect)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<u64>,
+
+    /// Time to first byte limit (since start of the request)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttfb: Option<u64>,
+
+    /// Total time limit (since start of the request)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+
+/// Implement JSON serialization on .to_string():
+impl fmt::Display for ResponseTimeLimits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(&self).unwrap_or_else(|_| {
+            String::from("{\"status\": \"ResponseTimeLimits serialization failure\"}")
+        });
+        write!(f, "{json}")
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+/// JSON body assertion. When no assertion is given, the value is expected to exist
+pub struct JsonExpectation {
+    /// JSONPath ("$.data.status") or JSON-pointer ("/data/status") expression
+    pub path: String,
+
+    /// Selected value has to be equal to given JSON value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+
+    /// Selected value has to match given regex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+
+    /// Selected value has to exist (or not exist if false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+}
+
+
+impl JsonExpectation {
+    /// Comparable representation, since JSON Value is neither Hash nor Ord
+    fn sort_key(&self) -> (&str, Option<String>, Option<&String>, Option<bool>) {
+        (
+            &self.path,
+            self.equals.as_ref().map(|value| value.to_string()),
+            self.matches.as_ref(),
+            self.exists,
+        )
+    }
+}
+
+
+impl Hash for JsonExpectation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sort_key().hash(state)
+    }
+}
+
+
+impl PartialOrd for JsonExpectation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+
+impl Ord for JsonExpectation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+
+/// Implement JSON serialization on .to_string():
+impl fmt::Display for JsonExpectation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(&self).unwrap_or_else(|_| {
+            String::from("{\"status\": \"JsonExpectation serialization failure\"}")
+        });
+        write!(f, "{json}")
+    }
 }
 
 
@@ -40,14 +230,60 @@ pub enum PageExpectation {
 pub type PageExpectations = Vec<PageExpectation>;
 
 
-#[derive(
-    Debug, Copy, Clone, Serialize, Deserialize, Error, PartialEq, Eq, PartialOrd, Ord,
-)]
+#[derive(Debug, Clone, Serialize, Deserialize, Error, PartialEq, Eq, PartialOrd, Ord)]
 /// Describes all supported domain expectations
 pub enum DomainExpectation {
     /// Domain expiry minimum period in days
     #[error("ValidExpiryPeriod: {0} days.")]
     ValidExpiryPeriod(i32),
+
+    /// Valid certificate issuer, ex. "Let's Encrypt" (case-insensitive match)
+    #[error("ValidIssuer: {0}.")]
+    ValidIssuer(String),
+
+    /// Certificate Subject Alternative Names cover the domain name
+    #[error("ValidSubjectAltName.")]
+    ValidSubjectAltName,
+
+    /// Certificate chain sent by the server is complete and trusted
+    #[error("ValidChain.")]
+    ValidChain,
+
+    /// Valid certificate public key algorithm: "RSA", "EC", "ED25519"…
+    #[error("ValidKeyAlgorithm: {0}.")]
+    ValidKeyAlgorithm(String),
+
+    /// Certificate public key minimum size in bits
+    #[error("ValidMinimumKeySize: {0} bits.")]
+    ValidMinimumKeySize(u32),
+
+    /// Valid certificate signature algorithm, ex. "sha256" (case-insensitive match)
+    #[error("ValidSignatureAlgorithm: {0}.")]
+    ValidSignatureAlgorithm(String),
+
+    /// TLS version which must not be accepted by the server, ex. "1.0"
+    #[error("ForbidTlsVersion: {0}.")]
+    ForbidTlsVersion(TlsVersion),
+
+    /// TLS version which must be accepted by the server, ex. "1.3"
+    #[error("RequireTlsVersion: {0}.")]
+    RequireTlsVersion(TlsVersion),
+
+    /// Server must not accept any weak cipher suite (NULL, anonymous, RC4, DES, 3DES…)
+    #[error("ForbidWeakCiphers.")]
+    ForbidWeakCiphers,
+
+    /// Certificate isn't revoked, according to its OCSP responder
+    #[error("NotRevoked.")]
+    NotRevoked,
+
+    /// Server staples fresh OCSP response, with certificate not revoked
+    #[error("RequireOcspStapling.")]
+    RequireOcspStapling,
+
+    /// Domain registration expiry minimum period in days (RDAP, WHOIS)
+    #[error("ValidRegistrationPeriod: {0} days.")]
+    ValidRegistrationPeriod(i32),
 }
 
 
@@ -55,6 +291,83 @@ pub enum DomainExpectation {
 pub type DomainExpectations = Vec<DomainExpectation>;
 
 
+#[derive(Debug, Clone, Serialize, Deserialize, Error, PartialEq, Eq, PartialOrd, Ord)]
+/// Describes all supported email domain expectations
+pub enum EmailDomainExpectation {
+    /// Single SPF record with valid syntax, within the DNS lookup limit
+    #[error("ValidSpf.")]
+    ValidSpf,
+
+    /// DMARC record with given (or stricter) policy: "None", "Quarantine", "Reject"
+    #[error("ValidDmarcPolicy: {0}.")]
+    ValidDmarcPolicy(DmarcPolicy),
+
+    /// DKIM selectors publishing public keys, ex. ["google", "mail2024"]
+    #[error("ValidDkimSelectors: {0:?}.")]
+    ValidDkimSelectors(Vec<String>),
+
+    /// Every MX host answers with SMTP greeting
+    #[error("ValidMxBanner.")]
+    ValidMxBanner,
+}
+
+
+/// Email domain expectations type
+pub type EmailDomainExpectations = Vec<EmailDomainExpectation>;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, Error, PartialEq, Eq, PartialOrd, Ord)]
+/// Describes all supported TCP service expectations
+pub enum TcpExpectation {
+    /// Banner (or response to the payload) matches regex, ex. "^SSH-2\\.0-"
+    #[error("ValidBanner: {0}.")]
+    ValidBanner(String),
+
+    /// TCP connect time limit in milliseconds
+    #[error("ValidConnectTime: {0}ms.")]
+    ValidConnectTime(u64),
+}
+
+
+/// TCP service expectations type
+pub type TcpExpectations = Vec<TcpExpectation>;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, Error, PartialEq, Eq, PartialOrd, Ord)]
+/// Describes all supported SSH server expectations
+pub enum SshExpectation {
+    /// Protocol banner matches regex, ex. "^SSH-2\\.0-OpenSSH_9"
+    #[error("ValidBanner: {0}.")]
+    ValidBanner(String),
+
+    /// Host key fingerprint in OpenSSH format (as shown by "ssh-keygen -l"), ex. "SHA256:…"
+    #[error("ValidHostKey: {0}.")]
+    ValidHostKey(String),
+}
+
+
+/// SSH server expectations type
+pub type SshExpectations = Vec<SshExpectation>;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, Error, PartialEq, Eq, PartialOrd, Ord)]
+/// Describes all supported database expectations (liveness is checked by default)
+pub enum DatabaseExpectation {
+    /// Replication role (Redis): "master" or "replica" ("slave" is accepted too)
+    #[error("ValidReplicationRole: {0}.")]
+    ValidReplicationRole(String),
+
+    /// Replication lag limit in seconds (Redis). Checked on master for all connected
+    /// replicas, and on replica for time since last interaction with master
+    #[error("ValidReplicationLag: {0}s.")]
+    ValidReplicationLag(u64),
+}
+
+
+/// Database expectations type
+pub type DatabaseExpectations = Vec<DatabaseExpectation>;
+
+
 #[derive(Debug, Clone, Serialize, Deserialize, Error, PartialEq, Eq, Hash)]
 /// All response types for all supported expectations
 pub enum Expected {
@@ -70,6 +383,66 @@ pub enum Expected {
     #[error("URL: \"{0}\" contains expected literal: \"{1}\".")]
     Content(String, String),
 
+    /// Check returned page contents matching expected regex
+    #[error("URL: \"{0}\" matches expected regex: \"{1}\" with: \"{2}\".")]
+    ContentRegex(String, String, String),
+
+    /// Check returned expected page contents (case-insensitive)
+    #[error("URL: \"{0}\" contains expected case-insensitive literal: \"{1}\".")]
+    ContentCaseInsensitive(String, String),
+
+    /// Check returned expected page contents expected amount of times
+    #[error("URL: \"{0}\" contains expected literal: \"{1}\" {2} times.")]
+    ContentOccurrences(String, String, usize),
+
+    /// Check returned page contents without invalid literal
+    #[error("URL: \"{0}\" doesn't contain invalid literal: \"{1}\".")]
+    ContentAbsent(String, String),
+
+    /// Check returned body with expected checksum
+    #[error("URL: \"{0}\" has expected SHA256 checksum: {1}.")]
+    Checksum(String, String),
+
+    /// Check returned content unchanged since last check (or first digest stored)
+    #[error("URL: \"{0}\" content is unchanged. Digest: {1}.")]
+    ContentUnchanged(String, String),
+
+    /// Check returned expected response header
+    #[error("URL: \"{0}\" returns expected header: \"{1}: {2}\".")]
+    Header(String, String, String),
+
+    /// Check returned no invalid response header
+    #[error("URL: \"{0}\" doesn't return invalid header: \"{1}\".")]
+    HeaderAbsent(String, String),
+
+    /// Check returned expected JSON value (url, path, value)
+    #[error("URL: \"{0}\" returns expected JSON value: {2} under path: \"{1}\".")]
+    Json(String, String, String),
+
+    /// Check returned no JSON value under path, as expected (url, path)
+    #[error("URL: \"{0}\" returns no JSON value under path: \"{1}\", as expected.")]
+    JsonAbsent(String, String),
+
+    /// Check redirected at most expected amount of times (url, redirects, max)
+    #[error("URL: \"{0}\" was redirected: {1} times. Expected at most: {2}.")]
+    RedirectCount(String, usize, usize),
+
+    /// Check followed expected redirect chain (url, chain)
+    #[error("URL: \"{0}\" followed expected redirect chain: {1}.")]
+    RedirectChain(String, String),
+
+    /// Check was permanently redirected to HTTPS (url, chain)
+    #[error("URL: \"{0}\" is permanently redirected to HTTPS: {1}.")]
+    HttpsRedirect(String, String),
+
+    /// Check negotiated expected HTTP version (url, version)
+    #[error("URL: \"{0}\" negotiated expected HTTP version: {1}.")]
+    HttpVersion(String, String),
+
+    /// Check responded within expected time limits
+    #[error("URL: \"{0}\" responded within expected time limits. Total time: {1}ms.")]
+    ResponseTime(String, u64),
+
     /// NoContentLength
     #[error("URL: \"{0}\" no content-length validation.")]
     NoContentLength(String),
@@ -82,6 +455,116 @@ pub enum Expected {
     #[error("URL: \"{0}\" has minimum content-length at least: {1} bytes long.")]
     ContentLength(String, usize),
 
+    /// Check returned page content not longer than expected
+    #[error("URL: \"{0}\" has content-length at most: {1} bytes long.")]
+    ContentLengthMax(String, usize),
+
+    /// Check returned page content length within expected range
+    #[error("URL: \"{0}\" has content-length between: {1} and {2} bytes.")]
+    ContentLengthRange(String, usize, usize),
+
+    /// Check returned page content of exact expected length
+    #[error("URL: \"{0}\" has content-length of exactly: {1} bytes.")]
+    ContentLengthExact(String, usize),
+
+    /// Check TLS certificate issued by expected issuer (domain, issuer)
+    #[error("TLS certificate for domain: \"{0}\", is issued by expected issuer: \"{1}\".")]
+    TLSCertificateIssuer(String, String),
+
+    /// Check TLS certificate names cover the domain
+    #[error("TLS certificate for domain: \"{0}\", covers the domain name.")]
+    TLSCertificateNameCovered(String),
+
+    /// Check TLS certificate chain is complete (domain, chain length)
+    #[error("TLS certificate for domain: \"{0}\", has complete chain of: {1} certificates.")]
+    TLSCertificateChainComplete(String, usize),
+
+    /// Check TLS certificate key is expected (domain, algorithm, bits)
+    #[error("TLS certificate for domain: \"{0}\", has expected key: {1} {2} bits.")]
+    TLSCertificateKey(String, String, u32),
+
+    /// Check TLS certificate signature algorithm is expected (domain, algorithm)
+    #[error("TLS certificate for domain: \"{0}\", has expected signature algorithm: {1}.")]
+    TLSCertificateSignature(String, String),
+
+    /// Check server refuses forbidden TLS version (domain, version)
+    #[error("TLS server for domain: \"{0}\", refuses forbidden TLS version: {1}.")]
+    TLSVersionRefused(String, String),
+
+    /// Check server accepts required TLS version (domain, version)
+    #[error("TLS server for domain: \"{0}\", accepts required TLS version: {1}.")]
+    TLSVersionAccepted(String, String),
+
+    /// Check server refuses all weak cipher suites
+    #[error("TLS server for domain: \"{0}\", refuses all weak cipher suites.")]
+    TLSWeakCiphersRefused(String),
+
+    /// Check TLS certificate isn't revoked (domain, OCSP responder)
+    #[error("TLS certificate for domain: \"{0}\", isn't revoked according to: {1}.")]
+    TLSCertificateNotRevoked(String, String),
+
+    /// Check TLS server staples fresh OCSP response
+    #[error("TLS server for domain: \"{0}\", staples fresh OCSP response.")]
+    TLSOcspStapled(String),
+
+    /// Check domain registration expiration time
+    #[error(
+        "Registration of domain: \"{0}\", will be valid for: {1} more days. Requested minimum: {2} days."
+    )]
+    DomainRegistrationFresh(String, i32, i32),
+
+    /// Check DNS record has expected answers (record, server, answers)
+    #[error("DNS record: \"{0}\" at: {1} has expected answers: [{2}].")]
+    DnsRecordValid(String, String, String),
+
+    /// Check email domain has valid SPF record (domain, DNS lookups)
+    #[error("Email domain: \"{0}\" has valid SPF record, with {1} DNS lookups.")]
+    SpfValid(String, usize),
+
+    /// Check email domain has DMARC policy (domain, policy)
+    #[error("Email domain: \"{0}\" has DMARC policy: {1}.")]
+    DmarcPolicyValid(String, String),
+
+    /// Check email domain publishes DKIM key (domain, selector)
+    #[error("Email domain: \"{0}\" publishes DKIM key for selector: {1}.")]
+    DkimKeyPublished(String, String),
+
+    /// Check TCP service accepts connections (address, connect time)
+    #[error("TCP service: \"{0}\" accepts connections. Connect time: {1}ms.")]
+    TcpConnected(String, u64),
+
+    /// Check TCP service responds with banner matching regex (address, regex, match)
+    #[error("TCP service: \"{0}\" matches expected regex: \"{1}\" with: \"{2}\".")]
+    TcpBanner(String, String, String),
+
+    /// Check TCP service connect time (address, connect time, limit)
+    #[error("TCP service: \"{0}\" connect time: {1}ms is within limit: {2}ms.")]
+    TcpConnectTime(String, u64, u64),
+
+    /// Check SSH server banner matches regex (address, regex, banner)
+    #[error("SSH server: \"{0}\" banner matches expected regex: \"{1}\" with: \"{2}\".")]
+    SshBanner(String, String, String),
+
+    /// Check SSH server presents (expected) host key (address, algorithm, fingerprint)
+    #[error("SSH server: \"{0}\" presents {1} host key: {2}.")]
+    SshHostKey(String, String, String),
+
+    /// Check database server is alive (database)
+    #[error("Database: \"{0}\" is alive.")]
+    DatabaseAlive(String),
+
+    /// Check database replication role (database, role)
+    #[error("Database: \"{0}\" has replication role: {1}.")]
+    ReplicationRole(String, String),
+
+    /// Check database replication lag (database, lag, limit)
+    #[error("Database: \"{0}\" replication lag: {1}s is within limit: {2}s.")]
+    ReplicationLag(String, u64, u64),
+
+    /// Check MX host answers with SMTP greeting (domain, MX host)
+    #[error("Email domain: \"{0}\" MX host: {1} answers with SMTP greeting.")]
+    MxBannerValid(String, String),
+
     /// Check TLS certificate expiration time
     #[error(
         "TLS certificate for domain: \"{0}\", will be valid for: {1} more days. Requested minimum: {2} days."
@@ -30,6 +30,67 @@ pub struct Story {
     /// Notifier to use if notification action is necessary
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notifier: Option<String>,
+
+    /// Story - request timings measured for page checks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<PageTimings>,
+
+    /// Story - effective remote address (IP:port) of page checks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_address: Option<String>,
+
+    /// Story - redirect chain of page checks (when page was redirected)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_chain: Option<Vec<RedirectHop>>,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Single hop of redirect chain: requested URL and HTTP code it responded with
+pub struct RedirectHop {
+    /// Requested URL
+    pub url: String,
+
+    /// HTTP code of the response
+    pub code: u32,
+}
+
+
+/// Implement human readable hop description, ex. "301 http://example.com/"
+impl fmt::Display for RedirectHop {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.url)
+    }
+}
+
+
+/// Human readable redirect chain description, ex. "301 http://a/ -> 200 https://a/"
+pub fn redirect_chain_description(chain: &[RedirectHop]) -> String {
+    chain
+        .iter()
+        .map(|hop| hop.to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Default, PartialEq, Eq, Hash)]
+/// Request timings measured by Curl (in milliseconds)
+pub struct PageTimings {
+    /// DNS lookup time
+    pub dns: u64,
+
+    /// TCP connect time (after DNS lookup)
+    pub connect: u64,
+
+    /// TLS handshake time (after TCP connect), 0 for plain HTTP
+    pub tls: u64,
+
+    /// Time to first byte (since start of the request)
+    pub ttfb: u64,
+
+    /// Total time (since start of the request)
+    pub total: u64,
 }
 
 
@@ -43,6 +104,9 @@ impl Story {
             minor: None,
             error: None,
             notifier,
+            timings: None,
+            remote_address: None,
+            redirect_chain: None,
         }
     }
 
@@ -56,6 +120,9 @@ impl Story {
             minor: None,
             error: Some(error),
             notifier,
+            timings: None,
+            remote_address: None,
+            redirect_chain: None,
         }
     }
 
@@ -69,15 +136,50 @@ impl Story {
             minor: Some(minor),
             error: None,
             notifier: None,
+            timings: None,
+            remote_address: None,
+            redirect_chain: None,
+        }
+    }
+
+
+    /// Attach request timings to the Story
+    #[instrument]
+    pub fn with_timings(self, timings: Option<PageTimings>) -> Story {
+        Story { timings, ..self }
+    }
+
+
+    /// Attach redirect chain to the Story. Chains without redirects are skipped
+    #[instrument]
+    pub fn with_redirect_chain(self, redirect_chain: &[RedirectHop]) -> Story {
+        Story {
+            redirect_chain: if redirect_chain.len() > 1 {
+                Some(redirect_chain.to_vec())
+            } else {
+                None
+            },
+            ..self
+        }
+    }
+
+
+    /// Attach effective remote address (IP:port) to the Story
+    #[instrument]
+    pub fn with_remote_address(self, remote_address: Option<String>) -> Story {
+        Story {
+            remote_address,
+            ..self
         }
     }
 }
 
 
 /// Implement JSON serialization on .to_string():
-impl ToString for Story {
-    fn to_string(&self) -> String {
-        serde_json::to_string(&self)
-            .unwrap_or_else(|_| String::from("{\"status\": \"Story serialization failure\"}"))
+impl fmt::Display for Story {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(&self)
+            .unwrap_or_else(|_| String::from("{\"status\": \"Story serialization failure\"}"));
+        write!(f, "{json}")
     }
 }
@@ -28,6 +28,173 @@ pub enum Unexpected {
     #[error("Expired SSL certificate for domain: \"{0}\", which is valid only for: {1} days")]
     TLSDomainExpired(String, i32),
 
+    /// Certificate issued by unexpected issuer (domain, issuer, expected issuer)
+    #[error(
+        "TLS certificate for domain: \"{0}\" is issued by: \"{1}\", but expected: \"{2}\""
+    )]
+    TLSIssuerMismatch(String, String, String),
+
+    /// Certificate names don't cover the domain (domain, certificate names)
+    #[error("TLS certificate for domain: \"{0}\" is issued for other names: {1}")]
+    TLSNameNotCovered(String, String),
+
+    /// Certificate chain is incomplete or untrusted (domain, details)
+    #[error("TLS certificate chain for domain: \"{0}\" can't be verified. Details: {1}")]
+    TLSChainIncomplete(String, String),
+
+    /// Certificate key algorithm differs from expected one (domain, algorithm, expected)
+    #[error("TLS certificate for domain: \"{0}\" has key algorithm: {1}, but expected: {2}")]
+    TLSKeyAlgorithmMismatch(String, String, String),
+
+    /// Certificate key is too small (domain, bits, minimum bits)
+    #[error(
+        "TLS certificate for domain: \"{0}\" has key of: {1} bits, but expected at least: {2}"
+    )]
+    TLSKeyTooSmall(String, u32, u32),
+
+    /// Certificate signature algorithm differs from expected (domain, algorithm, expected)
+    #[error("TLS certificate for domain: \"{0}\" is signed with: {1}, but expected: {2}")]
+    TLSSignatureAlgorithmMismatch(String, String, String),
+
+    /// Server accepts forbidden TLS version (domain, version)
+    #[error("TLS server for domain: \"{0}\" accepts forbidden TLS version: {1}")]
+    TLSVersionForbidden(String, String),
+
+    /// Server refuses required TLS version (domain, version)
+    #[error("TLS server for domain: \"{0}\" doesn't accept required TLS version: {1}")]
+    TLSVersionMissing(String, String),
+
+    /// Server accepts weak cipher suites (domain, cipher suites)
+    #[error("TLS server for domain: \"{0}\" accepts weak cipher suites: {1}")]
+    TLSWeakCiphersAccepted(String, String),
+
+    /// Certificate is revoked (domain, revocation time)
+    #[error("TLS certificate for domain: \"{0}\" is revoked since: {1}")]
+    TLSCertificateRevoked(String, String),
+
+    /// Server doesn't staple valid OCSP response (domain, details)
+    #[error("TLS server for domain: \"{0}\" doesn't staple valid OCSP response. Details: {1}")]
+    TLSOcspStaplingMissing(String, String),
+
+    /// Domain registration expires soon (domain, days)
+    #[error("Registration of domain: \"{0}\" expires, it's valid only for: {1} days")]
+    DomainRegistrationExpiring(String, i32),
+
+    /// Domain registration expiry can't be read from RDAP, nor WHOIS (domain, details)
+    #[error("Registration of domain: \"{0}\" lookup failed! Details: {1}")]
+    DomainRegistrationLookupFailed(String, String),
+
+    /// DNS answers differ from expected ones (record, server, expected, actual answers)
+    #[error(
+        "DNS record: \"{0}\" at: {1} has answers: [{3}], but expected: [{2}]"
+    )]
+    DnsRecordMismatch(String, String, String, String),
+
+    /// DNS servers can't be queried for the record (record, details)
+    #[error("DNS record: \"{0}\" query failed! Details: {1}")]
+    DnsQueryFailed(String, String),
+
+    /// TCP service doesn't accept connections (address, details)
+    #[error("TCP service: \"{0}\" is unreachable! Details: {1}")]
+    TcpConnectionFailed(String, String),
+
+    /// TCP service response doesn't match expected regex (address, regex)
+    #[error("TCP service: \"{0}\" response doesn't match expected regex: \"{1}\"")]
+    TcpBannerMismatch(String, String),
+
+    /// TCP connect time limit exceeded (address, connect time, limit)
+    #[error(
+        "TCP service: \"{0}\" is too slow! Connect time: {1}ms. Expected at most: {2}ms"
+    )]
+    TcpConnectTimeExceeded(String, u64, u64),
+
+    /// SSH key exchange failed (address, details)
+    #[error("SSH server: \"{0}\" key exchange failed! Details: {1}")]
+    SshHandshakeFailed(String, String),
+
+    /// SSH server banner doesn't match expected regex (address, regex, banner)
+    #[error("SSH server: \"{0}\" banner: \"{2}\" doesn't match expected regex: \"{1}\"")]
+    SshBannerMismatch(String, String, String),
+
+    /// SSH host key changed (address, algorithm, fingerprint, expected fingerprint)
+    #[error(
+        "SSH server: \"{0}\" host key has changed! Presents {1} host key: {2}, expected: {3}"
+    )]
+    SshHostKeyMismatch(String, String, String, String),
+
+    /// Database server is unreachable, refuses authentication, or fails the query (database,
+    /// details)
+    #[error("Database: \"{0}\" is unavailable! Details: {1}")]
+    DatabaseUnavailable(String, String),
+
+    /// Database replication role differs from expected one (database, role, expected role)
+    #[error("Database: \"{0}\" has replication role: {1}, but expected: {2}")]
+    ReplicationRoleMismatch(String, String, String),
+
+    /// Database replication doesn't work (database, details)
+    #[error("Database: \"{0}\" replication is broken! Details: {1}")]
+    ReplicationBroken(String, String),
+
+    /// Database replication lag limit exceeded (database, lag, limit)
+    #[error("Database: \"{0}\" replication lag: {1}s exceeds limit: {2}s")]
+    ReplicationLagExceeded(String, u64, u64),
+
+    /// SPF record is missing or invalid (domain, reason)
+    #[error("Email domain: \"{0}\" has invalid SPF record. Details: {1}")]
+    SpfInvalid(String, String),
+
+    /// SPF record needs too many DNS lookups (domain, lookups, maximum)
+    #[error(
+        "Email domain: \"{0}\" SPF record needs: {1} DNS lookups, but maximum is: {2}"
+    )]
+    SpfTooManyLookups(String, usize, usize),
+
+    /// DMARC record is missing or has no valid policy (domain)
+    #[error("Email domain: \"{0}\" has no valid DMARC policy!")]
+    DmarcPolicyMissing(String),
+
+    /// DMARC policy is weaker than required (domain, policy, required policy)
+    #[error("Email domain: \"{0}\" has DMARC policy: {1}, but required: {2}")]
+    DmarcPolicyTooWeak(String, String, String),
+
+    /// DKIM selector publishes no key (domain, selector)
+    #[error("Email domain: \"{0}\" publishes no DKIM key for selector: {1}")]
+    DkimKeyMissing(String, String),
+
+    /// MX host doesn't answer with SMTP greeting (domain, MX host, details)
+    #[error(
+        "Email domain: \"{0}\" MX host: {1} doesn't answer with SMTP greeting. Details: {2}"
+    )]
+    MxBannerMissing(String, String, String),
+
+    /// Email domain has no MX host to check (domain)
+    #[error("Email domain: \"{0}\" has no MX host to check SMTP greeting of!")]
+    MxHostsMissing(String),
+
+    /// DNS lookup of email domain records failed (domain with looked up records, details)
+    #[error("Email domain: \"{0}\" lookup failed! Details: {1}")]
+    EmailDomainLookupFailed(String, String),
+
+    /// Client certificate or key couldn't be used (url, details)
+    #[error("URL: \"{0}\" client TLS certificate or key can't be used. Details: {1}")]
+    TLSClientCertificateInvalid(String, String),
+
+    /// CA bundle couldn't be loaded (url, details)
+    #[error("URL: \"{0}\" CA bundle can't be loaded. Details: {1}")]
+    TLSCaBundleInvalid(String, String),
+
+    /// Server certificate failed verification (url, details)
+    #[error("URL: \"{0}\" TLS peer certificate verification failed. Details: {1}")]
+    TLSPeerVerificationFailed(String, String),
+
+    /// Server public key doesn't match the pinned one (url, details)
+    #[error("URL: \"{0}\" TLS public key doesn't match the pinned public key. Details: {1}")]
+    TLSPinnedKeyMismatch(String, String),
+
+    /// TLS handshake failed, ex. client certificate was rejected by server (url, details)
+    #[error("URL: \"{0}\" TLS handshake failed. Details: {1}")]
+    TLSHandshakeFailed(String, String),
+
     /// Failed to get expected Address
     #[error("URL: \"{0}\" has invalid final address: \"{1}\". Expected: \"{2}\"")]
     AddressInvalid(String, String, String),
@@ -44,16 +211,140 @@ pub enum Unexpected {
     #[error("URL: \"{0}\" returned error: {1}. Expected code: {2}")]
     HttpCodeInvalid(String, u32, u32),
 
+    /// HttpCode (url, code, expected codes description)
+    #[error("URL: \"{0}\" returned error: {1}. Expected codes: {2}")]
+    HttpCodeNotAccepted(String, u32, String),
+
+    /// HttpCode class (url, class)
+    #[error(
+        "URL: \"{0}\" has malformed expected code class: \"{1}\". Expected: \"1xx\" - \"5xx\""
+    )]
+    HttpCodeClassMalformed(String, String),
+
+    /// Too many redirects (url, redirects, max, chain)
+    #[error("URL: \"{0}\" was redirected: {1} times, but expected at most: {2}. Chain: {3}")]
+    RedirectCountExceeded(String, usize, usize, String),
+
+    /// Redirect chain differs from expected one (url, chain, expected chain)
+    #[error("URL: \"{0}\" followed redirect chain: {1}, but expected: {2}")]
+    RedirectChainMismatch(String, String, String),
+
+    /// Redirect loop detected - maximum amount of redirects reached (url, chain)
+    #[error("URL: \"{0}\" is a redirect loop. Chain: {1}")]
+    RedirectLoop(String, String),
+
+    /// Plain HTTP isn't permanently redirected to HTTPS (url, details)
+    #[error("URL: \"{0}\" isn't permanently redirected to HTTPS. Details: {1}")]
+    HttpsRedirectMissing(String, String),
+
+    /// Negotiated HTTP version differs from expected one (url, negotiated, expected)
+    #[error("URL: \"{0}\" negotiated HTTP version: {1}, but expected: {2}")]
+    HttpVersionMismatch(String, String, String),
+
+    /// Request body file couldn't be read (url, details)
+    #[error("URL: \"{0}\" request body is unreadable. Details: {1}")]
+    RequestBodyUnreadable(String, String),
+
+    /// Authentication - credentials couldn't be read or OAuth2 token couldn't be obtained
+    #[error("URL: \"{0}\" authentication failure. Details: {1}")]
+    AuthenticationFailed(String, String),
+
+    /// TLS client key passphrase secret couldn't be read (url, details)
+    #[error("URL: \"{0}\" TLS client key passphrase is unavailable. Details: {1}")]
+    ClientKeyPassphraseUnavailable(String, String),
+
     /// Content - expected content not found where expected
     #[error("URL: \"{0}\" lacks expected content: \"{1}\"")]
     ContentInvalid(String, String),
 
+    /// Content - expected regex doesn't match the content
+    #[error("URL: \"{0}\" doesn't match expected regex: \"{1}\"")]
+    ContentRegexInvalid(String, String),
+
+    /// Regex given in expectation can't be compiled
+    #[error("URL: \"{0}\" has malformed expectation regex: \"{1}\". Details: {2}")]
+    RegexMalformed(String, String, String),
+
+    /// Content - expected content not found where expected (case-insensitive)
+    #[error("URL: \"{0}\" lacks expected case-insensitive content: \"{1}\"")]
+    ContentCaseInsensitiveInvalid(String, String),
+
+    /// Content - expected content found not enough times
+    #[error(
+        "URL: \"{0}\" contains: \"{1}\" only {2} times. Expected at least: {3} occurrences"
+    )]
+    ContentOccurrencesInvalid(String, String, usize, usize),
+
+    /// Content - invalid content found in the page
+    #[error("URL: \"{0}\" contains invalid content: \"{1}\"")]
+    ContentForbidden(String, String),
+
     /// Failed content length check
     #[error(
         "URL: \"{0}\" is unable to pass minimum-content-length expectation! Actual content length: {1}. Expected minimum-length: {2}"
     )]
     ContentLengthInvalid(String, usize, usize),
 
+    /// Failed maximum content length check
+    #[error(
+        "URL: \"{0}\" is unable to pass maximum-content-length expectation! Actual content length: {1}. Expected maximum-length: {2}"
+    )]
+    ContentLengthTooLong(String, usize, usize),
+
+    /// Failed content length range check
+    #[error(
+        "URL: \"{0}\" is unable to pass content-length range expectation! Actual content length: {1}. Expected length between: {2} and {3}"
+    )]
+    ContentLengthOutOfRange(String, usize, usize, usize),
+
+    /// Failed exact content length check
+    #[error(
+        "URL: \"{0}\" is unable to pass exact-content-length expectation! Actual content length: {1}. Expected length: {2}"
+    )]
+    ContentLengthNotExact(String, usize, usize),
+
+    /// Received body size differs from declared Content-Length (url, declared, received)
+    #[error(
+        "URL: \"{0}\" declared Content-Length: {1}, but {2} bytes were received. Truncated transfer?"
+    )]
+    ContentLengthMismatch(String, u64, u64),
+
+    /// Checksum - body checksum differs (url, actual, expected)
+    #[error("URL: \"{0}\" has invalid SHA256 checksum: {1}. Expected: {2}")]
+    ChecksumInvalid(String, String, String),
+
+    /// Content changed since last check (url, previous digest, current digest)
+    #[error(
+        "URL: \"{0}\" content has changed since last check! Previous digest: {1}, current digest: {2}"
+    )]
+    ContentChanged(String, String, String),
+
+    /// Content digest can't be stored for change detection (url, digest file, details)
+    #[error("URL: \"{0}\" content digest can't be stored in: \"{1}\". Details: {2}")]
+    DigestUnwritable(String, String, String),
+
+    /// Header - expected header is missing in response
+    #[error("URL: \"{0}\" lacks expected header: \"{1}\"")]
+    HeaderMissing(String, String),
+
+    /// Header - header value doesn't match expected regex (url, name, value, regex)
+    #[error("URL: \"{0}\" returned header: \"{1}: {2}\". Expected value matching: \"{3}\"")]
+    HeaderInvalid(String, String, String, String),
+
+    /// Header - invalid header found in response (url, name, value)
+    #[error("URL: \"{0}\" returned invalid header: \"{1}: {2}\"")]
+    HeaderForbidden(String, String, String),
+
+    /// JSON - body is not JSON or selected value is unexpected (url, path, details)
+    #[error("URL: \"{0}\" failed JSON assertion for path: \"{1}\". Details: {2}")]
+    JsonAssertionFailed(String, String, String),
+
+    /// Response time limit exceeded (url, phase, measured, limit)
+    #[error(
+        "URL: \"{0}\" is too slow! Measured {1} time: {2}ms. Expected at most: {3}ms"
+    )]
+    ResponseTimeExceeded(String, String, u64, u64),
+
     /// Check file parse error
     #[error("Failed to parse check input data! Error details: \"{0}\"")]
     CheckParseProblem(String),
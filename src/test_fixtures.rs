@@ -0,0 +1,359 @@
+use crate::HISTORY_DIR;
+use hickory_proto::{
+    op::{Message, MessageType, ResponseCode},
+    rr::{Name, RData, Record, RecordType},
+};
+use openssl::{
+    asn1::Asn1Time,
+    bn::{BigNum, MsbOption},
+    hash::MessageDigest,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    ssl::{SslAcceptor, SslMethod, SslVerifyMode},
+    x509::{
+        extension::{BasicConstraints, ExtendedKeyUsage, SubjectAlternativeName},
+        X509NameBuilder, X509,
+    },
+};
+use std::{
+    io::{Read, Write},
+    ops::Deref,
+    net::{TcpListener, TcpStream, UdpSocket},
+    sync::Arc,
+    thread,
+};
+
+
+/// Path of test file written under HISTORY_DIR. File is removed when dropped
+pub(crate) struct FixtureFile(String);
+
+
+impl Deref for FixtureFile {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+
+impl Drop for FixtureFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.0).unwrap_or_default();
+    }
+}
+
+
+/// Writes test file with given contents under HISTORY_DIR
+pub(crate) fn fixture_file(name: &str, contents: &[u8]) -> FixtureFile {
+    let path = format!("{HISTORY_DIR}/krecik-test-{}-{name}", std::process::id());
+    std::fs::write(&path, contents).unwrap();
+    FixtureFile(path)
+}
+
+
+/// Spawns local HTTP server answering every request with a response built by respond()
+pub(crate) fn serve_http<F>(respond: F) -> String
+where
+    F: Fn(&str) -> Vec<u8> + Send + 'static,
+{
+    serve_http_on("127.0.0.1:0", respond)
+}
+
+
+/// Spawns local HTTP server bound to given address. Returns http://address:port/
+pub(crate) fn serve_http_on<F>(bind_address: &str, respond: F) -> String
+where
+    F: Fn(&str) -> Vec<u8> + Send + 'static,
+{
+    let listener = TcpListener::bind(bind_address).unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let request = read_request(&mut stream);
+            let response = respond(&request);
+            stream.write_all(&response).unwrap_or_default();
+        }
+    });
+    format!("http://{address}/")
+}
+
+
+/// Reads raw HTTP request: headers and body (up to Content-Length)
+pub(crate) fn read_request<S: Read>(stream: &mut S) -> String {
+    String::from_utf8_lossy(&read_raw_request(stream)).to_string()
+}
+
+
+/// Reads raw HTTP request bytes: headers and body (up to Content-Length)
+pub(crate) fn read_raw_request<S: Read>(stream: &mut S) -> Vec<u8> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let request_complete = |request: &[u8]| {
+        match request.windows(4).position(|window| window == b"\r\n\r\n") {
+            Some(head_length) => {
+                let content_length = String::from_utf8_lossy(&request[..head_length])
+                    .lines()
+                    .filter_map(|line| {
+                        line.split_once(':')
+                            .map(|(name, value)| (name.to_string(), value.to_string()))
+                    })
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                    .unwrap_or_default();
+                request.len() - head_length - 4 >= content_length
+            }
+            None => false,
+        }
+    };
+    while !request_complete(&request) {
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+    request
+}
+
+
+/// Builds raw HTTP/1.1 response with given code, extra headers and body
+pub(crate) fn http_response(code: u32, headers: &[&str], body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {code} Krecik\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    for header in headers {
+        response.push_str(&format!("{header}\r\n"));
+    }
+    response.push_str("\r\n");
+    [response.as_bytes(), body].concat()
+}
+
+
+/// Issues RSA certificate for localhost and 127.0.0.1, signed by given issuer.
+/// Without issuer, a self-signed CA certificate is issued
+pub(crate) fn issue_certificate(
+    common_name: &str,
+    issuer: Option<(&X509, &PKey<Private>)>,
+) -> (X509, PKey<Private>) {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", common_name).unwrap();
+    let name = name.build();
+    let mut serial = BigNum::new().unwrap();
+    serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&serial.to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(90).unwrap())
+        .unwrap();
+    match issuer {
+        Some((issuer_cert, issuer_key)) => {
+            builder
+                .set_issuer_name(issuer_cert.subject_name())
+                .unwrap();
+            let san = SubjectAlternativeName::new()
+                .dns("localhost")
+                .ip("127.0.0.1")
+                .build(&builder.x509v3_context(Some(issuer_cert), None))
+                .unwrap();
+            builder.append_extension(san).unwrap();
+            builder
+                .append_extension(
+                    ExtendedKeyUsage::new()
+                        .server_auth()
+                        .client_auth()
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap();
+            builder.sign(issuer_key, MessageDigest::sha256()).unwrap();
+        }
+        None => {
+            builder.set_issuer_name(&name).unwrap();
+            builder
+                .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+                .unwrap();
+            builder.sign(&key, MessageDigest::sha256()).unwrap();
+        }
+    }
+    (builder.build(), key)
+}
+
+
+/// Spawns local HTTPS server with given certificate, optionally requiring client
+/// certificates signed by given CA. Returns https://127.0.0.1:port/
+pub(crate) fn serve_https<F>(
+    certificate: &X509,
+    key: &PKey<Private>,
+    client_ca: Option<&X509>,
+    respond: F,
+) -> String
+where
+    F: Fn(&str) -> Vec<u8> + Send + 'static,
+{
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_certificate(certificate).unwrap();
+    acceptor.set_private_key(key).unwrap();
+    if let Some(client_ca) = client_ca {
+        acceptor.cert_store_mut().add_cert(client_ca.clone()).unwrap();
+        acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    }
+    let acceptor = acceptor.build();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Ok(mut stream) = acceptor.accept(stream) {
+                let request = read_request(&mut stream);
+                let response = respond(&request);
+                stream.write_all(&response).unwrap_or_default();
+                stream.shutdown().ok();
+            }
+        }
+    });
+    format!("https://127.0.0.1:{}/", address.port())
+}
+
+
+/// Spawns local server talking plain text protocol with negotiate(), before TLS handshake
+/// with given certificate. Connection is dropped when negotiate() returns false.
+/// Returns port of the server
+pub(crate) fn serve_starttls<F>(certificate: &X509, key: &PKey<Private>, negotiate: F) -> u16
+where
+    F: Fn(&mut TcpStream) -> bool + Send + 'static,
+{
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_certificate(certificate).unwrap();
+    acceptor.set_private_key(key).unwrap();
+    serve_tls_acceptor(acceptor.build(), negotiate)
+}
+
+
+/// Spawns local server talking plain text protocol with negotiate(), before TLS handshake
+/// with given acceptor. Returns port of the server
+pub(crate) fn serve_tls_acceptor<F>(acceptor: SslAcceptor, negotiate: F) -> u16
+where
+    F: Fn(&mut TcpStream) -> bool + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            if negotiate(&mut stream) {
+                if let Ok(mut stream) = acceptor.accept(stream) {
+                    stream.shutdown().ok();
+                }
+            }
+        }
+    });
+    port
+}
+
+
+/// Reads single line sent by the client
+pub(crate) fn read_client_line(stream: &mut TcpStream) -> String {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while byte[0] != b'\n' && stream.read_exact(&mut byte).is_ok() {
+        line.push(byte[0]);
+    }
+    String::from_utf8_lossy(&line).trim_end().to_string()
+}
+
+
+/// DNS zone of stub server: (name, type, recursion desired) => (answers, additionals)
+pub(crate) type StubZone =
+    Vec<((&'static str, RecordType, bool), (Vec<RData>, Vec<RData>))>;
+
+
+/// Spawns local stub DNS server (UDP, and TCP on the same port) answering from given zone.
+/// Answers bigger than 512 bytes are truncated over UDP
+pub(crate) fn serve_dns(zone: StubZone) -> u16 {
+    let (socket, listener) = loop {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        // TCP port may be taken already by other test:
+        if let Ok(listener) = TcpListener::bind(("127.0.0.1", port)) {
+            break (socket, listener);
+        }
+    };
+    let port = socket.local_addr().unwrap().port();
+    let zone = Arc::new(zone);
+    let respond = move |zone: &StubZone, query: &[u8], udp: bool| {
+        let query = Message::from_vec(query).unwrap();
+        let question = query.queries()[0].clone();
+        let name = question.name().to_ascii().trim_end_matches('.').to_string();
+        let mut response = Message::new();
+        response
+            .set_id(query.id())
+            .set_message_type(MessageType::Response)
+            .set_recursion_desired(query.recursion_desired())
+            .add_query(question.clone());
+        match zone.iter().find(|((zone_name, record_type, recursion), _)| {
+            *zone_name == name
+                && *record_type == question.query_type()
+                && *recursion == query.recursion_desired()
+        }) {
+            Some((_, (answers, additionals))) => {
+                for answer in answers {
+                    response.add_answer(Record::from_rdata(
+                        question.name().clone(),
+                        300,
+                        answer.clone(),
+                    ));
+                }
+                for additional in additionals {
+                    response.add_additional(Record::from_rdata(
+                        Name::from_ascii("ns.krecik-dns.test.").unwrap(),
+                        300,
+                        additional.clone(),
+                    ));
+                }
+            }
+            None => {
+                response.set_response_code(ResponseCode::NXDomain);
+            }
+        }
+        let response = response.to_vec().unwrap();
+        if udp && response.len() > 512 {
+            let mut truncated = Message::from_vec(&response).unwrap();
+            truncated.take_answers();
+            truncated.set_truncated(true);
+            truncated.to_vec().unwrap()
+        } else {
+            response
+        }
+    };
+    let udp_zone = zone.clone();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 512];
+        while let Ok((length, client)) = socket.recv_from(&mut buffer) {
+            let response = respond(&udp_zone, &buffer[..length], true);
+            socket.send_to(&response, client).unwrap_or_default();
+        }
+    });
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut length = [0u8; 2];
+            stream.read_exact(&mut length).unwrap_or_default();
+            let mut query = vec![0u8; u16::from_be_bytes(length) as usize];
+            stream.read_exact(&mut query).unwrap_or_default();
+            let response = respond(&zone, &query, false);
+            stream
+                .write_all(&(response.len() as u16).to_be_bytes())
+                .unwrap_or_default();
+            stream.write_all(&response).unwrap_or_default();
+        }
+    });
+    port
+}
@@ -19,6 +19,7 @@ mod all_tests {
         checks::{check::*, domain::*, page::*, pongo::*, *},
         configuration::*,
         products::{expected::*, unexpected::*, *},
+        test_fixtures::*,
         utilities::*,
         *,
     };
@@ -127,17 +128,17 @@ mod all_tests {
         // 3
         let handler3 = easy3handle.get_ref();
         let raw_page = String::from_utf8_lossy(&handler3.0);
-        assert!(raw_page.len() == 0);
+        assert!(raw_page.is_empty());
 
-        let mut handler1after = multi.remove2(easy1handle)?;
+        let handler1after = &mut multi.remove2(easy1handle)?;
         assert!(handler1after.response_code().unwrap() == CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE);
         assert!(handler1after.download_size().unwrap() > 0f64);
 
-        let mut handler2after = multi.remove2(easy2handle)?;
+        let handler2after = &mut multi.remove2(easy2handle)?;
         assert!(handler2after.response_code().unwrap() == CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE);
         assert!(handler2after.download_size().unwrap() > 0f64);
 
-        let mut handler3after = multi.remove2(easy3handle)?;
+        let handler3after = &mut multi.remove2(easy3handle)?;
         assert!(handler3after.response_code().unwrap() == 0); // NOTE: 0 since no connection is possible to non existing server
         assert!(handler2after.download_size().unwrap() > 0f64); // even if connection failed, we sent some bytes
 
@@ -197,6 +198,13 @@ mod all_tests {
                 expects: vec![DomainExpectation::ValidExpiryPeriod(
                     CHECK_MINIMUM_DAYS_OF_TLSCERT_VALIDITY,
                 )],
+                fan_out_dns: None,
+                ca_bundle: None,
+                port: None,
+                starttls: None,
+                ocsp_responder: None,
+                rdap_server: None,
+                whois_server: None,
             }]),
             pages: Some(vec![Page {
                 url: "http://rust-lang.org/".to_string(),
@@ -204,7 +212,13 @@ mod all_tests {
                     CHECK_DEFAULT_SUCCESSFUL_HTTP_CODE,
                 )],
                 options: Some(PageOptions::default()),
+                fan_out_dns: None,
             }]),
+            dns: None,
+            email_domains: None,
+            tcp: None,
+            ssh: None,
+            databases: None,
             notifier: None,
         };
         let output = serde_json::to_string(&check).unwrap();
@@ -1,5 +1,7 @@
 use glob::glob;
+use openssl::sha::sha256;
 use retry::{delay::Fixed, retry_with_index, OperationResult};
+use serde_json::Value;
 use slack_hooked::{AttachmentBuilder, PayloadBuilder, Slack};
 use std::{
     fs::{self, OpenOptions},
@@ -47,9 +49,8 @@ pub fn warn_for_undefined_notifiers(stories: &[Story]) {
         .collect::<Vec<_>>();
     let mut undefined = stories
         .iter()
-        .cloned()
         .filter(|elem| !notifier_names.contains(&elem.notifier.clone().unwrap_or_default()))
-        .filter_map(|elem| elem.notifier)
+        .filter_map(|elem| elem.notifier.clone())
         .collect::<Vec<String>>();
     undefined.dedup();
     undefined.into_iter().for_each(|notifier| {
@@ -207,3 +208,80 @@ pub fn file_name_from_path(path: &str) -> String {
         .into_string()
         .unwrap_or_default()
 }
+
+
+/// SHA256 digest of given bytes as lowercase hex string
+#[instrument(skip(bytes))]
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    sha256(bytes).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+
+/// Select values from JSON document using JSON-pointer ("/data/status") or
+/// JSONPath subset: "$.data.status", "$.items[0].name", "$['key']", "$.items[*].id", "$.items[-1]"
+#[instrument(skip(json))]
+pub fn select_json_values<'a>(json: &'a Value, path: &str) -> Result<Vec<&'a Value>, Error> {
+    let invalid_path = |details: &str| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid JSON path: \"{path}\". {details}"),
+        )
+    };
+    if path.is_empty() || path.starts_with('/') {
+        return Ok(json.pointer(path).into_iter().collect());
+    }
+    let mut rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| invalid_path("Path has to start with \"$\" or \"/\"."))?;
+    let mut nodes = vec![json];
+    while !rest.is_empty() {
+        let (selector, remaining) = if let Some(after_dot) = rest.strip_prefix('.') {
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            (after_dot[..end].to_string(), &after_dot[end..])
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let end = after_bracket
+                .find(']')
+                .ok_or_else(|| invalid_path("Unclosed bracket."))?;
+            (after_bracket[..end].trim().to_string(), &after_bracket[end + 1..])
+        } else {
+            return Err(invalid_path("Expected \".\" or \"[\"."));
+        };
+        if selector.is_empty() {
+            return Err(invalid_path("Empty selector."));
+        }
+        let quoted_key = selector
+            .strip_prefix('\'')
+            .and_then(|key| key.strip_suffix('\''))
+            .or_else(|| {
+                selector
+                    .strip_prefix('"')
+                    .and_then(|key| key.strip_suffix('"'))
+            });
+        nodes = nodes
+            .into_iter()
+            .flat_map(|node| {
+                match (quoted_key, selector.as_str(), node) {
+                    (Some(key), ..) => node.get(key).into_iter().collect(),
+                    (None, "*", Value::Array(items)) => items.iter().collect(),
+                    (None, "*", Value::Object(fields)) => fields.values().collect(),
+                    (None, index, Value::Array(items)) if index.parse::<i64>().is_ok() => {
+                        let index = index.parse::<i64>().unwrap_or_default();
+                        let position = if index < 0 {
+                            items.len() as i64 + index
+                        } else {
+                            index
+                        };
+                        usize::try_from(position)
+                            .ok()
+                            .and_then(|position| items.get(position))
+                            .into_iter()
+                            .collect()
+                    }
+                    (None, key, _) => node.get(key).into_iter().collect::<Vec<_>>(),
+                }
+            })
+            .collect();
+        rest = remaining;
+    }
+    Ok(nodes)
+}